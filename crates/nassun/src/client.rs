@@ -1,24 +1,47 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 use async_std::sync::Arc;
 use oro_client::{OroClient, OroClientBuilder};
-use oro_common::{CorgiManifest, CorgiPackument, CorgiVersionMetadata, Packument, VersionMetadata};
+use oro_common::{
+    CorgiManifest, CorgiPackument, CorgiVersionMetadata, Packument, VersionMetadata,
+    WarningCollector,
+};
 use url::Url;
 
+pub use oro_client::Credentials;
 pub use oro_package_spec::{PackageSpec, VersionSpec};
 
 use crate::entries::Entries;
-use crate::error::Result;
+use crate::error::{NassunError, Result};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::fetch::DirFetcher;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::fetch::GitFetcher;
-use crate::fetch::{DummyFetcher, NpmFetcher, PackageFetcher};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::fetch::MirrorFetcher;
+use crate::fetch::{DummyFetcher, FetchCounters, NpmFetcher, PackageFetcher};
 use crate::package::Package;
 use crate::resolver::{PackageResolution, PackageResolver};
 use crate::tarball::Tarball;
 
+/// Default number of retries for failed requests, same as [`OroClient`]'s.
+const DEFAULT_RETRIES: u32 = 2;
+
+/// Called when a registry tarball download starts, with the package being
+/// downloaded and the size of the download, if the registry reported one.
+#[derive(Clone)]
+pub(crate) struct DownloadStartHandler(pub(crate) Arc<dyn Fn(&Package, Option<u64>) + Send + Sync>);
+
+impl std::fmt::Debug for DownloadStartHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DownloadStartHandler")
+            .finish_non_exhaustive()
+    }
+}
+
 /// Build a new Nassun instance with specified options.
 #[derive(Clone, Debug, Default)]
 pub struct NassunOpts {
@@ -26,10 +49,22 @@ pub struct NassunOpts {
     client: Option<OroClient>,
     #[cfg(not(target_arch = "wasm32"))]
     cache: Option<PathBuf>,
+    #[cfg(not(target_arch = "wasm32"))]
+    store_dir: Option<PathBuf>,
+    #[cfg(not(target_arch = "wasm32"))]
+    metadata_cache_dir: Option<PathBuf>,
     base_dir: Option<PathBuf>,
     default_tag: Option<String>,
+    allow_prerelease: bool,
     registries: HashMap<Option<String>, Url>,
     memoize_metadata: bool,
+    retries: Option<u32>,
+    on_download_start: Option<DownloadStartHandler>,
+    #[cfg(not(target_arch = "wasm32"))]
+    local_tarball_dir: Option<PathBuf>,
+    #[cfg(not(target_arch = "wasm32"))]
+    allow_network_fallback: bool,
+    warnings: WarningCollector,
 }
 
 impl NassunOpts {
@@ -48,11 +83,30 @@ impl NassunOpts {
     #[cfg(not(target_arch = "wasm32"))]
     pub fn cache(mut self, cache: impl AsRef<Path>) -> Self {
         self.cache = Some(PathBuf::from(cache.as_ref()));
-        self.client_builder = self.client_builder.cache(cache.as_ref());
         self
     }
 
-    /// Sets the default registry for requests.
+    /// Directory for the content-addressable store that package tarballs
+    /// are extracted from. Overrides the location set by
+    /// [`NassunOpts::cache`] for tarball contents only.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn store_dir(mut self, store_dir: impl AsRef<Path>) -> Self {
+        self.store_dir = Some(PathBuf::from(store_dir.as_ref()));
+        self
+    }
+
+    /// Directory for the HTTP cache, which holds packuments and other
+    /// registry metadata. Overrides the location set by
+    /// [`NassunOpts::cache`] for metadata only.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn metadata_cache_dir(mut self, metadata_cache_dir: impl AsRef<Path>) -> Self {
+        self.metadata_cache_dir = Some(PathBuf::from(metadata_cache_dir.as_ref()));
+        self
+    }
+
+    /// Sets the default registry for requests. `file://` URLs are read as
+    /// local directory mirrors, laid out as `<name>/index.json` for each
+    /// packument and `<name>/-/<file>` for its tarballs.
     pub fn registry(mut self, registry: Url) -> Self {
         self.client_builder = self.client_builder.registry(registry.clone());
         self.registries.insert(None, registry);
@@ -69,6 +123,24 @@ impl NassunOpts {
         self
     }
 
+    /// Adds a registry for each scope in `registries`, as if calling
+    /// [`NassunOpts::scope_registry`] for each of them.
+    pub fn registries(mut self, registries: HashMap<String, Url>) -> Self {
+        for (scope, registry) in registries {
+            self = self.scope_registry(scope, registry);
+        }
+        self
+    }
+
+    /// Sets credentials for several registries at once, keyed by registry
+    /// URL.
+    pub fn credentials(mut self, credentials: HashMap<Url, Credentials>) -> Self {
+        for (registry, credentials) in credentials {
+            self.client_builder = self.client_builder.credentials(registry, credentials);
+        }
+        self
+    }
+
     /// Sets basic auth credentials for a registry.
     pub fn basic_auth(
         mut self,
@@ -112,6 +184,20 @@ impl NassunOpts {
         self
     }
 
+    /// Where to record warnings, like dist-tags pointing at versions that
+    /// don't exist. Defaults to a fresh collector.
+    pub fn warnings(mut self, warnings: WarningCollector) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Whether version ranges can resolve to pre-release versions, even if
+    /// the range doesn't mention a pre-release itself. Defaults to `false`.
+    pub fn allow_prerelease(mut self, allow_prerelease: bool) -> Self {
+        self.allow_prerelease = allow_prerelease;
+        self
+    }
+
     /// Whether to memoize package metadata. This will keep any processed
     /// packuments in memory for the lifetime of this `Nassun` instance.
     /// Setting this to `true` may increase performance when fetching many
@@ -121,9 +207,52 @@ impl NassunOpts {
         self
     }
 
-    /// Number of times to retry failed requests.
+    /// Callback for when a registry tarball download starts. Along with the
+    /// package, it receives the total size of the download in bytes, taken
+    /// from the response's `Content-Length`. This is `None` when the size
+    /// isn't known up front, in which case progress can't be shown as a
+    /// percentage.
+    pub fn on_download_start(
+        mut self,
+        f: impl Fn(&Package, Option<u64>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_download_start = Some(DownloadStartHandler(Arc::new(f)));
+        self
+    }
+
+    /// Serve cached packuments younger than `max_age` without hitting the
+    /// network. Older packuments are revalidated against the registry
+    /// (using their `ETag`, if any). Requires [`NassunOpts::cache`].
+    pub fn packument_max_age(mut self, max_age: Duration) -> Self {
+        self.client_builder = self.client_builder.packument_max_age(max_age);
+        self
+    }
+
+    /// Number of times to retry failed requests. This includes tarball
+    /// downloads that get cut off partway through, which will be requested
+    /// again and picked back up where they left off.
     pub fn retries(mut self, retries: u32) -> Self {
         self.client_builder = self.client_builder.retries(retries);
+        self.retries = Some(retries);
+        self
+    }
+
+    /// Directory of pre-downloaded registry tarballs to use instead of
+    /// downloading them. Tarballs are looked up by the file name in their
+    /// URL (or `<name>-<version>.tgz`), and still have their integrity
+    /// checked. Packuments are fetched as usual.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn local_tarball_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.local_tarball_dir = Some(PathBuf::from(dir.as_ref()));
+        self
+    }
+
+    /// Whether tarballs missing from [`NassunOpts::local_tarball_dir`] can
+    /// be downloaded from the registry instead. Defaults to `false`, so
+    /// installs from a tarball directory never touch the network for them.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn allow_network_fallback(mut self, allow: bool) -> Self {
+        self.allow_network_fallback = allow;
         self
     }
 
@@ -151,15 +280,59 @@ impl NassunOpts {
         self
     }
 
+    /// Trusts an additional root certificate, in DER format, when making
+    /// requests.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_root_certificate(mut self, der: impl AsRef<[u8]>) -> Result<Self> {
+        self.client_builder = self.client_builder.add_root_certificate(der)?;
+        Ok(self)
+    }
+
+    /// Trusts every certificate in the PEM-encoded CA bundle at `path` when
+    /// making requests.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn ca_bundle(mut self, path: impl AsRef<Path>) -> Result<Self> {
+        self.client_builder = self.client_builder.ca_bundle(path)?;
+        Ok(self)
+    }
+
+    /// Skips TLS certificate validation. Only use this for testing.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.client_builder = self.client_builder.danger_accept_invalid_certs(accept);
+        self
+    }
+
     /// Build a new Nassun instance from this options object.
     pub fn build(self) -> Nassun {
+        #[allow(unused_mut)]
+        let mut client_builder = self.client_builder;
         #[cfg(not(target_arch = "wasm32"))]
-        let cache = if let Some(cache) = self.cache {
-            Arc::new(Some(cache))
+        if let Some(metadata_cache_dir) = self.metadata_cache_dir.or_else(|| self.cache.clone()) {
+            client_builder = client_builder.cache(metadata_cache_dir);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let cache = Arc::new(self.store_dir.or(self.cache));
+        let client = self.client.unwrap_or_else(|| client_builder.build());
+        #[cfg(not(target_arch = "wasm32"))]
+        let mirror_fetcher = if MirrorFetcher::any_mirrors(&self.registries) {
+            Some(Arc::new(MirrorFetcher::new(self.registries.clone())))
         } else {
-            Arc::new(None)
+            None
         };
-        let client = self.client.unwrap_or_else(|| self.client_builder.build());
+        let mut npm_fetcher = NpmFetcher::new(
+            #[allow(clippy::redundant_clone)]
+            client.clone(),
+            self.registries,
+            self.memoize_metadata,
+        );
+        npm_fetcher.on_download_start = self.on_download_start;
+        npm_fetcher.tarball_retries = self.retries.unwrap_or(DEFAULT_RETRIES);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            npm_fetcher.local_tarball_dir = self.local_tarball_dir;
+            npm_fetcher.allow_network_fallback = self.allow_network_fallback;
+        }
         Nassun {
             #[cfg(not(target_arch = "wasm32"))]
             cache,
@@ -173,13 +346,13 @@ impl NassunOpts {
                     .base_dir
                     .unwrap_or_else(|| std::env::current_dir().expect("failed to get cwd.")),
                 default_tag: self.default_tag.unwrap_or_else(|| "latest".into()),
+                allow_prerelease: self.allow_prerelease,
+                warnings: self.warnings,
             },
-            npm_fetcher: Arc::new(NpmFetcher::new(
-                #[allow(clippy::redundant_clone)]
-                client.clone(),
-                self.registries,
-                self.memoize_metadata,
-            )),
+            counters: npm_fetcher.counters.clone(),
+            npm_fetcher: Arc::new(npm_fetcher),
+            #[cfg(not(target_arch = "wasm32"))]
+            mirror_fetcher,
             #[cfg(not(target_arch = "wasm32"))]
             dir_fetcher: Arc::new(DirFetcher::new()),
             #[cfg(not(target_arch = "wasm32"))]
@@ -188,13 +361,35 @@ impl NassunOpts {
     }
 }
 
+/// Totals for the tarball downloads a [`Nassun`] instance has made so far,
+/// and for how often its caches could serve a request.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FetchStats {
+    /// Number of registry tarballs requested over the network.
+    pub tarballs_fetched: usize,
+    /// Total bytes read from those tarball downloads.
+    pub bytes_downloaded: u64,
+    /// Packument requests answered by the HTTP cache.
+    pub packument_cache_hits: usize,
+    /// Packument requests that had to go to the registry.
+    pub packument_cache_misses: usize,
+    /// Tarball extractions served out of the content cache.
+    pub tarball_cache_hits: usize,
+    /// Tarball extractions that looked in the content cache and had to
+    /// fetch the tarball instead.
+    pub tarball_cache_misses: usize,
+}
+
 /// Toplevel client for making package requests.
 #[derive(Clone)]
 pub struct Nassun {
     cache: Arc<Option<PathBuf>>,
     resolver: PackageResolver,
+    counters: Arc<FetchCounters>,
     npm_fetcher: Arc<dyn PackageFetcher>,
     #[cfg(not(target_arch = "wasm32"))]
+    mirror_fetcher: Option<Arc<MirrorFetcher>>,
+    #[cfg(not(target_arch = "wasm32"))]
     dir_fetcher: Arc<dyn PackageFetcher>,
     #[cfg(not(target_arch = "wasm32"))]
     git_fetcher: Arc<dyn PackageFetcher>,
@@ -283,6 +478,18 @@ impl Nassun {
         Self::new().resolve(spec.as_ref()).await?.entries().await
     }
 
+    /// Tarball download totals for this instance (and its clones) so far.
+    pub fn fetch_stats(&self) -> FetchStats {
+        FetchStats {
+            tarballs_fetched: self.counters.tarballs_fetched.load(Ordering::Relaxed),
+            bytes_downloaded: self.counters.bytes_downloaded.load(Ordering::Relaxed),
+            packument_cache_hits: self.counters.packument_cache_hits.load(Ordering::Relaxed),
+            packument_cache_misses: self.counters.packument_cache_misses.load(Ordering::Relaxed),
+            tarball_cache_hits: self.counters.tarball_cache_hits.load(Ordering::Relaxed),
+            tarball_cache_misses: self.counters.tarball_cache_misses.load(Ordering::Relaxed),
+        }
+    }
+
     /// Resolve a string spec (e.g. `foo@^1.2.3`, `github:foo/bar`, etc), to a
     /// [`Package`] that can be used for further operations.
     pub async fn resolve(&self, spec: impl AsRef<str>) -> Result<Package> {
@@ -293,10 +500,19 @@ impl Nassun {
     /// Resolve a spec (e.g. `foo@^1.2.3`, `github:foo/bar`, etc), to a
     /// [`Package`] that can be used for further operations.
     pub async fn resolve_spec(&self, spec: PackageSpec) -> Result<Package> {
-        let fetcher = self.pick_fetcher(&spec);
+        if let PackageSpec::Catalog { .. } = spec.target() {
+            return Err(NassunError::UnresolvedCatalog(spec));
+        }
+        let fetcher = self.pick_fetcher(&spec)?;
         let name = fetcher.name(&spec, &self.resolver.base_dir).await?;
         self.resolver
-            .resolve(name, spec, fetcher, self.cache.clone())
+            .resolve(
+                name,
+                spec,
+                fetcher,
+                self.cache.clone(),
+                self.counters.clone(),
+            )
             .await
     }
 
@@ -309,10 +525,16 @@ impl Nassun {
         name: String,
         from: PackageSpec,
         resolved: PackageResolution,
-    ) -> Package {
-        let fetcher = self.pick_fetcher(&from);
-        self.resolver
-            .resolve_from(name, from, resolved, fetcher, self.cache.clone())
+    ) -> Result<Package> {
+        let fetcher = self.pick_fetcher(&from)?;
+        Ok(self.resolver.resolve_from(
+            name,
+            from,
+            resolved,
+            fetcher,
+            self.cache.clone(),
+            self.counters.clone(),
+        ))
     }
 
     /// Creates a "resolved" package from a plain [`oro_common::Manifest`].
@@ -321,6 +543,7 @@ impl Nassun {
     pub fn dummy_from_manifest(manifest: CorgiManifest) -> Package {
         Package {
             cache: Arc::new(None),
+            counters: Arc::new(FetchCounters::default()),
             from: PackageSpec::Dir {
                 path: PathBuf::from("."),
             },
@@ -331,13 +554,22 @@ impl Nassun {
             },
             base_dir: PathBuf::from("."),
             fetcher: Arc::new(DummyFetcher(manifest)),
+            warnings: WarningCollector::new(),
         }
     }
 
-    fn pick_fetcher(&self, arg: &PackageSpec) -> Arc<dyn PackageFetcher> {
+    /// `catalog:` specs don't have a fetcher of their own. They have to be
+    /// swapped for whatever their catalog says first.
+    fn pick_fetcher(&self, arg: &PackageSpec) -> Result<Arc<dyn PackageFetcher>> {
         use PackageSpec::*;
-        match *arg {
-            Alias { ref spec, .. } => self.pick_fetcher(spec),
+        Ok(match *arg {
+            Alias { ref spec, .. } => self.pick_fetcher(spec)?,
+            #[cfg(not(target_arch = "wasm32"))]
+            Npm { ref scope, .. } => match &self.mirror_fetcher {
+                Some(mirror) if mirror.serves(scope) => mirror.clone() as Arc<dyn PackageFetcher>,
+                _ => self.npm_fetcher.clone(),
+            },
+            #[cfg(target_arch = "wasm32")]
             Npm { .. } => self.npm_fetcher.clone(),
             #[cfg(not(target_arch = "wasm32"))]
             Dir { .. } => self.dir_fetcher.clone(),
@@ -353,6 +585,83 @@ impl Nassun {
                 "Git dependencies are not enabled. (While trying to process {})",
                 arg
             ),
+            Catalog { .. } => return Err(NassunError::UnresolvedCatalog(arg.clone())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[async_std::test]
+    async fn bulk_registries_and_credentials() -> miette::Result<()> {
+        let mut a_server = mockito::Server::new();
+        let mut b_server = mockito::Server::new();
+        let a_url = Url::parse(&a_server.url()).unwrap();
+        let b_url = Url::parse(&b_server.url()).unwrap();
+        let mut mocks = Vec::new();
+        for (server, name, auth) in [
+            (&mut a_server, "@a/pkg", "Bearer a-token"),
+            // base64("user:pass")
+            (&mut b_server, "@b/pkg", "Basic dXNlcjpwYXNz"),
+        ] {
+            let mock = server
+                .mock("GET", format!("/{name}").as_str())
+                .match_header("authorization", auth)
+                .with_header("content-type", "application/json")
+                .with_body(format!(
+                    r#"{{
+                        "name": "{name}",
+                        "dist-tags": {{ "latest": "1.0.0" }},
+                        "versions": {{
+                            "1.0.0": {{ "name": "{name}", "version": "1.0.0" }}
+                        }}
+                    }}"#
+                ))
+                .expect(1)
+                .create_async()
+                .await;
+            mocks.push(mock);
+        }
+
+        let nassun = NassunOpts::new()
+            .registries(HashMap::from([
+                ("@a".to_string(), a_url.clone()),
+                ("b".to_string(), b_url.clone()),
+            ]))
+            .credentials(HashMap::from([
+                (a_url, Credentials::Token("a-token".into())),
+                (
+                    b_url,
+                    Credentials::Basic {
+                        username: "user".into(),
+                        password: Some("pass".into()),
+                    },
+                ),
+            ]))
+            .build();
+        nassun.resolve("@a/pkg@1").await?;
+        nassun.resolve("@b/pkg@1").await?;
+
+        for mock in mocks {
+            mock.assert_async().await;
         }
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_from_rejects_catalog_specs() -> miette::Result<()> {
+        let nassun = Nassun::new();
+        let res = nassun.resolve_from(
+            "a".into(),
+            "a@catalog:".parse()?,
+            PackageResolution::Dir {
+                name: "a".into(),
+                path: PathBuf::from("."),
+            },
+        );
+        assert!(matches!(res, Err(NassunError::UnresolvedCatalog(_))));
+        Ok(())
     }
 }
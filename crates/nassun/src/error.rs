@@ -22,6 +22,16 @@ pub enum NassunError {
     )]
     MissingVersion(PackageSpec, Version),
 
+    /// `catalog:` specs stand for a version defined in the root project's
+    /// manifest, so they can't be resolved by themselves.
+    #[error("Can't resolve `{0}` without the catalog it refers to.")]
+    #[diagnostic(
+        code(nassun::unresolved_catalog),
+        url(docsrs),
+        help("`catalog:` dependencies are resolved against the `catalog` and `catalogs` fields in the root package.json.")
+    )]
+    UnresolvedCatalog(PackageSpec),
+
     /// Something went wrong while trying to parse a PackageSpec.
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -58,6 +68,20 @@ pub enum NassunError {
     #[diagnostic(code(nassun::io::extract), url(docsrs))]
     ExtractIoError(#[source] std::io::Error, Option<PathBuf>, String),
 
+    /// A tarball contains two files whose paths only differ in case, which
+    /// would overwrite each other on case-insensitive filesystems (the
+    /// default on macOS and Windows). On those, only the first of them is
+    /// extracted, and this is reported as a warning.
+    #[error("Skipped extracting `{}` from tarball because it only differs in case from `{}`.", .0.display(), .1.display())]
+    #[diagnostic(
+        code(nassun::extract::case_collision),
+        url(docsrs),
+        help(
+            "This is a problem with the package itself. Consider reporting it to its maintainers."
+        )
+    )]
+    CaseCollision(PathBuf, PathBuf),
+
     /// Failed to extract a tarball to the cache. Refer to the error message
     /// for more details.
     #[cfg(not(target_arch = "wasm32"))]
@@ -69,6 +93,13 @@ pub enum NassunError {
     #[diagnostic(code(nassun::cache::extract), url(docsrs))]
     ExtractCacheError(#[source] cacache::Error, Option<PathBuf>),
 
+    /// Failed to read entries from the cache. Refer to the error message for
+    /// more details.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("Failed to read from cache.")]
+    #[diagnostic(code(nassun::cache::read), url(docsrs))]
+    CacheReadError(#[source] cacache::Error),
+
     #[cfg(not(target_arch = "wasm32"))]
     #[error("Missing file index for cache entry for {0}.")]
     #[diagnostic(code(nassun::cache::missing_index), url(docsrs))]
@@ -170,6 +201,17 @@ pub enum NassunError {
     #[diagnostic(code(nassun::cache::deserialize), url(docsrs))]
     DeserializeCacheError(String),
 
+    /// A local tarball directory was configured, but it has no tarball for
+    /// this package, and falling back to the network isn't allowed.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("No tarball for `{0}` in {}.", .1.display())]
+    #[diagnostic(
+        code(nassun::missing_local_tarball),
+        url(docsrs),
+        help("Add the missing tarball to the directory, or allow falling back to the network.")
+    )]
+    MissingLocalTarball(String, PathBuf),
+
     /// A miscellaneous, usually internal error. This is used mainly to wrap
     /// either manual InternalErrors, or those using external errors that
     /// don't implement std::error::Error.
@@ -3,7 +3,6 @@ use std::path::Path;
 
 use async_std::sync::Arc;
 use async_trait::async_trait;
-use futures::io::AsyncRead;
 use node_semver::Version;
 use oro_common::{
     CorgiManifest, CorgiPackument, CorgiVersionMetadata, Manifest as OroManifest, Packument,
@@ -144,10 +143,263 @@ impl PackageFetcher for DirFetcher {
         self.corgi_packument_from_path(&path).await
     }
 
-    async fn tarball(&self, _pkg: &Package) -> Result<Box<dyn AsyncRead + Unpin + Send + Sync>> {
-        // TODO: need to implement pack before this can be implemented :(
-        unimplemented!()
+    async fn tarball(&self, pkg: &Package) -> Result<crate::TarballStream> {
+        let path = match pkg.resolved() {
+            PackageResolution::Dir { path, .. } => path.clone(),
+            _ => panic!("There shouldn't be anything but Dirs here"),
+        };
+        let data = async_std::task::spawn_blocking(move || pack_dir(&path)).await?;
+        Ok(Box::new(futures::io::Cursor::new(data)))
+    }
+}
+
+/// Packs the contents of a local package directory into an in-memory
+/// `.tgz`, laid out the same way as registry tarballs, so it can go through
+/// regular tarball extraction. Only the files `npm pack` would include are
+/// packed: see [`PackRules`].
+fn pack_dir(dir: &Path) -> Result<Vec<u8>> {
+    fn append_dir(
+        builder: &mut tar::Builder<flate2::write::GzEncoder<Vec<u8>>>,
+        rules: &PackRules,
+        dir: &Path,
+        rel: &str,
+    ) -> Result<()> {
+        let mut entries = std::fs::read_dir(dir)
+            .and_then(|entries| entries.collect::<std::io::Result<Vec<_>>>())
+            .map_err(|err| NassunError::DirReadError(err, dir.to_path_buf()))?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let name = entry.file_name();
+            let rel = if rel.is_empty() {
+                name.to_string_lossy().into_owned()
+            } else {
+                format!("{rel}/{}", name.to_string_lossy())
+            };
+            let path = entry.path();
+            let file_type = entry
+                .file_type()
+                .map_err(|err| NassunError::DirReadError(err, path.clone()))?;
+            if file_type.is_dir() {
+                if !rules.is_ignored(&rel, true) {
+                    append_dir(builder, rules, &path, &rel)?;
+                }
+            } else if file_type.is_file() && rules.includes(&rel) {
+                builder
+                    .append_path_with_name(&path, Path::new("package").join(&rel))
+                    .map_err(|err| NassunError::DirReadError(err, path.clone()))?;
+            }
+        }
+        Ok(())
+    }
+
+    let rules = PackRules::from_dir(dir)?;
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::fast(),
+    ));
+    append_dir(&mut builder, &rules, dir, "")?;
+    builder
+        .into_inner()
+        .and_then(|gz| gz.finish())
+        .map_err(|err| NassunError::DirReadError(err, dir.to_path_buf()))
+}
+
+/// Names that are never packed, wherever they show up.
+const ALWAYS_IGNORED: [&str; 12] = [
+    ".git",
+    ".svn",
+    ".hg",
+    "CVS",
+    "node_modules",
+    ".npmrc",
+    ".DS_Store",
+    "._*",
+    ".*.swp",
+    "*.orig",
+    "npm-debug.log",
+    "package-lock.json",
+];
+
+/// Which files in a package directory get packed, following `npm pack`: if
+/// package.json has a `files` list, only the files it names are packed.
+/// Otherwise, everything is packed except what `.npmignore` (or
+/// `.gitignore`, if there's no `.npmignore`) excludes. package.json, README
+/// and license files, and the package's `main` and `bin` files are always
+/// packed.
+#[derive(Debug, Default)]
+struct PackRules {
+    files: Option<Vec<String>>,
+    /// Ignore patterns, in file order, along with whether they're negated.
+    ignore: Vec<(String, bool)>,
+    /// `main` and `bin` paths, relative to the package root.
+    entry_points: Vec<String>,
+}
+
+impl PackRules {
+    fn from_dir(dir: &Path) -> Result<Self> {
+        let manifest = std::fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+            .unwrap_or_default();
+        let files = manifest["files"].as_array().map(|files| {
+            files
+                .iter()
+                .filter_map(|file| file.as_str())
+                .map(clean_path)
+                .collect()
+        });
+        let mut entry_points = Vec::new();
+        if let Some(main) = manifest["main"].as_str() {
+            entry_points.push(clean_path(main));
+        }
+        match &manifest["bin"] {
+            serde_json::Value::String(bin) => entry_points.push(clean_path(bin)),
+            serde_json::Value::Object(bins) => {
+                entry_points.extend(bins.values().filter_map(|bin| bin.as_str()).map(clean_path))
+            }
+            _ => {}
+        }
+        let mut ignore = Vec::new();
+        if files.is_none() {
+            for ignore_file in [".npmignore", ".gitignore"] {
+                let path = dir.join(ignore_file);
+                if !path.exists() {
+                    continue;
+                }
+                let contents = std::fs::read_to_string(&path)
+                    .map_err(|err| NassunError::DirReadError(err, path.clone()))?;
+                ignore = contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| match line.strip_prefix('!') {
+                        Some(pattern) => (pattern.to_string(), true),
+                        None => (line.to_string(), false),
+                    })
+                    .collect();
+                break;
+            }
+        }
+        Ok(Self {
+            files,
+            ignore,
+            entry_points,
+        })
+    }
+
+    /// Whether the file at `rel` (a `/`-separated path relative to the
+    /// package root) should be packed.
+    fn includes(&self, rel: &str) -> bool {
+        if self.is_ignored(rel, false) {
+            return false;
+        }
+        if self.entry_points.iter().any(|entry| entry == rel) {
+            return true;
+        }
+        if !rel.contains('/') {
+            let name = rel.to_lowercase();
+            if name == "package.json"
+                || name.starts_with("readme")
+                || name.starts_with("license")
+                || name.starts_with("licence")
+            {
+                return true;
+            }
+        }
+        match &self.files {
+            // A `files` entry can name the file itself, or any directory
+            // it's in.
+            Some(files) => files.iter().any(|pattern| {
+                let mut prefix = rel;
+                loop {
+                    if glob_match(pattern, prefix) {
+                        return true;
+                    }
+                    match prefix.rsplit_once('/') {
+                        Some((parent, _)) => prefix = parent,
+                        None => return false,
+                    }
+                }
+            }),
+            None => true,
+        }
+    }
+
+    /// Whether `rel` is excluded by the ignore rules. Ignored directories
+    /// are skipped entirely.
+    fn is_ignored(&self, rel: &str, is_dir: bool) -> bool {
+        let name = rel.rsplit('/').next().unwrap_or(rel);
+        if ALWAYS_IGNORED
+            .iter()
+            .any(|pattern| glob_match_name(pattern, name))
+        {
+            return true;
+        }
+        // Like with `.gitignore`, the last matching pattern wins.
+        self.ignore
+            .iter()
+            .rev()
+            .find(|(pattern, _)| ignore_matches(pattern, rel, is_dir))
+            .map(|(_, negated)| !negated)
+            .unwrap_or(false)
+    }
+}
+
+/// `path` without any leading `./` or `/`, or trailing `/`.
+fn clean_path(path: &str) -> String {
+    path.trim_start_matches("./").trim_matches('/').to_string()
+}
+
+/// Matches a `.gitignore`-style pattern. Patterns with a `/` anywhere but at
+/// the end are matched against the whole path from the package root, and
+/// others against the file or directory name at any depth. A trailing `/`
+/// only matches directories.
+fn ignore_matches(pattern: &str, rel: &str, is_dir: bool) -> bool {
+    let (pattern, dirs_only) = match pattern.strip_suffix('/') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    if dirs_only && !is_dir {
+        return false;
+    }
+    if pattern.contains('/') {
+        glob_match(pattern.trim_start_matches('/'), rel)
+    } else {
+        glob_match_name(pattern, rel.rsplit('/').next().unwrap_or(rel))
+    }
+}
+
+/// Matches a `/`-separated glob against a `/`-separated path, where `**`
+/// matches any number of path segments.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    fn segments(pattern: &[&str], path: &[&str]) -> bool {
+        match pattern.split_first() {
+            None => path.is_empty(),
+            Some((&"**", rest)) => (0..=path.len()).any(|i| segments(rest, &path[i..])),
+            Some((first, rest)) => {
+                !path.is_empty() && glob_match_name(first, path[0]) && segments(rest, &path[1..])
+            }
+        }
+    }
+    let pattern = pattern.split('/').collect::<Vec<_>>();
+    let path = path.split('/').collect::<Vec<_>>();
+    segments(&pattern, &path)
+}
+
+/// Matches a single path segment against a glob, where `*` matches any
+/// number of characters and `?` matches exactly one.
+fn glob_match_name(pattern: &str, name: &str) -> bool {
+    fn chars(pattern: &[char], name: &[char]) -> bool {
+        match pattern.split_first() {
+            None => name.is_empty(),
+            Some(('*', rest)) => (0..=name.len()).any(|i| chars(rest, &name[i..])),
+            Some(('?', rest)) => !name.is_empty() && chars(rest, &name[1..]),
+            Some((c, rest)) => name.first() == Some(c) && chars(rest, &name[1..]),
+        }
     }
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    chars(&pattern, &name)
 }
 
 #[derive(Serialize, Deserialize)]
@@ -238,7 +490,11 @@ impl Manifest {
 #[cfg(test)]
 mod test {
     use super::*;
-    use std::{fs::File, io::Write, path::PathBuf};
+    use std::{
+        fs::File,
+        io::{Read, Write},
+        path::PathBuf,
+    };
 
     use tempfile::{tempdir, TempDir};
 
@@ -303,4 +559,136 @@ mod test {
         );
         Ok(())
     }
+
+    /// Paths in the tarball [`pack_dir`] makes out of `dir`.
+    fn packed_paths(dir: &Path) -> Result<Vec<String>> {
+        let data = pack_dir(dir)?;
+        let mut gz = flate2::read::GzDecoder::new(&data[..]);
+        let mut tar = Vec::new();
+        gz.read_to_end(&mut tar).unwrap();
+        let mut archive = tar::Archive::new(&tar[..]);
+        Ok(archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .path()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect())
+    }
+
+    #[test]
+    fn pack_dir_skips_always_ignored() -> Result<()> {
+        let dir = tempdir().unwrap();
+        // Even a `files` list naming them doesn't bring these back.
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"files": ["lib", ".npmrc", "package-lock.json"]}"#,
+        )
+        .unwrap();
+        for file in [
+            "lib/index.js",
+            "lib/index.js.orig",
+            "lib/.index.js.swp",
+            "lib/.DS_Store",
+            "lib/._index.js",
+            ".npmrc",
+            "package-lock.json",
+            "npm-debug.log",
+            ".git/HEAD",
+            ".hg/store",
+            ".svn/entries",
+            "CVS/Root",
+            "node_modules/dep/index.js",
+        ] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        assert_eq!(
+            packed_paths(dir.path())?,
+            vec!["package/lib/index.js", "package/package.json"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pack_dir_honors_files() -> Result<()> {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"main": "./index.js", "files": ["lib/", "types/*.d.ts"]}"#,
+        )
+        .unwrap();
+        for file in [
+            "index.js",
+            "README.md",
+            "LICENSE",
+            "notes.txt",
+            "lib/a.js",
+            "lib/nested/b.js",
+            "types/a.d.ts",
+            "types/a.ts",
+            "test/a.js",
+        ] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        assert_eq!(
+            packed_paths(dir.path())?,
+            vec![
+                "package/LICENSE",
+                "package/README.md",
+                "package/index.js",
+                "package/lib/a.js",
+                "package/lib/nested/b.js",
+                "package/package.json",
+                "package/types/a.d.ts",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pack_dir_honors_npmignore() -> Result<()> {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+        std::fs::write(
+            dir.path().join(".npmignore"),
+            "# comments are skipped\ntest/\n*.log\n!keep.log\n/src/internal\n",
+        )
+        .unwrap();
+        // `.gitignore` is only used when there's no `.npmignore`.
+        std::fs::write(dir.path().join(".gitignore"), "lib/\n").unwrap();
+        for file in [
+            "debug.log",
+            "keep.log",
+            "lib/a.js",
+            "src/internal/a.js",
+            "src/public/internal/a.js",
+            "test/a.js",
+            "lib/test/b.js",
+        ] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        assert_eq!(
+            packed_paths(dir.path())?,
+            vec![
+                "package/.gitignore",
+                "package/.npmignore",
+                "package/keep.log",
+                "package/lib/a.js",
+                "package/package.json",
+                "package/src/public/internal/a.js",
+            ]
+        );
+        Ok(())
+    }
 }
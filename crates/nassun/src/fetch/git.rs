@@ -93,7 +93,7 @@ impl GitFetcher {
     async fn fetch_tarball(&self, dir: &Path, tarball: &Url) -> Result<()> {
         let tarball = self.client.stream_external(tarball).await?;
         Tarball::new_unchecked(tarball)
-            .extract_from_tarball_data(dir, None, crate::ExtractMode::AutoHardlink)
+            .extract_from_tarball_data(dir, None, crate::ExtractMode::AutoHardlink, None, None)
             .await?;
         Ok(())
     }
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use async_std::sync::Arc;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use oro_common::{CorgiPackument, CorgiVersionMetadata, Packument, VersionMetadata};
+use oro_package_spec::PackageSpec;
+use serde::de::DeserializeOwned;
+use url::Url;
+
+use crate::error::{IoContext, NassunError, Result};
+use crate::fetch::PackageFetcher;
+use crate::package::Package;
+use crate::resolver::PackageResolution;
+
+/// Serves packuments and tarballs for `file://` registries: local directory
+/// mirrors laid out by package name, so no network access is needed.
+///
+/// Each package gets a directory under the mirror root, like `foo/` or
+/// `@scope/foo/`, holding its packument as `index.json` and its tarballs in
+/// a `-/` subdirectory, mirroring the registry's own URL layout. Tarballs are
+/// looked up by the file name of the URL in the packument, so packuments
+/// copied verbatim from a registry work as-is.
+#[derive(Debug)]
+pub(crate) struct MirrorFetcher {
+    registries: HashMap<Option<String>, Url>,
+    packuments: DashMap<String, Arc<Packument>>,
+    corgi_packuments: DashMap<String, Arc<CorgiPackument>>,
+}
+
+impl MirrorFetcher {
+    pub(crate) fn new(registries: HashMap<Option<String>, Url>) -> Self {
+        Self {
+            registries,
+            packuments: DashMap::new(),
+            corgi_packuments: DashMap::new(),
+        }
+    }
+
+    /// Whether any of `registries` is a directory mirror.
+    pub(crate) fn any_mirrors(registries: &HashMap<Option<String>, Url>) -> bool {
+        registries.values().any(|url| url.scheme() == "file")
+    }
+
+    /// Whether `scope`'s packages come from a directory mirror, rather than
+    /// an actual registry.
+    pub(crate) fn serves(&self, scope: &Option<String>) -> bool {
+        self.registry(scope)
+            .map(|url| url.scheme() == "file")
+            .unwrap_or(false)
+    }
+
+    fn registry(&self, scope: &Option<String>) -> Option<&Url> {
+        self.registries
+            .get(scope)
+            .or_else(|| self.registries.get(&None))
+    }
+
+    fn package_dir(&self, spec: &PackageSpec) -> Result<(String, PathBuf)> {
+        let PackageSpec::Npm { name, scope, .. } = spec.target() else {
+            unreachable!("How did a non-Npm spec get here?");
+        };
+        let url = self
+            .registry(scope)
+            .expect("only used for specs with a file:// registry");
+        let root = url
+            .to_file_path()
+            .map_err(|_| NassunError::MiscError(format!("Invalid mirror directory URL: {url}")))?;
+        Ok((name.clone(), root.join(name)))
+    }
+
+    async fn read_packument<T: DeserializeOwned>(&self, dir: &Path) -> Result<T> {
+        let path = dir.join("index.json");
+        let json = async_std::fs::read(&path).await.io_context(|| {
+            format!(
+                "Failed to read packument from mirror at {}.",
+                path.display()
+            )
+        })?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+#[async_trait]
+impl PackageFetcher for MirrorFetcher {
+    async fn name(&self, spec: &PackageSpec, _base_dir: &Path) -> Result<String> {
+        match spec {
+            PackageSpec::Npm { ref name, .. } | PackageSpec::Alias { ref name, .. } => {
+                Ok(name.clone())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    async fn metadata(&self, pkg: &Package) -> Result<VersionMetadata> {
+        let wanted = match pkg.resolved() {
+            PackageResolution::Npm { ref version, .. } => version,
+            _ => unreachable!(),
+        };
+        self.packument(pkg.from(), Path::new(""))
+            .await?
+            .versions
+            .get(wanted)
+            .cloned()
+            .ok_or_else(|| NassunError::MissingVersion(pkg.from().clone(), wanted.clone()))
+    }
+
+    async fn packument(&self, spec: &PackageSpec, _base_dir: &Path) -> Result<Arc<Packument>> {
+        let (name, dir) = self.package_dir(spec)?;
+        if let Some(packument) = self.packuments.get(&name) {
+            return Ok(packument.value().clone());
+        }
+        let packument: Arc<Packument> = Arc::new(self.read_packument(&dir).await?);
+        self.packuments.insert(name, packument.clone());
+        Ok(packument)
+    }
+
+    async fn corgi_metadata(&self, pkg: &Package) -> Result<CorgiVersionMetadata> {
+        let wanted = match pkg.resolved() {
+            PackageResolution::Npm { ref version, .. } => version,
+            _ => unreachable!(),
+        };
+        self.corgi_packument(pkg.from(), Path::new(""))
+            .await?
+            .versions
+            .get(wanted)
+            .cloned()
+            .ok_or_else(|| NassunError::MissingVersion(pkg.from().clone(), wanted.clone()))
+    }
+
+    async fn corgi_packument(
+        &self,
+        spec: &PackageSpec,
+        _base_dir: &Path,
+    ) -> Result<Arc<CorgiPackument>> {
+        let (name, dir) = self.package_dir(spec)?;
+        if let Some(packument) = self.corgi_packuments.get(&name) {
+            return Ok(packument.value().clone());
+        }
+        let packument: Arc<CorgiPackument> = Arc::new(self.read_packument(&dir).await?);
+        self.corgi_packuments.insert(name, packument.clone());
+        Ok(packument)
+    }
+
+    async fn tarball(&self, pkg: &Package) -> Result<crate::TarballStream> {
+        let (url, version) = match pkg.resolved() {
+            PackageResolution::Npm {
+                ref tarball,
+                ref version,
+                ..
+            } => (tarball, version),
+            _ => panic!("How did a non-Npm resolution get here?"),
+        };
+        let (name, dir) = self.package_dir(pkg.from())?;
+        let file_name = url
+            .path_segments()
+            .and_then(|s| s.last())
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .unwrap_or_else(|| {
+                let name = name.rsplit('/').next().unwrap_or(&name);
+                format!("{name}-{version}.tgz")
+            });
+        let path = dir.join("-").join(file_name);
+        let file = async_std::fs::File::open(&path)
+            .await
+            .io_context(|| format!("Failed to open tarball from mirror at {}.", path.display()))?;
+        Ok(Box::new(file))
+    }
+}
@@ -13,13 +13,17 @@ pub(crate) use dir::DirFetcher;
 pub(crate) use dummy::DummyFetcher;
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) use git::GitFetcher;
-pub(crate) use npm::NpmFetcher;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use mirror::MirrorFetcher;
+pub(crate) use npm::{FetchCounters, NpmFetcher};
 
 #[cfg(not(target_arch = "wasm32"))]
 mod dir;
 mod dummy;
 #[cfg(not(target_arch = "wasm32"))]
 mod git;
+#[cfg(not(target_arch = "wasm32"))]
+mod mirror;
 mod npm;
 
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
@@ -1,18 +1,59 @@
 use std::collections::HashMap;
 use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
 
 use async_std::sync::Arc;
 use async_trait::async_trait;
 use dashmap::DashMap;
+use futures::AsyncRead;
 use oro_client::{self, OroClient};
 use oro_common::{CorgiPackument, CorgiVersionMetadata, Packument, VersionMetadata};
 use oro_package_spec::PackageSpec;
 use url::Url;
 
+use crate::client::DownloadStartHandler;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::IoContext;
 use crate::error::{NassunError, Result};
 use crate::fetch::PackageFetcher;
 use crate::package::Package;
 use crate::resolver::PackageResolution;
+use crate::TarballStream;
+
+/// Running totals of the tarball downloads an [`NpmFetcher`] has made, and
+/// of how often the packument and tarball caches could serve a request.
+#[derive(Debug, Default)]
+pub(crate) struct FetchCounters {
+    pub(crate) tarballs_fetched: AtomicUsize,
+    pub(crate) bytes_downloaded: AtomicU64,
+    pub(crate) packument_cache_hits: AtomicUsize,
+    pub(crate) packument_cache_misses: AtomicUsize,
+    pub(crate) tarball_cache_hits: AtomicUsize,
+    pub(crate) tarball_cache_misses: AtomicUsize,
+}
+
+impl FetchCounters {
+    pub(crate) fn record_packument(&self, hit: bool) {
+        if hit {
+            self.packument_cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.packument_cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) fn record_tarball(&self, hit: bool) {
+        if hit {
+            self.tarball_cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.tarball_cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct NpmFetcher {
@@ -21,6 +62,13 @@ pub(crate) struct NpmFetcher {
     cache_packuments: bool,
     packuments: DashMap<String, Arc<Packument>>,
     corgi_packuments: DashMap<String, Arc<CorgiPackument>>,
+    pub(crate) counters: Arc<FetchCounters>,
+    pub(crate) on_download_start: Option<DownloadStartHandler>,
+    pub(crate) tarball_retries: u32,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) local_tarball_dir: Option<PathBuf>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) allow_network_fallback: bool,
 }
 
 impl NpmFetcher {
@@ -35,6 +83,13 @@ impl NpmFetcher {
             packuments: DashMap::new(),
             corgi_packuments: DashMap::new(),
             cache_packuments,
+            counters: Arc::new(FetchCounters::default()),
+            on_download_start: None,
+            tarball_retries: 0,
+            #[cfg(not(target_arch = "wasm32"))]
+            local_tarball_dir: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            allow_network_fallback: false,
         }
     }
 }
@@ -49,6 +104,81 @@ impl NpmFetcher {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+impl NpmFetcher {
+    /// Looks for `pkg`'s tarball in `dir`, either under the name `npm pack`
+    /// would give it, or under the file name from its tarball URL. The
+    /// latter drops the scope, so `@scope/foo` and `foo` can both end up
+    /// wanting `foo-1.0.0.tgz`. When we know the package's integrity,
+    /// candidates that don't match it are skipped rather than used.
+    async fn local_tarball(
+        &self,
+        dir: &Path,
+        pkg: &Package,
+        url: &Url,
+    ) -> Result<Option<async_std::fs::File>> {
+        let mut candidates = Vec::new();
+        if let PackageResolution::Npm { name, version, .. } = pkg.resolved() {
+            let name = name.strip_prefix('@').unwrap_or(name).replace('/', "-");
+            candidates.push(format!("{name}-{version}.tgz"));
+        }
+        if let Some(file_name) = url.path_segments().and_then(|s| s.last()) {
+            if !file_name.is_empty() && !candidates.iter().any(|c| c == file_name) {
+                candidates.push(file_name.to_string());
+            }
+        }
+        for candidate in candidates {
+            let path = dir.join(candidate);
+            let mut file = match async_std::fs::File::open(&path).await {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(e).io_context(|| {
+                        format!("Failed to open local tarball at {}.", path.display())
+                    })
+                }
+            };
+            if let Some(integrity) = pkg.resolved().integrity() {
+                if !Self::local_tarball_matches(&mut file, integrity)
+                    .await
+                    .io_context(|| format!("Failed to read local tarball at {}.", path.display()))?
+                {
+                    tracing::debug!(
+                        "Skipping local tarball {} for {}: integrity does not match",
+                        path.display(),
+                        pkg.name()
+                    );
+                    continue;
+                }
+            }
+            tracing::trace!("Using local tarball {} for {}", path.display(), pkg.name());
+            return Ok(Some(file));
+        }
+        Ok(None)
+    }
+
+    /// Checks `file` against `integrity`, leaving it rewound so it can be
+    /// read again from the start.
+    async fn local_tarball_matches(
+        file: &mut async_std::fs::File,
+        integrity: &ssri::Integrity,
+    ) -> std::io::Result<bool> {
+        use futures::{AsyncReadExt, AsyncSeekExt};
+
+        let mut checker = ssri::IntegrityChecker::new(integrity.clone());
+        let mut buf = [0u8; 1024 * 8];
+        loop {
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            checker.input(&buf[..n]);
+        }
+        file.seek(std::io::SeekFrom::Start(0)).await?;
+        Ok(checker.result().is_ok())
+    }
+}
+
 impl NpmFetcher {
     fn _name<'a>(&'a self, spec: &'a PackageSpec) -> &'a str {
         match spec {
@@ -110,7 +240,9 @@ impl PackageFetcher for NpmFetcher {
                 }
             }
             let client = self.client.with_registry(self.pick_registry(scope));
-            let packument = Arc::new(client.corgi_packument(&name).await?);
+            let (packument, cache_hit) = client.corgi_packument_with_cache_hit(&name).await?;
+            self.counters.record_packument(cache_hit);
+            let packument = Arc::new(packument);
             if self.cache_packuments {
                 self.corgi_packuments
                     .insert(name.clone(), packument.clone());
@@ -141,7 +273,9 @@ impl PackageFetcher for NpmFetcher {
                 }
             }
             let client = self.client.with_registry(self.pick_registry(scope));
-            let packument = Arc::new(client.packument(&name).await?);
+            let (packument, cache_hit) = client.packument_with_cache_hit(&name).await?;
+            self.counters.record_packument(cache_hit);
+            let packument = Arc::new(packument);
             if self.cache_packuments {
                 self.packuments.insert(name.clone(), packument.clone());
             }
@@ -156,7 +290,160 @@ impl PackageFetcher for NpmFetcher {
             PackageResolution::Npm { ref tarball, .. } => tarball,
             _ => panic!("How did a non-Npm resolution get here?"),
         };
-        Ok(self.client.stream_external(url).await?)
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(dir) = &self.local_tarball_dir {
+            if let Some(file) = self.local_tarball(dir, pkg, url).await? {
+                return Ok(Box::new(file));
+            }
+            if !self.allow_network_fallback {
+                return Err(NassunError::MissingLocalTarball(
+                    pkg.resolved().to_string(),
+                    dir.clone(),
+                ));
+            }
+        }
+        let (stream, length) = self.client.stream_external_sized(url).await?;
+        if let Some(on_download_start) = &self.on_download_start {
+            (on_download_start.0)(pkg, length);
+        }
+        self.counters
+            .tarballs_fetched
+            .fetch_add(1, Ordering::Relaxed);
+        #[cfg(not(target_arch = "wasm32"))]
+        let stream = Box::new(ResumingStream {
+            client: self.client.clone(),
+            url: url.clone(),
+            inner: Some(stream),
+            reconnect: None,
+            read: 0,
+            skip: 0,
+            retries_left: self.tarball_retries,
+        });
+        Ok(Box::new(CountingStream {
+            inner: stream,
+            counters: self.counters.clone(),
+        }))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type Reconnect = std::sync::Mutex<
+    futures::future::BoxFuture<
+        'static,
+        std::result::Result<TarballStream, oro_client::OroClientError>,
+    >,
+>;
+
+/// Requests a tarball again when its download gets cut off, and picks back up
+/// where it left off by skipping over the bytes that were already read.
+/// Nothing gets written to the cache until the whole tarball is downloaded,
+/// so there's no partial data to clean up.
+#[cfg(not(target_arch = "wasm32"))]
+struct ResumingStream {
+    client: OroClient,
+    url: Url,
+    inner: Option<TarballStream>,
+    // Only ever accessed through `&mut`, so the lock is never contended. It's
+    // just there to make this `Sync`, like `TarballStream` needs to be.
+    reconnect: Option<Reconnect>,
+    read: u64,
+    skip: u64,
+    retries_left: u32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AsyncRead for ResumingStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        loop {
+            if let Some(reconnect) = self.reconnect.as_mut() {
+                let reconnect = reconnect.get_mut().expect("lock is never poisoned");
+                let result = futures::ready!(futures::FutureExt::poll_unpin(reconnect, cx));
+                self.reconnect = None;
+                match result {
+                    Ok(stream) => {
+                        self.inner = Some(stream);
+                        self.skip = self.read;
+                    }
+                    Err(e) => {
+                        return Poll::Ready(Err(std::io::Error::new(std::io::ErrorKind::Other, e)))
+                    }
+                }
+            }
+            let inner = self
+                .inner
+                .as_mut()
+                .expect("stream is only missing while reconnecting");
+            match futures::ready!(Pin::new(inner).poll_read(cx, buf)) {
+                Ok(0) if self.skip > 0 => {
+                    return Poll::Ready(Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        format!("tarball at {} got shorter when requested again", self.url),
+                    )))
+                }
+                Ok(n) => {
+                    let skipped = std::cmp::min(n as u64, self.skip) as usize;
+                    self.skip -= skipped as u64;
+                    if skipped == n && n > 0 {
+                        continue;
+                    }
+                    buf.copy_within(skipped..n, 0);
+                    self.read += (n - skipped) as u64;
+                    return Poll::Ready(Ok(n - skipped));
+                }
+                Err(e) if is_dropped_connection(&e) && self.retries_left > 0 => {
+                    self.retries_left -= 1;
+                    tracing::debug!(
+                        "tarball download from {} was cut off after {} bytes ({e}). Retrying.",
+                        self.url,
+                        self.read
+                    );
+                    let client = self.client.clone();
+                    let url = self.url.clone();
+                    self.inner = None;
+                    self.reconnect = Some(std::sync::Mutex::new(Box::pin(async move {
+                        client.stream_external(&url).await
+                    })));
+                }
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+}
+
+/// Whether `err` means the connection went away partway through a download,
+/// as opposed to something that requesting it again wouldn't fix.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_dropped_connection(err: &std::io::Error) -> bool {
+    use std::io::ErrorKind::*;
+    matches!(
+        err.kind(),
+        ConnectionReset | ConnectionAborted | BrokenPipe | UnexpectedEof | TimedOut
+    )
+}
+
+/// Tallies up bytes as they're read off a tarball download.
+struct CountingStream {
+    inner: TarballStream,
+    counters: Arc<FetchCounters>,
+}
+
+impl AsyncRead for CountingStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(n)) = &poll {
+            self.counters
+                .bytes_downloaded
+                .fetch_add(*n as u64, Ordering::Relaxed);
+        }
+        poll
     }
 }
 
@@ -292,4 +579,268 @@ mod test {
         );
         Ok(())
     }
+
+    #[async_std::test]
+    async fn packument_within_max_age_served_from_cache() -> miette::Result<()> {
+        let mut mock_server = mockito::Server::new();
+        let mock = mock_server
+            .mock("GET", "/oro-test-example")
+            .with_header("content-type", "application/json")
+            .with_header("etag", "\"abc123\"")
+            .with_body(r#"{"name": "oro-test-example", "versions": {}, "dist-tags": {}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let cache_path = tempdir().unwrap();
+        let mut registries = HashMap::new();
+        registries.insert(None, Url::parse(mock_server.url().as_ref()).unwrap());
+        let spec = PackageSpec::Npm {
+            scope: None,
+            name: "oro-test-example".to_string(),
+            requested: None,
+        };
+
+        // Separate fetchers, so the second one can't lean on the in-memory
+        // packument cache, only the on-disk one.
+        for cached in [false, true] {
+            let client = oro_client::OroClient::builder()
+                .registry(Url::parse(mock_server.url().as_ref()).unwrap())
+                .cache(cache_path.path())
+                .packument_max_age(std::time::Duration::from_secs(60 * 60))
+                .build();
+            let fetcher = NpmFetcher::new(client, registries.clone(), false);
+            fetcher.packument(&spec, cache_path.path()).await?;
+            let hits = fetcher
+                .counters
+                .packument_cache_hits
+                .load(Ordering::Relaxed);
+            let misses = fetcher
+                .counters
+                .packument_cache_misses
+                .load(Ordering::Relaxed);
+            assert_eq!((hits, misses), if cached { (1, 0) } else { (0, 1) });
+        }
+
+        mock.assert_async().await;
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn tarball_cache_lookups_are_counted() -> miette::Result<()> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::none(),
+        ));
+        let manifest = r#"{"name": "oro-test-example"}"#;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "package/package.json", manifest.as_bytes())
+            .unwrap();
+        let tarball = builder.into_inner().unwrap().finish().unwrap();
+        let integrity = ssri::Integrity::from(&tarball);
+
+        let mut mock_server = mockito::Server::new();
+        let tarball_url = format!(
+            "{}/oro-test-example/-/oro-test-example-1.0.0.tgz",
+            mock_server.url()
+        );
+        mock_server
+            .mock("GET", "/oro-test-example")
+            .with_header("content-type", "application/json")
+            .with_body(format!(
+                r#"{{
+                    "name": "oro-test-example",
+                    "dist-tags": {{ "latest": "1.0.0" }},
+                    "versions": {{
+                        "1.0.0": {{
+                            "name": "oro-test-example",
+                            "version": "1.0.0",
+                            "dist": {{ "tarball": "{tarball_url}", "integrity": "{integrity}" }}
+                        }}
+                    }}
+                }}"#
+            ))
+            .create_async()
+            .await;
+        let tarball_mock = mock_server
+            .mock("GET", "/oro-test-example/-/oro-test-example-1.0.0.tgz")
+            .with_body(tarball)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let cache = tempdir().unwrap();
+        let nassun = crate::NassunOpts::new()
+            .registry(Url::parse(mock_server.url().as_ref()).unwrap())
+            .cache(cache.path())
+            .build();
+        let pkg = nassun.resolve("oro-test-example@1.0.0").await?;
+        for _ in 0..2 {
+            let dir = tempdir().unwrap();
+            pkg.extract_to_dir(dir.path(), crate::ExtractMode::Copy)
+                .await?;
+        }
+
+        tarball_mock.assert_async().await;
+        let stats = nassun.fetch_stats();
+        assert_eq!(stats.tarball_cache_misses, 1);
+        assert_eq!(stats.tarball_cache_hits, 1);
+        assert_eq!(stats.tarballs_fetched, 1);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn download_start_reports_content_length() -> miette::Result<()> {
+        let mut mock_server = mockito::Server::new();
+        let tarball_url = format!(
+            "{}/oro-test-example/-/oro-test-example-1.0.0.tgz",
+            mock_server.url()
+        );
+        mock_server
+            .mock("GET", "/oro-test-example")
+            .with_header("content-type", "application/json")
+            .with_body(format!(
+                r#"{{
+                    "name": "oro-test-example",
+                    "dist-tags": {{ "latest": "1.0.0" }},
+                    "versions": {{
+                        "1.0.0": {{
+                            "name": "oro-test-example",
+                            "version": "1.0.0",
+                            "dist": {{ "tarball": "{tarball_url}" }}
+                        }}
+                    }}
+                }}"#
+            ))
+            .create_async()
+            .await;
+        mock_server
+            .mock("GET", "/oro-test-example/-/oro-test-example-1.0.0.tgz")
+            // mockito sends a Content-Length for fixed-size bodies.
+            .with_body([0u8; 42])
+            .create_async()
+            .await;
+
+        let totals = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let nassun = {
+            let totals = totals.clone();
+            crate::NassunOpts::new()
+                .registry(Url::parse(mock_server.url().as_ref()).unwrap())
+                .on_download_start(move |pkg, total| {
+                    totals.lock().unwrap().push((pkg.name().to_string(), total));
+                })
+                .build()
+        };
+        let pkg = nassun.resolve("oro-test-example@1.0.0").await?;
+        pkg.tarball_unchecked().await?;
+
+        assert_eq!(
+            *totals.lock().unwrap(),
+            vec![("oro-test-example".to_string(), Some(42))]
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn tarball_download_resumes_after_reset() -> miette::Result<()> {
+        use std::io::{Read, Write};
+
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::none(),
+        ));
+        // Big enough that the first response can get cut off partway.
+        let contents = "x".repeat(64 * 1024);
+        for (path, contents) in [
+            ("package/package.json", r#"{"name": "oro-test-example"}"#),
+            ("package/index.js", contents.as_str()),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        let tarball = builder.into_inner().unwrap().finish().unwrap();
+        let integrity = ssri::Integrity::from(&tarball);
+
+        // A bare-bones server that hangs up halfway through the first
+        // response, and serves the whole thing after that.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let tarball_url = format!(
+            "http://{}/oro-test-example.tgz",
+            listener.local_addr().unwrap()
+        );
+        let requests = Arc::new(AtomicUsize::new(0));
+        {
+            let tarball = tarball.clone();
+            let requests = requests.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut request = Vec::new();
+                    let mut byte = [0u8];
+                    while !request.ends_with(b"\r\n\r\n") {
+                        stream.read_exact(&mut byte).unwrap();
+                        request.push(byte[0]);
+                    }
+                    let first = requests.fetch_add(1, Ordering::SeqCst) == 0;
+                    let body = if first {
+                        &tarball[..tarball.len() / 2]
+                    } else {
+                        &tarball[..]
+                    };
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        tarball.len()
+                    );
+                    let _ = stream.write_all(body);
+                }
+            });
+        }
+
+        let mut mock_server = mockito::Server::new();
+        mock_server
+            .mock("GET", "/oro-test-example")
+            .with_header("content-type", "application/json")
+            .with_body(format!(
+                r#"{{
+                    "name": "oro-test-example",
+                    "dist-tags": {{ "latest": "1.0.0" }},
+                    "versions": {{
+                        "1.0.0": {{
+                            "name": "oro-test-example",
+                            "version": "1.0.0",
+                            "dist": {{ "tarball": "{tarball_url}", "integrity": "{integrity}" }}
+                        }}
+                    }}
+                }}"#
+            ))
+            .create_async()
+            .await;
+
+        let nassun = crate::NassunOpts::new()
+            .registry(Url::parse(mock_server.url().as_ref()).unwrap())
+            .retries(1)
+            .build();
+        let pkg = nassun.resolve("oro-test-example@1.0.0").await?;
+        let dir = tempdir().unwrap();
+        pkg.extract_to_dir(dir.path(), crate::ExtractMode::Copy)
+            .await?;
+
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("index.js")).unwrap(),
+            contents
+        );
+        assert_eq!(nassun.fetch_stats().bytes_downloaded, tarball.len() as u64);
+        Ok(())
+    }
 }
@@ -13,6 +13,8 @@ pub mod error;
 pub mod fetch;
 pub mod package;
 pub mod resolver;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod store;
 pub mod tarball;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
@@ -27,6 +29,8 @@ pub use error::NassunError;
 pub use package::*;
 pub use resolver::*;
 #[cfg(not(target_arch = "wasm32"))]
+pub use store::*;
+#[cfg(not(target_arch = "wasm32"))]
 pub use tarball::*;
 #[cfg(target_arch = "wasm32")]
 pub use wasm::*;
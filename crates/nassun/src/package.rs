@@ -4,7 +4,11 @@ use std::path::Path;
 use std::path::PathBuf;
 
 use async_std::sync::Arc;
-use oro_common::{CorgiPackument, CorgiVersionMetadata, Packument, VersionMetadata};
+#[cfg(not(target_arch = "wasm32"))]
+use oro_common::WarningKind;
+use oro_common::{
+    CorgiPackument, CorgiVersionMetadata, Packument, VersionMetadata, WarningCollector,
+};
 use oro_package_spec::PackageSpec;
 use ssri::Integrity;
 
@@ -12,7 +16,7 @@ use crate::entries::Entries;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::error::NassunError;
 use crate::error::Result;
-use crate::fetch::PackageFetcher;
+use crate::fetch::{FetchCounters, PackageFetcher};
 use crate::resolver::PackageResolution;
 use crate::tarball::Tarball;
 #[cfg(not(target_arch = "wasm32"))]
@@ -44,6 +48,22 @@ impl ExtractMode {
     }
 }
 
+/// Decides which files in a package get written out during extraction. It
+/// receives each file's path, relative to the package root, and returns
+/// `false` for files that should be skipped. `package.json` is always
+/// extracted, regardless of what the filter says.
+///
+/// Filtered files are still written to the cache, so the same cached package
+/// can be extracted with a different filter later.
+#[cfg(not(target_arch = "wasm32"))]
+pub type ExtractFilter = Arc<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// Called with the number of bytes written each time a chunk of package
+/// data is written out to the cache (or to the target directory, when there
+/// is no cache) during extraction.
+#[cfg(not(target_arch = "wasm32"))]
+pub type ProgressCallback = Arc<dyn Fn(usize) + Send + Sync>;
+
 /// A resolved package. A concrete version has been determined from its
 /// PackageSpec by the version resolver.
 #[derive(Clone)]
@@ -55,6 +75,10 @@ pub struct Package {
     pub(crate) base_dir: PathBuf,
     #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
     pub(crate) cache: Arc<Option<PathBuf>>,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) counters: Arc<FetchCounters>,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) warnings: WarningCollector,
 }
 
 impl Package {
@@ -116,7 +140,7 @@ impl Package {
         if let Some(integrity) = self.resolved.integrity() {
             Ok(Tarball::new(data, integrity.clone()))
         } else {
-            self.tarball_unchecked().await
+            Ok(Tarball::new_unchecked(data))
         }
     }
 
@@ -160,12 +184,56 @@ impl Package {
         extract_mode: ExtractMode,
     ) -> Result<Integrity> {
         async fn inner(me: &Package, dir: &Path, extract_mode: ExtractMode) -> Result<Integrity> {
-            me.extract_to_dir_inner(dir, me.resolved.integrity(), extract_mode)
+            me.extract_to_dir_inner(dir, me.resolved.integrity(), extract_mode, None, None)
                 .await
         }
         inner(self, dir.as_ref(), extract_mode).await
     }
 
+    /// Extract tarball to a directory, optionally caching its contents, and
+    /// skipping any files rejected by `filter`. The tarball stream will have
+    /// its integrity validated based on package metadata. See
+    /// [`Package::tarball`] for more information.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn extract_to_dir_filtered(
+        &self,
+        dir: impl AsRef<Path>,
+        extract_mode: ExtractMode,
+        filter: ExtractFilter,
+    ) -> Result<Integrity> {
+        self.extract_to_dir_inner(
+            dir.as_ref(),
+            self.resolved.integrity(),
+            extract_mode,
+            Some(&filter),
+            None,
+        )
+        .await
+    }
+
+    /// Extract tarball to a directory, optionally caching its contents and
+    /// skipping any files rejected by `filter`, and calling `progress` with
+    /// byte counts as file contents are written. The tarball stream will have
+    /// its integrity validated based on package metadata. See
+    /// [`Package::tarball`] for more information.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn extract_to_dir_with_progress(
+        &self,
+        dir: impl AsRef<Path>,
+        extract_mode: ExtractMode,
+        filter: Option<ExtractFilter>,
+        progress: ProgressCallback,
+    ) -> Result<Integrity> {
+        self.extract_to_dir_inner(
+            dir.as_ref(),
+            self.resolved.integrity(),
+            extract_mode,
+            filter.as_ref(),
+            Some(&progress),
+        )
+        .await
+    }
+
     /// Extract tarball to a directory, optionally caching its contents. The
     /// tarball stream will NOT have its integrity validated. See
     /// [`Package::tarball_unchecked`] for more information.
@@ -176,7 +244,8 @@ impl Package {
         extract_mode: ExtractMode,
     ) -> Result<Integrity> {
         async fn inner(me: &Package, dir: &Path, extract_mode: ExtractMode) -> Result<Integrity> {
-            me.extract_to_dir_inner(dir, None, extract_mode).await
+            me.extract_to_dir_inner(dir, None, extract_mode, None, None)
+                .await
         }
         inner(self, dir.as_ref(), extract_mode).await
     }
@@ -197,7 +266,8 @@ impl Package {
             sri: Integrity,
             extract_mode: ExtractMode,
         ) -> Result<Integrity> {
-            me.extract_to_dir_inner(dir, Some(&sri), extract_mode).await
+            me.extract_to_dir_inner(dir, Some(&sri), extract_mode, None, None)
+                .await
         }
         inner(self, dir.as_ref(), sri, extract_mode).await
     }
@@ -208,55 +278,142 @@ impl Package {
         dir: &Path,
         integrity: Option<&Integrity>,
         extract_mode: ExtractMode,
+        filter: Option<&ExtractFilter>,
+        progress: Option<&ProgressCallback>,
     ) -> Result<Integrity> {
         if let Some(sri) = integrity {
             if let Some(cache) = self.cache.as_deref() {
-                if let Some(entry) = cacache::index::find(cache, &crate::tarball::tarball_key(sri))
-                    .map_err(|e| NassunError::ExtractCacheError(e, None))?
-                {
+                if let Some((cached_sri, entry)) = crate::tarball::find_tarball_index(cache, sri)? {
                     let sri = sri.clone();
+                    let sha512 = crate::tarball::recorded_sha512(&entry);
+                    let index = entry.raw_metadata.clone();
                     match self
-                        .extract_from_cache(dir, cache, entry, extract_mode)
+                        .extract_from_cache(dir, cache, entry, extract_mode, filter)
                         .await
                     {
-                        Ok(_) => return Ok(sri),
+                        Ok(_) => {
+                            self.counters.record_tarball(true);
+                            if let Some(sha512) = sha512 {
+                                return Ok(sri.concat(sha512));
+                            }
+                            if let (ssri::Algorithm::Sha1, Some(index)) =
+                                (sri.pick_algorithm(), index)
+                            {
+                                // This tarball was cached with only a sha1
+                                // to go by, so we fetch it once more to get
+                                // its sha512 recorded, too.
+                                match self
+                                    .record_sha512(cache, sri.clone(), &cached_sri, &index)
+                                    .await
+                                {
+                                    Ok(sha512) => return Ok(sri.concat(sha512)),
+                                    Err(e) => tracing::debug!(
+                                        "Failed to record a sha512 for {:?}: {e}",
+                                        self.resolved()
+                                    ),
+                                }
+                            }
+                            return Ok(sri);
+                        }
                         // If extracting from the cache failed for some reason
                         // (bad data, etc), then go ahead and do a network
                         // extract.
                         Err(e) => {
                             tracing::warn!("extracting package {:?} from cache failed, possily due to cache corruption: {e}", self.resolved());
-                            if let Some(entry) =
-                                cacache::index::find(cache, &crate::tarball::tarball_key(&sri))
-                                    .map_err(|e| NassunError::ExtractCacheError(e, None))?
+                            if let Some(entry) = cacache::index::find(
+                                cache,
+                                &crate::tarball::tarball_key(&cached_sri),
+                            )
+                            .map_err(|e| NassunError::ExtractCacheError(e, None))?
                             {
                                 tracing::debug!("removing corrupted cache entry.");
-                                clean_from_cache(cache, &sri, entry)?;
+                                clean_from_cache(cache, &cached_sri, entry)?;
                             }
+                            self.counters.record_tarball(false);
                             return self
                                 .tarball_checked(sri)
                                 .await?
-                                .extract_from_tarball_data(dir, self.cache.as_deref(), extract_mode)
-                                .await;
+                                .extract_from_tarball_data(
+                                    dir,
+                                    self.cache.as_deref(),
+                                    extract_mode,
+                                    filter,
+                                    progress,
+                                )
+                                .await
+                                .map(|extracted| self.report_case_collisions(extracted));
                         }
                     }
                 } else {
+                    self.counters.record_tarball(false);
                     return self
                         .tarball_checked(sri.clone())
                         .await?
-                        .extract_from_tarball_data(dir, self.cache.as_deref(), extract_mode)
-                        .await;
+                        .extract_from_tarball_data(
+                            dir,
+                            self.cache.as_deref(),
+                            extract_mode,
+                            filter,
+                            progress,
+                        )
+                        .await
+                        .map(|extracted| self.report_case_collisions(extracted));
                 }
             }
             self.tarball_checked(sri.clone())
                 .await?
-                .extract_from_tarball_data(dir, self.cache.as_deref(), extract_mode)
+                .extract_from_tarball_data(
+                    dir,
+                    self.cache.as_deref(),
+                    extract_mode,
+                    filter,
+                    progress,
+                )
                 .await
+                .map(|extracted| self.report_case_collisions(extracted))
         } else {
             self.tarball_unchecked()
                 .await?
-                .extract_from_tarball_data(dir, self.cache.as_deref(), extract_mode)
+                .extract_from_tarball_data(
+                    dir,
+                    self.cache.as_deref(),
+                    extract_mode,
+                    filter,
+                    progress,
+                )
                 .await
+                .map(|extracted| self.report_case_collisions(extracted))
+        }
+    }
+
+    /// Passes along the files that were left out of an extraction because
+    /// they only differ in case from another one, as warnings.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn report_case_collisions(
+        &self,
+        (integrity, collisions): (Integrity, Vec<NassunError>),
+    ) -> Integrity {
+        for err in collisions {
+            self.warnings
+                .push(WarningKind::CaseCollision, format!("{}: {err}", self.name));
         }
+        integrity
+    }
+
+    /// Fetches this package's tarball, checked against `sri`, to record its
+    /// sha512 alongside the cache entry that was found under `cached_sri`.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn record_sha512(
+        &self,
+        cache: &Path,
+        sri: Integrity,
+        cached_sri: &Integrity,
+        index: &[u8],
+    ) -> Result<Integrity> {
+        self.tarball_checked(sri)
+            .await?
+            .record_sha512(cache, cached_sri, index)
+            .await
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -266,10 +423,12 @@ impl Package {
         cache: &Path,
         entry: cacache::Metadata,
         mut extract_mode: ExtractMode,
+        filter: Option<&ExtractFilter>,
     ) -> Result<()> {
         let dir = PathBuf::from(dir);
         let cache = PathBuf::from(cache);
         let name = self.name().to_owned();
+        let filter = filter.cloned();
         async_std::task::spawn_blocking(move || {
             let created = dashmap::DashSet::new();
             let index = rkyv::check_archived_root::<TarballIndex>(
@@ -288,6 +447,9 @@ impl Package {
                 extract_mode
             };
             for (archived_path, (sri, mode)) in index.files.iter() {
+                if !crate::tarball::should_extract(filter.as_ref(), Path::new(&archived_path[..])) {
+                    continue;
+                }
                 let sri: Integrity = sri.parse()?;
                 let path = dir.join(&archived_path[..]);
                 let parent = PathBuf::from(path.parent().expect("this will always have a parent"));
@@ -1,13 +1,13 @@
 use std::{fmt::Display, path::PathBuf, sync::Arc};
 
 use node_semver::{Range as SemVerRange, Version as SemVerVersion};
-use oro_common::CorgiPackument;
+use oro_common::{CorgiPackument, WarningCollector, WarningKind};
 use oro_package_spec::{GitInfo, PackageSpec, VersionSpec};
 use ssri::Integrity;
 use url::Url;
 
 use crate::error::{IoContext, NassunError};
-use crate::fetch::PackageFetcher;
+use crate::fetch::{FetchCounters, PackageFetcher};
 use crate::package::Package;
 
 /// Represents a fully-resolved, specific version of a package as it would be fetched.
@@ -98,7 +98,9 @@ impl PackageResolution {
 #[derive(Clone, Debug)]
 pub(crate) struct PackageResolver {
     pub(crate) default_tag: String,
+    pub(crate) allow_prerelease: bool,
     pub(crate) base_dir: PathBuf,
+    pub(crate) warnings: WarningCollector,
 }
 
 impl PackageResolver {
@@ -109,6 +111,7 @@ impl PackageResolver {
         resolved: PackageResolution,
         fetcher: Arc<dyn PackageFetcher>,
         cache: Arc<Option<PathBuf>>,
+        counters: Arc<FetchCounters>,
     ) -> Package {
         Package {
             name,
@@ -116,7 +119,9 @@ impl PackageResolver {
             resolved,
             fetcher,
             cache,
+            counters,
             base_dir: self.base_dir.clone(),
+            warnings: self.warnings.clone(),
         }
     }
 
@@ -126,6 +131,7 @@ impl PackageResolver {
         wanted: PackageSpec,
         fetcher: Arc<dyn PackageFetcher>,
         cache: Arc<Option<PathBuf>>,
+        counters: Arc<FetchCounters>,
     ) -> Result<Package, NassunError> {
         let packument = fetcher.corgi_packument(&wanted, &self.base_dir).await?;
         let resolved = self.get_resolution(&name, &wanted, &packument)?;
@@ -136,6 +142,8 @@ impl PackageResolver {
             fetcher,
             base_dir: self.base_dir.clone(),
             cache,
+            counters,
+            warnings: self.warnings.clone(),
         })
     }
 
@@ -194,6 +202,38 @@ impl PackageResolver {
 
         let tag_version = packument.tags.get(&self.default_tag);
 
+        // The default dist-tag can be left pointing at a version that's since
+        // been unpublished. Rather than failing, fall back to the highest
+        // available version.
+        if let Some(missing) = tag_version.filter(|v| !packument.versions.contains_key(*v)) {
+            let wants_tag = match spec {
+                Npm {
+                    requested: None, ..
+                } => true,
+                Npm {
+                    requested: Some(VersionSpec::Tag(tag)),
+                    ..
+                } => tag == &self.default_tag,
+                _ => false,
+            };
+            if wants_tag {
+                target = max_satisfying(
+                    packument.versions.keys(),
+                    &SemVerRange::any(),
+                    self.allow_prerelease,
+                );
+                if let Some(fallback) = target {
+                    self.warnings.push(
+                        WarningKind::DistTag,
+                        format!(
+                            "The `{}` dist-tag for {name} points to {missing}, which doesn't exist. Using {fallback} instead.",
+                            self.default_tag
+                        ),
+                    );
+                }
+            }
+        }
+
         if target.is_none()
             && tag_version.is_some()
             && packument
@@ -220,7 +260,7 @@ impl PackageResolver {
                 ..
             } = spec
             {
-                target = max_satisfying(packument.versions.keys(), range);
+                target = max_satisfying(packument.versions.keys(), range, self.allow_prerelease);
             }
         }
 
@@ -277,11 +317,47 @@ impl PackageResolver {
     }
 }
 
+/// Highest version satisfying `range`. Pre-releases are only considered if
+/// `allow_prerelease` is set, or if `range` itself mentions one.
 fn max_satisfying<'a>(
     versions: impl Iterator<Item = &'a SemVerVersion>,
     range: &SemVerRange,
+    allow_prerelease: bool,
 ) -> Option<&'a SemVerVersion> {
-    versions.filter(|v| range.satisfies(v)).max()
+    versions
+        .filter(|v| {
+            if allow_prerelease || !v.is_prerelease() {
+                range.satisfies(v)
+            } else {
+                range_allows_prerelease(range, v)
+            }
+        })
+        .max()
+}
+
+/// Whether `range` accepts the pre-release `version` the way npm would:
+/// one of the comparators in the `||` alternative it satisfies has to
+/// mention a pre-release of the same `major.minor.patch`. So `^1.2.3-beta.1`
+/// accepts `1.2.3-beta.2`, but not `1.6.0-rc.1`.
+fn range_allows_prerelease(range: &SemVerRange, version: &SemVerVersion) -> bool {
+    let same_tuple = |comparator: &SemVerVersion| {
+        comparator.is_prerelease()
+            && (comparator.major, comparator.minor, comparator.patch)
+                == (version.major, version.minor, version.patch)
+    };
+    range.to_string().split("||").any(|set| {
+        let Ok(set_range) = SemVerRange::parse(set.trim()) else {
+            return false;
+        };
+        set_range.satisfies(version)
+            && set
+                .split_whitespace()
+                .filter_map(|comparator| {
+                    SemVerVersion::parse(comparator.trim_start_matches(['<', '>', '=', '~', '^']))
+                        .ok()
+                })
+                .any(|comparator| same_tuple(&comparator))
+    })
 }
 
 #[cfg(test)]
@@ -289,6 +365,128 @@ mod tests {
     use super::*;
     use test_case::test_case;
 
+    #[test]
+    fn dangling_default_tag() -> Result<(), NassunError> {
+        let packument: CorgiPackument = serde_json::from_value(serde_json::json!({
+            "versions": {
+                "1.0.0": {
+                    "name": "oro-test-package",
+                    "version": "1.0.0",
+                    "dist": { "tarball": "https://example.com/oro-test-package-1.0.0.tgz" }
+                },
+                "1.2.0": {
+                    "name": "oro-test-package",
+                    "version": "1.2.0",
+                    "dist": { "tarball": "https://example.com/oro-test-package-1.2.0.tgz" }
+                },
+                "2.0.0-beta.1": {
+                    "name": "oro-test-package",
+                    "version": "2.0.0-beta.1",
+                    "dist": { "tarball": "https://example.com/oro-test-package-2.0.0-beta.1.tgz" }
+                }
+            },
+            "dist-tags": { "latest": "1.3.0", "next": "1.4.0" }
+        }))
+        .unwrap();
+        let packument = Arc::new(packument);
+        let resolver = PackageResolver {
+            default_tag: "latest".into(),
+            allow_prerelease: false,
+            base_dir: PathBuf::from("."),
+            warnings: WarningCollector::new(),
+        };
+
+        for spec in ["oro-test-package", "oro-test-package@latest"] {
+            let resolved =
+                resolver.get_resolution("oro-test-package", &spec.parse()?, &packument)?;
+            assert_eq!(
+                resolved.npm_version(),
+                Some("1.2.0".parse().unwrap()),
+                "{spec} should fall back to the highest available version"
+            );
+        }
+
+        // Other dangling tags were asked for explicitly, so they still fail.
+        assert!(matches!(
+            resolver.get_resolution(
+                "oro-test-package",
+                &"oro-test-package@next".parse()?,
+                &packument
+            ),
+            Err(NassunError::NoVersion { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn prerelease_selection() -> Result<(), NassunError> {
+        let packument: CorgiPackument = serde_json::from_value(serde_json::json!({
+            "versions": {
+                "1.0.0": {
+                    "name": "oro-test-package",
+                    "version": "1.0.0",
+                    "dist": { "tarball": "https://example.com/oro-test-package-1.0.0.tgz" }
+                },
+                "2.0.0-beta.1": {
+                    "name": "oro-test-package",
+                    "version": "2.0.0-beta.1",
+                    "dist": { "tarball": "https://example.com/oro-test-package-2.0.0-beta.1.tgz" }
+                }
+            },
+            "dist-tags": { "latest": "1.0.0" }
+        }))
+        .unwrap();
+        let packument = Arc::new(packument);
+        let resolver = PackageResolver {
+            default_tag: "latest".into(),
+            allow_prerelease: false,
+            base_dir: PathBuf::from("."),
+            warnings: WarningCollector::new(),
+        };
+        let version = |spec: &str| {
+            resolver
+                .get_resolution("oro-test-package", &spec.parse().unwrap(), &packument)
+                .map(|resolved| resolved.npm_version().unwrap().to_string())
+        };
+
+        assert_eq!(version("oro-test-package@>=1.0.0")?, "1.0.0");
+        assert_eq!(version("oro-test-package@*")?, "1.0.0");
+        assert_eq!(version("oro-test-package@<3.0.0")?, "1.0.0");
+        assert_eq!(
+            version("oro-test-package@>=2.0.0-beta.0")?,
+            "2.0.0-beta.1",
+            "ranges that mention a pre-release can still select one"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn prerelease_only_for_same_version_tuple() {
+        let versions = [
+            "1.2.3-beta.1",
+            "1.2.3-beta.2",
+            "1.5.0",
+            "1.6.0-rc.1",
+            "2.0.0-rc.1",
+        ];
+        let versions = versions
+            .iter()
+            .map(|v| SemVerVersion::parse(v).unwrap())
+            .collect::<Vec<_>>();
+        let max = |range: &str| {
+            max_satisfying(versions.iter(), &SemVerRange::parse(range).unwrap(), false)
+                .map(|v| v.to_string())
+        };
+
+        assert_eq!(max("^1.2.3-beta.1").as_deref(), Some("1.5.0"));
+        assert_eq!(max("~1.2.3-beta.1").as_deref(), Some("1.2.3-beta.2"));
+        assert_eq!(
+            max("^1.2.3-beta.1 || >=2.0.0-rc.0").as_deref(),
+            Some("2.0.0-rc.1"),
+            "pre-releases mentioned in any alternative count"
+        );
+    }
+
     #[test_case("1.4.2", "1.4.2", true; "exact version match")]
     #[test_case("1.4.2", "~1.4.0", true; "same minor version")]
     #[test_case("1.4.2", "~1", true; "tilde same major version")]
@@ -0,0 +1,176 @@
+//! Inspection of Nassun's on-disk package store (its content cache).
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use serde::Serialize;
+use ssri::Integrity;
+
+use crate::error::{IoContext, NassunError, Result};
+use crate::tarball::{TarballIndex, TARBALL_KEY_PREFIX};
+
+/// Disk usage and entry counts for a package store.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StoreStatus {
+    /// Total size, in bytes, of every content blob in the store.
+    pub content_size: u64,
+    /// Number of live index entries.
+    pub entries: usize,
+    /// Number of distinct content integrities referenced from the index,
+    /// including individual files referenced by cached package tarballs.
+    pub unique_integrities: usize,
+    /// Number of content blobs that nothing in the index refers to.
+    pub orphaned_blobs: usize,
+    /// Total size, in bytes, of orphaned content blobs.
+    pub orphaned_size: u64,
+}
+
+impl StoreStatus {
+    /// Computes the status of the store at `cache` by walking its index and
+    /// content directories. A missing cache is reported as empty.
+    pub fn compute(cache: impl AsRef<Path>) -> Result<Self> {
+        let cache = cache.as_ref();
+        let mut status = Self::default();
+        if !cache.exists() {
+            return Ok(status);
+        }
+
+        let mut referenced = HashSet::new();
+        for entry in cacache::list_sync(cache) {
+            let entry = entry.map_err(NassunError::CacheReadError)?;
+            status.entries += 1;
+            if entry.key.starts_with(TARBALL_KEY_PREFIX) {
+                // Package entries only point at placeholder content. The
+                // actual files live in the tarball index.
+                let Some(raw) = entry.raw_metadata.as_ref() else {
+                    continue;
+                };
+                let index = rkyv::check_archived_root::<TarballIndex>(raw)
+                    .map_err(|e| NassunError::DeserializeCacheError(e.to_string()))?;
+                for (sri, _) in index.files.values() {
+                    referenced.insert(content_id(&sri.parse()?));
+                }
+            } else {
+                referenced.insert(content_id(&entry.integrity));
+            }
+        }
+        status.unique_integrities = referenced.len();
+
+        let content_dir = cache.join("content-v2");
+        if content_dir.exists() {
+            for algo_dir in read_dir(&content_dir)? {
+                let algo = algo_dir.file_name().to_string_lossy().to_string();
+                walk_content(&algo_dir.path(), &mut |hex, size| {
+                    status.content_size += size;
+                    if !referenced.contains(&(algo.clone(), hex)) {
+                        status.orphaned_blobs += 1;
+                        status.orphaned_size += size;
+                    }
+                })?;
+            }
+        }
+
+        Ok(status)
+    }
+}
+
+/// Identifies a content blob the same way its path in the content directory
+/// does: by algorithm and hex digest.
+fn content_id(sri: &Integrity) -> (String, String) {
+    let (algo, hex) = sri.to_hex();
+    (algo.to_string(), hex)
+}
+
+fn read_dir(dir: &Path) -> Result<Vec<std::fs::DirEntry>> {
+    std::fs::read_dir(dir)
+        .io_context(|| format!("Failed to read cache directory at {}.", dir.display()))?
+        .map(|entry| {
+            entry.io_context(|| format!("Failed to read cache directory at {}.", dir.display()))
+        })
+        .collect()
+}
+
+/// Calls `f` with the hex digest and size of every blob under an algorithm
+/// directory. Digests are split across nested directories, so the full digest
+/// is the concatenation of the path segments.
+fn walk_content(dir: &Path, f: &mut impl FnMut(String, u64)) -> Result<()> {
+    fn inner(dir: &Path, prefix: &str, f: &mut impl FnMut(String, u64)) -> Result<()> {
+        for entry in read_dir(dir)? {
+            let path = entry.path();
+            let hex = format!("{prefix}{}", entry.file_name().to_string_lossy());
+            let meta = entry
+                .metadata()
+                .io_context(|| format!("Failed to read metadata for {}.", path.display()))?;
+            if meta.is_dir() {
+                inner(&path, &hex, f)?;
+            } else {
+                f(hex, meta.len());
+            }
+        }
+        Ok(())
+    }
+    inner(dir, "", f)
+}
+
+#[cfg(test)]
+mod tests {
+    use cacache::WriteOpts;
+
+    use super::*;
+
+    #[test]
+    fn counts_entries_and_orphans() -> miette::Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path();
+
+        // Two keys pointing at the same content, plus one with its own.
+        let shared = b"shared content".to_vec();
+        let unique = b"some other, longer content".to_vec();
+        cacache::write_sync(cache, "a", &shared).map_err(NassunError::CacheReadError)?;
+        cacache::write_sync(cache, "b", &shared).map_err(NassunError::CacheReadError)?;
+        cacache::write_sync(cache, "c", &unique).map_err(NassunError::CacheReadError)?;
+
+        // Content with no index entry pointing at it.
+        let orphan = b"nobody loves me".to_vec();
+        cacache::write_hash_sync(cache, &orphan).map_err(NassunError::CacheReadError)?;
+
+        // A cached package, whose files are only referenced by its index.
+        let file = b"module.exports = 1".to_vec();
+        let file_sri =
+            cacache::write_hash_sync(cache, &file).map_err(NassunError::CacheReadError)?;
+        let mut index = TarballIndex::default();
+        index
+            .files
+            .insert("index.js".into(), (file_sri.to_string(), 0o644));
+        cacache::index::insert(
+            cache,
+            &format!("{TARBALL_KEY_PREFIX}sha512-deadbeef"),
+            WriteOpts::new()
+                .integrity("xxh3-deadbeef".parse().unwrap())
+                .raw_metadata(rkyv::util::to_bytes::<_, 1024>(&index).unwrap().into_vec()),
+        )
+        .map_err(NassunError::CacheReadError)?;
+
+        assert_eq!(
+            StoreStatus::compute(cache)?,
+            StoreStatus {
+                content_size: (shared.len() + unique.len() + orphan.len() + file.len()) as u64,
+                entries: 4,
+                unique_integrities: 3,
+                orphaned_blobs: 1,
+                orphaned_size: orphan.len() as u64,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn missing_cache_is_empty() -> miette::Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            StoreStatus::compute(dir.path().join("nope"))?,
+            StoreStatus::default()
+        );
+        Ok(())
+    }
+}
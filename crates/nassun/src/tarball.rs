@@ -5,7 +5,7 @@ use std::io::Write;
 #[cfg(not(target_arch = "wasm32"))]
 use std::io::{Read, Seek};
 #[cfg(not(target_arch = "wasm32"))]
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 #[cfg(not(target_arch = "wasm32"))]
@@ -34,7 +34,7 @@ use crate::entries::{Entries, Entry};
 use crate::error::IoContext;
 use crate::error::{NassunError, Result};
 #[cfg(not(target_arch = "wasm32"))]
-use crate::package::ExtractMode;
+use crate::package::{ExtractFilter, ExtractMode, ProgressCallback};
 use crate::TarballStream;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -76,13 +76,24 @@ impl Tarball {
         dir: &Path,
         cache: Option<&Path>,
         extract_mode: ExtractMode,
-    ) -> Result<Integrity> {
+        filter: Option<&ExtractFilter>,
+        progress: Option<&ProgressCallback>,
+    ) -> Result<(Integrity, Vec<NassunError>)> {
         let integrity = self.integrity.take();
         let temp = self.into_temp().await?;
         let dir = PathBuf::from(dir);
         let cache = cache.map(PathBuf::from);
+        let filter = filter.cloned();
+        let progress = progress.cloned();
         async_std::task::spawn_blocking(move || {
-            temp.extract_to_dir(&dir, integrity, cache.as_deref(), extract_mode)
+            temp.extract_to_dir(
+                &dir,
+                integrity,
+                cache.as_deref(),
+                extract_mode,
+                filter.as_ref(),
+                progress.as_ref(),
+            )
         })
         .await
     }
@@ -138,6 +149,34 @@ impl Tarball {
         Ok(TempTarball::Memory(std::io::Cursor::new(vec)))
     }
 
+    /// Reads through this tarball to compute its sha512, and records the
+    /// cached `index` under it. The entry under `cached_sri` gets the sha512
+    /// noted on it, so this only needs doing once.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) async fn record_sha512(
+        self,
+        cache: &Path,
+        cached_sri: &Integrity,
+        index: &[u8],
+    ) -> Result<Integrity> {
+        let mut reader = BufReader::new(self);
+        let mut hasher = IntegrityOpts::new().algorithm(ssri::Algorithm::Sha512);
+        let mut buf = [0u8; 1024 * 8];
+        loop {
+            let n = reader.read(&mut buf).await.map_err(|e| {
+                NassunError::ExtractIoError(e, None, "reading from tarball stream".into())
+            })?;
+            if n == 0 {
+                break;
+            }
+            hasher.input(&buf[..n]);
+        }
+        let sha512 = hasher.result();
+        insert_tarball_index(cache, &sha512, index, None)?;
+        insert_tarball_index(cache, cached_sri, index, Some(&sha512))?;
+        Ok(sha512)
+    }
+
     /// A `Stream` of extracted entries from this tarball.
     pub(crate) fn entries(self) -> Result<Entries> {
         let decoder = GzipDecoder::new(BufReader::new(self));
@@ -203,189 +242,334 @@ pub(crate) enum TempTarball {
 
 #[cfg(not(target_arch = "wasm32"))]
 impl TempTarball {
+    /// Extracts this tarball into `dir`, returning its integrity along with
+    /// the [`NassunError::CaseCollision`]s for any files that were skipped
+    /// because they'd have clobbered each other.
     pub(crate) fn extract_to_dir(
         mut self,
         dir: &Path,
         tarball_integrity: Option<Integrity>,
         cache: Option<&Path>,
-        mut extract_mode: ExtractMode,
-    ) -> Result<Integrity> {
-        let mut build_mani: Option<BuildManifest> = None;
-        let mut tarball_index = TarballIndex::default();
-        let mut drain_buf = [0u8; 1024 * 8];
-        let created = dashmap::DashSet::new();
-
-        self.rewind().io_context(|| {
-            format!(
-                "Failed to seek to the beginning of temp tarball fd while extracting to dir: {}",
-                dir.display()
-            )
-        })?;
+        extract_mode: ExtractMode,
+        filter: Option<&ExtractFilter>,
+        progress: Option<&ProgressCallback>,
+    ) -> Result<(Integrity, Vec<NassunError>)> {
+        let mut case_insensitive = None;
+        // Top-level directory the package's files are nested under, if they
+        // all share one. We guess from the first entry, and only go over the
+        // tarball again if the rest of it turns out not to agree.
+        let mut prefix = self.first_entry_prefix()?;
 
-        let mut reader = std::io::BufReader::new(self);
-        let mut integrity = IntegrityOpts::new().algorithm(ssri::Algorithm::Xxh3);
-        let mut tee_reader = io_tee::TeeReader::new(&mut reader, &mut integrity);
-        let gz = std::io::BufReader::new(flate2::read::GzDecoder::new(&mut tee_reader));
-        let mut ar = tar::Archive::new(gz);
-        let files = ar.entries().map_err(|e| {
-            NassunError::ExtractIoError(e, None, "getting tarball entries iterator".into())
-        })?;
+        // Tarballs we can only check against a sha1 get a sha512 computed as
+        // they're read, so their cache entry can also be found by the
+        // stronger hash from then on.
+        let upgrade = tarball_integrity
+            .as_ref()
+            .map(|sri| sri.pick_algorithm() == ssri::Algorithm::Sha1)
+            .unwrap_or(false);
 
-        mkdirp(dir, &created)?;
+        loop {
+            let mut extract_mode = extract_mode;
+            let mut build_mani: Option<BuildManifest> = None;
+            let mut tarball_index = TarballIndex::default();
+            let mut case_paths = CasePaths::default();
+            let mut collisions = Vec::new();
+            let mut drain_buf = [0u8; 1024 * 8];
+            let created = dashmap::DashSet::new();
+            let mut written = Vec::new();
+            let mut seen = Vec::new();
 
-        for file in files {
-            let mut file = file.map_err(|e| {
-                NassunError::ExtractIoError(
-                    e,
-                    Some(PathBuf::from(dir)),
-                    "reading entry from tarball".into(),
+            self.rewind().io_context(|| {
+                format!(
+                    "Failed to seek to the beginning of temp tarball fd while extracting to dir: {}",
+                    dir.display()
                 )
             })?;
-            let header = file.header();
-            let mode = header.mode().unwrap_or(0o644) | 0o600;
-            let entry_path = header.path().map_err(|e| {
-                NassunError::ExtractIoError(e, None, "reading path from entry header.".into())
+
+            let mut reader = std::io::BufReader::new(&mut self);
+            let mut integrity = IntegrityOpts::new().algorithm(ssri::Algorithm::Xxh3);
+            if upgrade {
+                integrity = integrity.algorithm(ssri::Algorithm::Sha512);
+            }
+            let mut tee_reader = io_tee::TeeReader::new(&mut reader, &mut integrity);
+            let gz = std::io::BufReader::new(flate2::read::GzDecoder::new(&mut tee_reader));
+            let mut ar = tar::Archive::new(gz);
+            let files = ar.entries().map_err(|e| {
+                NassunError::ExtractIoError(e, None, "getting tarball entries iterator".into())
             })?;
-            let entry_subpath = strip_one(&entry_path)
-                .unwrap_or_else(|| entry_path.as_ref())
-                .to_path_buf();
-            let path = dir.join(&entry_subpath);
-            if let tar::EntryType::Regular = header.entry_type() {
-                let parent = path.parent().unwrap();
-                mkdirp(parent, &created)?;
-
-                if let Some(cache) = cache {
-                    let mut writer = WriteOpts::new()
-                        .algorithm(cacache::Algorithm::Xxh3)
-                        .open_hash_sync(cache)
-                        .map_err(|e| NassunError::ExtractCacheError(e, Some(path.clone())))?;
-
-                    std::io::copy(&mut file, &mut writer).map_err(|e| {
-                        NassunError::ExtractIoError(
-                            e,
-                            Some(path.clone()),
-                            "copying to cacache + node_modules".into(),
-                        )
-                    })?;
 
-                    let sri = writer
-                        .commit()
-                        .map_err(|e| NassunError::ExtractCacheError(e, Some(path.clone())))?;
+            mkdirp(dir, &created)?;
 
-                    extract_from_cache(cache, &sri, &path, extract_mode, mode)?;
+            for file in files {
+                let mut file = file.map_err(|e| {
+                    NassunError::ExtractIoError(
+                        e,
+                        Some(PathBuf::from(dir)),
+                        "reading entry from tarball".into(),
+                    )
+                })?;
+                let header = file.header();
+                let mode = header.mode().unwrap_or(0o644) | 0o600;
+                let entry_path = header.path().map_err(|e| {
+                    NassunError::ExtractIoError(e, None, "reading path from entry header.".into())
+                })?;
+                if header.entry_type().is_file() || header.entry_type().is_dir() {
+                    seen.push((entry_path.to_path_buf(), header.entry_type().is_dir()));
+                }
+                let entry_subpath = match prefix.as_deref() {
+                    Some(prefix) => strip_prefix(&entry_path, prefix),
+                    None => entry_path.components().collect(),
+                };
+                let path = dir.join(&entry_subpath);
+                let keep = should_extract(filter, &entry_subpath);
+                if let tar::EntryType::Regular = header.entry_type() {
+                    if let Err(err) = case_paths.insert(&entry_subpath) {
+                        // Both files can be extracted just fine where case
+                        // matters, so we only check once there's a collision.
+                        if *case_insensitive.get_or_insert_with(|| is_case_insensitive(dir)) {
+                            collisions.push(err);
+                            std::io::copy(&mut file, &mut std::io::sink()).map_err(|e| {
+                                NassunError::ExtractIoError(
+                                    e,
+                                    None,
+                                    "draining file from tarball.".into(),
+                                )
+                            })?;
+                            continue;
+                        }
+                    }
 
-                    let entry_subpath = entry_subpath.to_string_lossy().to_string();
+                    if keep {
+                        let parent = path.parent().unwrap();
+                        mkdirp(parent, &created)?;
+                    }
 
-                    // We check whether the package has any install scripts.
-                    // If so, we need to re-extract all previous files as full
-                    // copies and mark the package as having scripts in it.
-                    if entry_subpath == "package.json" {
-                        let manifest = BuildManifest::from_path(&path).io_context(|| {
-                            format!(
-                                "Failed to read BuildManifest from path at {}.",
-                                path.display()
+                    if let Some(cache) = cache {
+                        let mut writer = ProgressWriter::new(
+                            WriteOpts::new()
+                                .algorithm(cacache::Algorithm::Xxh3)
+                                .open_hash_sync(cache)
+                                .map_err(|e| {
+                                    NassunError::ExtractCacheError(e, Some(path.clone()))
+                                })?,
+                            progress.cloned(),
+                        );
+
+                        std::io::copy(&mut file, &mut writer).map_err(|e| {
+                            NassunError::ExtractIoError(
+                                e,
+                                Some(path.clone()),
+                                "copying to cacache + node_modules".into(),
                             )
                         })?;
-                        if ["preinstall", "install", "postinstall"]
-                            .iter()
-                            .any(|s| manifest.scripts.contains_key(*s))
-                        {
-                            tarball_index.should_copy = true;
-                            if !extract_mode.is_copy() {
-                                extract_mode = ExtractMode::Auto;
-                                for (entry, (sri, mode)) in &tarball_index.files {
-                                    let path = dir.join(entry);
-                                    std::fs::remove_file(&path).io_context(|| format!("Failed to remove target file while extracting a new version, at {}.", path.display()))?;
-                                    let sri = sri.parse()?;
-                                    extract_from_cache(cache, &sri, &path, extract_mode, *mode)?;
+
+                        let sri = writer
+                            .into_inner()
+                            .commit()
+                            .map_err(|e| NassunError::ExtractCacheError(e, Some(path.clone())))?;
+
+                        if keep {
+                            extract_from_cache(cache, &sri, &path, extract_mode, mode)?;
+                            written.push(path.clone());
+                        }
+
+                        let entry_subpath = entry_subpath.to_string_lossy().to_string();
+
+                        // We check whether the package has any install scripts.
+                        // If so, we need to re-extract all previous files as full
+                        // copies and mark the package as having scripts in it.
+                        if entry_subpath == "package.json" {
+                            let manifest = BuildManifest::from_path(&path).io_context(|| {
+                                format!(
+                                    "Failed to read BuildManifest from path at {}.",
+                                    path.display()
+                                )
+                            })?;
+                            if ["preinstall", "install", "postinstall"]
+                                .iter()
+                                .any(|s| manifest.scripts.contains_key(*s))
+                            {
+                                tarball_index.should_copy = true;
+                                if !extract_mode.is_copy() {
+                                    extract_mode = ExtractMode::Auto;
+                                    for (entry, (sri, mode)) in &tarball_index.files {
+                                        if !should_extract(filter, Path::new(entry)) {
+                                            continue;
+                                        }
+                                        let path = dir.join(entry);
+                                        std::fs::remove_file(&path).io_context(|| format!("Failed to remove target file while extracting a new version, at {}.", path.display()))?;
+                                        let sri = sri.parse()?;
+                                        extract_from_cache(
+                                            cache,
+                                            &sri,
+                                            &path,
+                                            extract_mode,
+                                            *mode,
+                                        )?;
+                                    }
                                 }
                             }
+                            build_mani = Some(manifest);
                         }
-                        build_mani = Some(manifest);
-                    }
-                    tarball_index
-                        .files
-                        .insert(entry_subpath, (sri.to_string(), mode));
-                } else {
-                    let mut open_opts = std::fs::OpenOptions::new();
-                    open_opts.write(true).create_new(true);
+                        tarball_index
+                            .files
+                            .insert(entry_subpath, (sri.to_string(), mode));
+                    } else if keep {
+                        let mut open_opts = std::fs::OpenOptions::new();
+                        open_opts.write(true).create_new(true);
 
-                    #[cfg(unix)]
-                    {
-                        use std::os::unix::fs::OpenOptionsExt;
-                        open_opts.mode(mode);
-                    }
+                        #[cfg(unix)]
+                        {
+                            use std::os::unix::fs::OpenOptionsExt;
+                            open_opts.mode(mode);
+                        }
 
-                    let mut writer = open_opts
-                        .open(&path)
-                        .map_err(|e| {
+                        let mut writer = open_opts
+                            .open(&path)
+                            .map_err(|e| {
+                                NassunError::ExtractIoError(
+                                    e,
+                                    Some(path.clone()),
+                                    "Opening destination file inside node_modules.".into(),
+                                )
+                            })
+                            .map(std::io::BufWriter::new)
+                            .map(|w| ProgressWriter::new(w, progress.cloned()))?;
+                        std::io::copy(&mut file, &mut writer).map_err(|e| {
                             NassunError::ExtractIoError(
                                 e,
                                 Some(path.clone()),
-                                "Opening destination file inside node_modules.".into(),
+                                "Copying file to node_modules destination.".into(),
                             )
-                        })
-                        .map(std::io::BufWriter::new)?;
-                    std::io::copy(&mut file, &mut writer).map_err(|e| {
-                        NassunError::ExtractIoError(
-                            e,
-                            Some(path.clone()),
-                            "Copying file to node_modules destination.".into(),
-                        )
-                    })?;
+                        })?;
+                        written.push(path.clone());
+                    }
+                } else {
+                    loop {
+                        let n = file.read(&mut drain_buf).map_err(|e| {
+                            NassunError::ExtractIoError(
+                                e,
+                                None,
+                                "draining file from tarball.".into(),
+                            )
+                        })?;
+                        if n == 0 {
+                            break;
+                        }
+                    }
                 }
-            } else {
-                loop {
-                    let n = file.read(&mut drain_buf).map_err(|e| {
-                        NassunError::ExtractIoError(e, None, "draining file from tarball.".into())
-                    })?;
-                    if n == 0 {
-                        break;
+            }
+
+            let actual_prefix =
+                tarball_prefix(seen.iter().map(|(path, is_dir)| (path.as_path(), *is_dir)));
+            if actual_prefix != prefix {
+                // The first entry didn't tell the whole story, so we undo what
+                // we extracted and start over now that we know better.
+                remove_extracted(dir, &written, &created)?;
+                prefix = actual_prefix;
+                continue;
+            }
+
+            if let Some(BuildManifest { bin, .. }) = &build_mani {
+                for binpath in bin.values() {
+                    tarball_index
+                        .bin_paths
+                        .push(binpath.to_string_lossy().to_string());
+                    #[cfg(unix)]
+                    {
+                        if should_extract(filter, binpath) {
+                            set_bin_mode(&dir.join(binpath))?;
+                        }
                     }
                 }
             }
-        }
 
-        if let Some(BuildManifest { bin, .. }) = &build_mani {
-            for binpath in bin.values() {
-                tarball_index
-                    .bin_paths
-                    .push(binpath.to_string_lossy().to_string());
-                #[cfg(unix)]
-                set_bin_mode(&dir.join(binpath))?;
+            // Drain the rest of the tarball to make sure we have its full
+            // contents (there can be trailing data);
+            loop {
+                let n = tee_reader.read(&mut drain_buf).map_err(|e| {
+                    NassunError::ExtractIoError(
+                        e,
+                        None,
+                        "flushing out the rest of the tarball".into(),
+                    )
+                })?;
+                if n == 0 {
+                    break;
+                }
             }
-        }
 
-        // Drain the rest of the tarball to make sure we have its full
-        // contents (there can be trailing data);
-        loop {
-            let n = tee_reader.read(&mut drain_buf).map_err(|e| {
-                NassunError::ExtractIoError(e, None, "flushing out the rest of the tarball".into())
-            })?;
-            if n == 0 {
-                break;
+            let computed = integrity.result();
+            let (integrity, upgraded) = match tarball_integrity {
+                Some(sri) if upgrade => {
+                    let sha512 = Integrity {
+                        hashes: computed
+                            .hashes
+                            .into_iter()
+                            .filter(|hash| hash.algorithm == ssri::Algorithm::Sha512)
+                            .collect(),
+                    };
+                    (sri, Some(sha512))
+                }
+                Some(sri) => (sri, None),
+                None => (computed, None),
+            };
+
+            if let Some(cache) = cache {
+                let index = rkyv::util::to_bytes::<_, 1024>(&tarball_index)
+                    .map_err(|e| NassunError::SerializeCacheError(format!("{e}")))?
+                    .into_vec();
+                insert_tarball_index(cache, &integrity, &index, upgraded.as_ref())?;
+                if let Some(sha512) = &upgraded {
+                    insert_tarball_index(cache, sha512, &index, None)?;
+                }
             }
-        }
 
-        let integrity = tarball_integrity.unwrap_or_else(|| integrity.result());
-
-        if let Some(cache) = cache {
-            cacache::index::insert(
-                cache,
-                &tarball_key(&integrity),
-                WriteOpts::new()
-                    // This is just so the index entry is loadable.
-                    .integrity("xxh3-deadbeef".parse().unwrap())
-                    .raw_metadata(
-                        rkyv::util::to_bytes::<_, 1024>(&tarball_index)
-                            .map_err(|e| NassunError::SerializeCacheError(format!("{e}")))?
-                            .into_vec(),
-                    ),
-            )
-            .map_err(|e| NassunError::ExtractCacheError(e, None))?;
+            let integrity = match upgraded {
+                Some(sha512) => integrity.concat(sha512),
+                None => integrity,
+            };
+            return Ok((integrity, collisions));
         }
+    }
+}
 
-        Ok(integrity)
+#[cfg(not(target_arch = "wasm32"))]
+impl TempTarball {
+    /// Guesses the top-level directory the tarball's contents are nested
+    /// under from its first file or directory, which only needs the start of
+    /// it decompressed.
+    fn first_entry_prefix(&mut self) -> Result<Option<PathBuf>> {
+        self.rewind().io_context(|| {
+            "Failed to seek to the beginning of temp tarball fd while looking for its prefix."
+                .into()
+        })?;
+        let gz = std::io::BufReader::new(flate2::read::GzDecoder::new(std::io::BufReader::new(
+            &mut *self,
+        )));
+        let mut ar = tar::Archive::new(gz);
+        let entries = ar.entries().map_err(|e| {
+            NassunError::ExtractIoError(e, None, "getting tarball entries iterator".into())
+        })?;
+        for entry in entries {
+            let entry = entry.map_err(|e| {
+                NassunError::ExtractIoError(e, None, "reading entry from tarball".into())
+            })?;
+            let header = entry.header();
+            let entry_type = header.entry_type();
+            if !entry_type.is_file() && !entry_type.is_dir() {
+                continue;
+            }
+            let path = header.path().map_err(|e| {
+                NassunError::ExtractIoError(e, None, "reading path from entry header.".into())
+            })?;
+            // Entries for `./` itself don't say anything either way.
+            if path.components().all(|c| matches!(c, Component::CurDir)) {
+                continue;
+            }
+            return Ok(tarball_prefix([(&*path, entry_type.is_dir())]));
+        }
+        Ok(None)
     }
 }
 
@@ -409,6 +593,42 @@ impl std::io::Seek for TempTarball {
     }
 }
 
+/// Wraps a writer, such as a [`cacache::SyncWriter`], and reports the number
+/// of bytes written through it to a [`ProgressCallback`]. Data is passed
+/// through unchanged, so integrity calculations are unaffected.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ProgressWriter<W> {
+    inner: W,
+    progress: Option<ProgressCallback>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<W: Write> ProgressWriter<W> {
+    pub fn new(inner: W, progress: Option<ProgressCallback>) -> Self {
+        Self { inner, progress }
+    }
+
+    /// Returns the wrapped writer, so it can be committed.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<W: Write> Write for ProgressWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(progress) = &self.progress {
+            progress(n);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[derive(rkyv::Archive, rkyv::Serialize, Default)]
 #[archive(check_bytes)]
@@ -418,15 +638,193 @@ pub(crate) struct TarballIndex {
     pub(crate) files: HashMap<String, (String, u32)>,
 }
 
+/// Paths of the files extracted from a tarball so far, used to catch entries
+/// that would clobber each other on case-insensitive filesystems.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct CasePaths(HashMap<String, PathBuf>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl CasePaths {
+    /// Records `path`, or errors if it only differs in case from a path
+    /// that was recorded before it. The first path always wins.
+    fn insert(&mut self, path: &Path) -> Result<()> {
+        let key = path.to_string_lossy().to_lowercase();
+        match self.0.get(&key) {
+            Some(existing) if existing != path => Err(NassunError::CaseCollision(
+                path.to_path_buf(),
+                existing.clone(),
+            )),
+            _ => {
+                self.0.insert(key, path.to_path_buf());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Whether `dir` is on a filesystem that ignores case, found by creating a
+/// file in it and looking it up again under a different case. If the probe
+/// can't be created, we assume the worst.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_case_insensitive(dir: &Path) -> bool {
+    let probe = dir.join(".oro-case-probe");
+    if std::fs::File::create(&probe).is_err() {
+        return true;
+    }
+    let insensitive = dir.join(".ORO-CASE-PROBE").exists();
+    let _ = std::fs::remove_file(&probe);
+    insensitive
+}
+
+/// Whether a file at `path` (relative to the package root) passes `filter`.
 #[cfg(not(target_arch = "wasm32"))]
-fn strip_one(path: &Path) -> Option<&Path> {
-    let mut comps = path.components();
-    comps.next().map(|_| comps.as_path())
+pub(crate) fn should_extract(filter: Option<&ExtractFilter>, path: &Path) -> bool {
+    path == Path::new("package.json") || filter.map(|f| f(path)).unwrap_or(true)
 }
 
+/// Works out which top-level directory a tarball's contents are nested
+/// under, from the paths of its files and directories, and whether each is
+/// a directory.
+///
+/// npm tarballs use `package/`, but tarballs from elsewhere (like git hosts'
+/// `<repo>-<sha>/`) can use anything. Tarballs with any file at their root,
+/// or with more than one top-level directory, aren't nested at all, and get
+/// extracted as-is.
+#[cfg(not(target_arch = "wasm32"))]
+fn tarball_prefix<'a>(entries: impl IntoIterator<Item = (&'a Path, bool)>) -> Option<PathBuf> {
+    let mut prefix: Option<PathBuf> = None;
+    for (path, is_dir) in entries {
+        let mut comps = path
+            .components()
+            .skip_while(|c| matches!(c, Component::CurDir));
+        let Some(first) = comps.next() else {
+            continue;
+        };
+        if !is_dir && comps.next().is_none() {
+            return None;
+        }
+        match &prefix {
+            Some(prefix) if prefix.as_os_str() != first.as_os_str() => return None,
+            Some(_) => {}
+            None => prefix = Some(PathBuf::from(first.as_os_str())),
+        }
+    }
+    prefix
+}
+
+/// Removes the files in `written`, and then any directories in `created`
+/// that were left empty, so extraction into `dir` can start over.
+#[cfg(not(target_arch = "wasm32"))]
+fn remove_extracted(
+    dir: &Path,
+    written: &[PathBuf],
+    created: &dashmap::DashSet<PathBuf>,
+) -> Result<()> {
+    for path in written {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).io_context(|| {
+                    format!(
+                        "Failed to remove partially-extracted file at {}.",
+                        path.display()
+                    )
+                })
+            }
+            _ => {}
+        }
+    }
+    let mut dirs = created
+        .iter()
+        .map(|path| path.key().clone())
+        .filter(|path| path.starts_with(dir) && path != dir)
+        .collect::<Vec<_>>();
+    dirs.sort_by_key(|path| std::cmp::Reverse(path.components().count()));
+    for path in dirs {
+        // Anything that's still got something in it wasn't ours to remove.
+        let _ = std::fs::remove_dir(path);
+    }
+    Ok(())
+}
+
+/// Strips `prefix` from the front of `path`, ignoring any leading `./`.
+/// Paths outside of `prefix` are left alone.
+#[cfg(not(target_arch = "wasm32"))]
+fn strip_prefix(path: &Path, prefix: &Path) -> PathBuf {
+    let path = path
+        .components()
+        .skip_while(|c| matches!(c, Component::CurDir))
+        .collect::<PathBuf>();
+    path.strip_prefix(prefix)
+        .map(Path::to_path_buf)
+        .unwrap_or(path)
+}
+
+/// Prefix for the index keys of cached package tarballs.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) const TARBALL_KEY_PREFIX: &str = "nassun::package::";
+
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) fn tarball_key(integrity: &Integrity) -> String {
-    format!("nassun::package::{integrity}")
+    format!("{TARBALL_KEY_PREFIX}{integrity}")
+}
+
+/// Records `index` as the cached index for the tarball with `integrity`.
+/// `sha512` is noted on the entry when `integrity` is a weaker hash that one
+/// was computed for.
+#[cfg(not(target_arch = "wasm32"))]
+fn insert_tarball_index(
+    cache: &Path,
+    integrity: &Integrity,
+    index: &[u8],
+    sha512: Option<&Integrity>,
+) -> Result<()> {
+    let mut opts = WriteOpts::new()
+        // This is just so the index entry is loadable.
+        .integrity("xxh3-deadbeef".parse().unwrap())
+        .raw_metadata(index.to_vec());
+    if let Some(sha512) = sha512 {
+        opts = opts.metadata(serde_json::json!({ "sha512": sha512.to_string() }));
+    }
+    cacache::index::insert(cache, &tarball_key(integrity), opts)
+        .map_err(|e| NassunError::ExtractCacheError(e, None))?;
+    Ok(())
+}
+
+/// The sha512 noted on a tarball's cache entry, if it was cached under a
+/// weaker hash and one has been computed for it since.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn recorded_sha512(entry: &cacache::Metadata) -> Option<Integrity> {
+    entry.metadata.get("sha512")?.as_str()?.parse().ok()
+}
+
+/// Looks up the cached index for a package tarball. If there's no entry for
+/// `integrity` as a whole, each of its hashes is tried on its own, strongest
+/// first, so tarballs cached under only one of them are still found.
+///
+/// Returns the integrity the entry was found under, along with the entry.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn find_tarball_index(
+    cache: &Path,
+    integrity: &Integrity,
+) -> Result<Option<(Integrity, cacache::Metadata)>> {
+    let candidates = std::iter::once(integrity.clone()).chain(
+        integrity
+            .hashes
+            .iter()
+            .filter(|_| integrity.hashes.len() > 1)
+            .map(|hash| Integrity {
+                hashes: vec![hash.clone()],
+            }),
+    );
+    for sri in candidates {
+        if let Some(entry) = cacache::index::find(cache, &tarball_key(&sri))
+            .map_err(|e| NassunError::ExtractCacheError(e, None))?
+        {
+            return Ok(Some((sri, entry)));
+        }
+    }
+    Ok(None)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -553,3 +951,281 @@ pub(crate) fn mkdirp(path: &Path, cache: &dashmap::DashSet<PathBuf>) -> Result<(
     }
     Ok(())
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn progress_writer_reports_all_bytes() -> miette::Result<()> {
+        let cache = tempfile::tempdir().unwrap();
+        let blob = (0..MAX_IN_MEMORY_TARBALL_SIZE * 2)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<u8>>();
+        let total = Arc::new(AtomicUsize::new(0));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let progress: ProgressCallback = {
+            let total = total.clone();
+            let calls = calls.clone();
+            Arc::new(move |n| {
+                total.fetch_add(n, Ordering::SeqCst);
+                calls.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+
+        let mut writer = ProgressWriter::new(
+            WriteOpts::new()
+                .open_hash_sync(cache.path())
+                .map_err(|e| NassunError::ExtractCacheError(e, None))?,
+            Some(progress),
+        );
+        for chunk in blob.chunks(64 * 1024) {
+            writer.write_all(chunk).unwrap();
+        }
+        let sri = writer
+            .into_inner()
+            .commit()
+            .map_err(|e| NassunError::ExtractCacheError(e, None))?;
+
+        assert_eq!(total.load(Ordering::SeqCst), blob.len());
+        assert!(calls.load(Ordering::SeqCst) > 1);
+        assert_eq!(sri, Integrity::from(&blob));
+        assert_eq!(
+            cacache::read_hash_sync(cache.path(), &sri)
+                .map_err(|e| NassunError::ExtractCacheError(e, None))?,
+            blob
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn case_colliding_entries() -> miette::Result<()> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        for (path, contents) in [
+            ("package/README", "first"),
+            ("package/readme", "second"),
+            ("package/index.js", ""),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        let data = builder.into_inner().unwrap().finish().unwrap();
+
+        for _ in 0..2 {
+            let dir = tempfile::tempdir().unwrap();
+            let (_, collisions) = TempTarball::Memory(std::io::Cursor::new(data.clone()))
+                .extract_to_dir(dir.path(), None, None, ExtractMode::Copy, None, None)?;
+            let mut names = std::fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            names.sort();
+            assert_eq!(
+                std::fs::read_to_string(dir.path().join("README")).unwrap(),
+                "first"
+            );
+            if is_case_insensitive(dir.path()) {
+                assert_eq!(names, ["README", "index.js"]);
+                assert!(matches!(
+                    &collisions[..],
+                    [NassunError::CaseCollision(path, existing)]
+                        if path == Path::new("readme") && existing == Path::new("README")
+                ));
+            } else {
+                // Nothing gets clobbered where case matters, so there's no
+                // reason to leave anything out.
+                assert_eq!(names, ["README", "index.js", "readme"]);
+                assert_eq!(
+                    std::fs::read_to_string(dir.path().join("readme")).unwrap(),
+                    "second"
+                );
+                assert!(collisions.is_empty());
+            }
+        }
+
+        let mut case_paths = CasePaths::default();
+        case_paths.insert(Path::new("README"))?;
+        case_paths.insert(Path::new("README"))?;
+        assert!(matches!(
+            case_paths.insert(Path::new("readme")),
+            Err(NassunError::CaseCollision(path, existing))
+                if path == Path::new("readme") && existing == Path::new("README")
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn non_standard_prefixes() -> miette::Result<()> {
+        for (entries, expected) in [
+            (
+                vec!["repo-abc123/package.json", "repo-abc123/lib/index.js"],
+                ["lib/index.js", "package.json"],
+            ),
+            (
+                vec!["package.json", "lib/index.js"],
+                ["lib/index.js", "package.json"],
+            ),
+            (
+                vec!["lib/index.js", "package.json"],
+                ["lib/index.js", "package.json"],
+            ),
+        ] {
+            let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            ));
+            for path in entries {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(2);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder
+                    .append_data(&mut header, path, "{}".as_bytes())
+                    .unwrap();
+            }
+            let data = builder.into_inner().unwrap().finish().unwrap();
+
+            let dir = tempfile::tempdir().unwrap();
+            TempTarball::Memory(std::io::Cursor::new(data)).extract_to_dir(
+                dir.path(),
+                None,
+                None,
+                ExtractMode::Copy,
+                None,
+                None,
+            )?;
+            for path in expected {
+                assert!(
+                    dir.path().join(path).is_file(),
+                    "{path} should be at the package root"
+                );
+            }
+            // Guessing wrong from the first entry shouldn't leave anything
+            // from the first try behind.
+            assert!(!dir.path().join("index.js").exists());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sha1_tarballs_get_sha512() -> miette::Result<()> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "package/package.json", "{}".as_bytes())
+            .unwrap();
+        let data = builder.into_inner().unwrap().finish().unwrap();
+        let sha1 = IntegrityOpts::new()
+            .algorithm(ssri::Algorithm::Sha1)
+            .chain(&data)
+            .result();
+        let sha512 = IntegrityOpts::new()
+            .algorithm(ssri::Algorithm::Sha512)
+            .chain(&data)
+            .result();
+
+        let cache = tempfile::tempdir().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let (integrity, _) = TempTarball::Memory(std::io::Cursor::new(data)).extract_to_dir(
+            dir.path(),
+            Some(sha1.clone()),
+            Some(cache.path()),
+            ExtractMode::Copy,
+            None,
+            None,
+        )?;
+        assert_eq!(integrity, sha1.concat(sha512.clone()));
+
+        // The original entry is still there, and the stronger hash finds
+        // the same one, whether on its own or alongside the sha1.
+        for sri in [&sha1, &sha512, &integrity] {
+            let (_, entry) = find_tarball_index(cache.path(), sri)?
+                .unwrap_or_else(|| panic!("no cache entry for {sri}"));
+            assert!(entry.raw_metadata.is_some());
+        }
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn sha1_cache_entries_get_sha512_on_read() -> miette::Result<()> {
+        let base = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(base.path().join("dep")).unwrap();
+        std::fs::write(
+            base.path().join("dep").join("package.json"),
+            r#"{"name": "dep", "version": "1.0.0"}"#,
+        )
+        .unwrap();
+        let cache = tempfile::tempdir().unwrap();
+        let pkg = crate::NassunOpts::new()
+            .cache(cache.path())
+            .base_dir(base.path())
+            .build()
+            .resolve("file:./dep")
+            .await?;
+
+        let mut data = Vec::new();
+        pkg.tarball_unchecked()
+            .await?
+            .read_to_end(&mut data)
+            .await
+            .unwrap();
+        let sha1 = IntegrityOpts::new()
+            .algorithm(ssri::Algorithm::Sha1)
+            .chain(&data)
+            .result();
+        let sha512 = IntegrityOpts::new()
+            .algorithm(ssri::Algorithm::Sha512)
+            .chain(&data)
+            .result();
+
+        // An entry cached from before sha512s were recorded for sha1-only
+        // tarballs.
+        let contents = cacache::write_hash_sync(
+            cache.path(),
+            std::fs::read(base.path().join("dep").join("package.json")).unwrap(),
+        )
+        .unwrap();
+        let mut index = TarballIndex::default();
+        index
+            .files
+            .insert("package.json".into(), (contents.to_string(), 0o644));
+        let index = rkyv::util::to_bytes::<_, 1024>(&index)
+            .map_err(|e| NassunError::SerializeCacheError(format!("{e}")))?
+            .into_vec();
+        insert_tarball_index(cache.path(), &sha1, &index, None)?;
+
+        let dir = tempfile::tempdir().unwrap();
+        let integrity = pkg
+            .extract_to_dir_checked(dir.path(), sha1.clone(), ExtractMode::Copy)
+            .await?;
+        assert_eq!(integrity, sha1.concat(sha512.clone()));
+        assert!(dir.path().join("package.json").exists());
+
+        // The sha512 now finds the same entry, and the sha1 entry remembers
+        // it, so later reads don't have to compute it again.
+        let (_, entry) = find_tarball_index(cache.path(), &sha512)?
+            .unwrap_or_else(|| panic!("no cache entry for {sha512}"));
+        assert_eq!(entry.raw_metadata, Some(index));
+        let (_, entry) = find_tarball_index(cache.path(), &sha1)?
+            .unwrap_or_else(|| panic!("no cache entry for {sha1}"));
+        assert_eq!(recorded_sha512(&entry), Some(sha512));
+        Ok(())
+    }
+}
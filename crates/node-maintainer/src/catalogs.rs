@@ -0,0 +1,46 @@
+use indexmap::IndexMap;
+use nassun::PackageSpec;
+use oro_common::CorgiManifest;
+
+use crate::error::NodeMaintainerError;
+
+/// pnpm-style catalogs, which `catalog:` and `catalog:<name>` dependencies
+/// get their actual versions from.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Catalogs {
+    default: IndexMap<String, String>,
+    named: IndexMap<String, IndexMap<String, String>>,
+}
+
+impl Catalogs {
+    pub(crate) fn from_manifest(manifest: &CorgiManifest) -> Self {
+        Self {
+            default: manifest.catalog.clone(),
+            named: manifest.catalogs.clone(),
+        }
+    }
+
+    /// Returns the spec that a `catalog:` dependency on `name` stands for.
+    /// `catalog:default` is the same as a plain `catalog:`, and can also be
+    /// defined as `catalogs.default`.
+    pub(crate) fn get(
+        &self,
+        name: &str,
+        catalog: Option<&str>,
+    ) -> Result<PackageSpec, NodeMaintainerError> {
+        let entries = match catalog {
+            None | Some("default") if self.default.is_empty() => self.named.get("default"),
+            None | Some("default") => Some(&self.default),
+            Some(catalog) => self.named.get(catalog),
+        };
+        let spec = entries
+            .and_then(|entries| entries.get(name))
+            .ok_or_else(|| {
+                NodeMaintainerError::MissingCatalogEntry(
+                    name.into(),
+                    catalog.unwrap_or("default").into(),
+                )
+            })?;
+        Ok(format!("{name}@{spec}").parse()?)
+    }
+}
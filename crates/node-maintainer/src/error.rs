@@ -85,6 +85,16 @@ pub enum NodeMaintainerError {
     #[diagnostic(code(node_maintainer::kdl::invalid_lockfile_version), url(docsrs))]
     InvalidLockfileVersion,
 
+    /// The `package-lock.kdl` was written by a newer version of Orogene,
+    /// using a lockfile format this version doesn't understand.
+    #[error("Unsupported lockfile version: {0}")]
+    #[diagnostic(
+        code(node_maintainer::kdl::unsupported_lockfile_version),
+        url(docsrs),
+        help("This lockfile was probably written by a newer version of Orogene. Try upgrading.")
+    )]
+    UnsupportedLockfileVersion(u64),
+
     /// Error from serde_wasm_bindgen
     #[cfg(target_arch = "wasm32")]
     #[error(transparent)]
@@ -155,6 +165,16 @@ pub enum NodeMaintainerError {
     #[diagnostic(code(node_maintainer::mpsc_error), url(docsrs))]
     TrySendError,
 
+    /// Two different packages in the resolved tree would have been installed
+    /// to the same path.
+    #[error("Both {1} and {2} would be installed to {0}.")]
+    #[diagnostic(
+        code(node_maintainer::layout_collision),
+        url(docsrs),
+        help("This is a bug with the orogene resolver. Please report it.")
+    )]
+    LayoutCollision(String, String, String),
+
     /// Failed to validate a graph. Refer to the error message for more details.
     #[error("{0}")]
     #[diagnostic(code(node_maintainer::graph_error), url(docsrs))]
@@ -197,6 +217,70 @@ pub enum NodeMaintainerError {
         help("Did you modify package.json by hand?")
     )]
     LockfileMismatch,
+
+    /// The resolved tree has peer dependencies that are resolved to versions
+    /// that don't satisfy what their dependents asked for. Peers that are
+    /// missing entirely are only reported as warnings.
+    #[error("Found unresolvable peer dependency conflicts:\n{0}")]
+    #[diagnostic(
+        code(node_maintainer::peer_conflicts),
+        url(docsrs),
+        help("Use `legacy_peer_deps` (`--legacy-peer-deps`) to install the tree anyway, treating these as warnings.")
+    )]
+    PeerConflicts(String),
+
+    /// A `catalog:` dependency referred to a package that isn't in the
+    /// catalog.
+    #[error("`{0}` is not defined in the `{1}` catalog.")]
+    #[diagnostic(
+        code(node_maintainer::missing_catalog_entry),
+        url(docsrs),
+        help("Add it to the `catalog` or `catalogs` field in your root package.json.")
+    )]
+    MissingCatalogEntry(String, String),
+
+    /// Conflicting versions of packages that depend on each other kept
+    /// getting nested inside each other, and would go on forever. Cycles
+    /// between packages are otherwise fine, and are resolved like any other
+    /// dependency.
+    #[error(
+        "Dependency cycle can't be resolved: {0} would keep getting nested inside itself, at {1}."
+    )]
+    #[diagnostic(
+        code(node_maintainer::unresolvable_cycle),
+        url(docsrs),
+        help("Use `resolutions` in your package.json to pick a single version for the packages in the cycle.")
+    )]
+    UnresolvableCycle(String, String),
+
+    /// Another process is currently modifying this project's `node_modules`
+    /// and lockfile. Concurrent installs into the same project are refused
+    /// so they can't corrupt each other's work.
+    ///
+    /// The lock is released as soon as the other process exits, so this
+    /// goes away on its own once it's done.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("Another orogene process is running in this project (lock file at {}).", .0.display())]
+    #[diagnostic(
+        code(node_maintainer::project_locked),
+        url(docsrs),
+        help("Wait for the other process to finish, then try again.")
+    )]
+    ProjectLocked(std::path::PathBuf),
+
+    /// The project's `package-lock.kdl` exists, but couldn't be parsed.
+    ///
+    /// Use [`crate::NodeMaintainerOptions::fix_lockfile`] (`--fix-lockfile`
+    /// on the command line) to discard it and resolve a fresh tree from
+    /// `package.json` instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("Failed to parse {}.", .0.display())]
+    #[diagnostic(
+        code(node_maintainer::corrupt_lockfile),
+        url(docsrs),
+        help("Run again with `--fix-lockfile` to discard it and re-resolve from package.json.")
+    )]
+    CorruptLockfile(std::path::PathBuf, #[source] Box<NodeMaintainerError>),
 }
 
 impl<T> From<mpsc::TrySendError<T>> for NodeMaintainerError {
@@ -1,20 +1,23 @@
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     ffi::OsStr,
+    fmt,
     ops::{Index, IndexMut},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use indexmap::IndexMap;
 use kdl::KdlDocument;
 use nassun::{package::Package, PackageResolution, PackageSpec};
-use oro_common::CorgiManifest;
+use oro_common::{CorgiManifest, PeerDependencyMeta};
 use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableGraph};
 #[cfg(not(target_arch = "wasm32"))]
 use petgraph::Direction;
+use ssri::Integrity;
 use unicase::UniCase;
+use url::Url;
 
-use crate::{error::NodeMaintainerError, Lockfile, LockfileNode};
+use crate::{error::NodeMaintainerError, Lockfile, LockfileNode, LOCKFILE_VERSION};
 
 #[cfg(debug_assertions)]
 use NodeMaintainerError::GraphValidationError;
@@ -45,12 +48,25 @@ pub struct Node {
     pub(crate) dependencies: IndexMap<UniCase<String>, EdgeIndex>,
     /// Map of dependencies to their requirements.
     pub(crate) dependency_reqs: IndexMap<UniCase<String>, (PackageSpec, DepType)>,
+    /// Map of peer dependencies to their requirements, and whether they're
+    /// marked as optional through `peerDependenciesMeta`.
+    pub(crate) peer_reqs: IndexMap<UniCase<String>, (PackageSpec, bool)>,
     /// Parent, if any, of this Node in the logical filesystem hierarchy.
     pub(crate) parent: Option<NodeIndex>,
     /// Children of this node in the logical filesystem hierarchy. These are
     /// not necessarily dependencies, and this Node's dependencies may not all
     /// be in this HashMap.
     pub(crate) children: IndexMap<UniCase<String>, NodeIndex>,
+    /// Names of the packages along the dependency path through which this
+    /// Node was first reached, starting from (but not including) the root.
+    /// Used to match `resolutions` patterns.
+    pub(crate) logical_path: Vec<UniCase<String>>,
+    /// Operating systems this Node's package supports, from its manifest.
+    pub(crate) os: Vec<String>,
+    /// CPU architectures this Node's package supports, from its manifest.
+    pub(crate) cpu: Vec<String>,
+    /// C standard libraries this Node's package supports, from its manifest.
+    pub(crate) libc: Vec<String>,
 }
 
 impl Node {
@@ -90,6 +106,20 @@ impl Node {
                 (format!("{name}@{spec}").parse()?, dep_type),
             );
         }
+        let mut peer_reqs = IndexMap::new();
+        for (name, spec) in &manifest.peer_dependencies {
+            let optional = manifest
+                .peer_dependencies_meta
+                .get(name)
+                .map(|meta| meta.optional)
+                .unwrap_or(false);
+            // Peers are only ever checked against the tree, never fetched, so
+            // there's no reason to fail the whole resolution over a spec we
+            // can't understand.
+            if let Ok(spec) = format!("{name}@{spec}").parse() {
+                peer_reqs.insert(UniCase::new(name.clone()), (spec, optional));
+            }
+        }
         Ok(Self {
             package,
             name,
@@ -99,6 +129,11 @@ impl Node {
             children: IndexMap::new(),
             dependencies: IndexMap::new(),
             dependency_reqs,
+            peer_reqs,
+            logical_path: Vec::new(),
+            os: manifest.os,
+            cpu: manifest.cpu,
+            libc: manifest.libc,
         })
     }
 
@@ -116,6 +151,97 @@ pub enum DepType {
     Opt,
 }
 
+/// A peer dependency that isn't satisfied by the resolved tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerConflict {
+    /// Name of the package that declared the peer dependency.
+    pub dependent: String,
+    /// The requested peer dependency.
+    pub requested: PackageSpec,
+    /// The package that was found in the peer's place, if any.
+    pub found: Option<PackageResolution>,
+}
+
+impl fmt::Display for PeerConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(found) = &self.found {
+            let found = match found {
+                PackageResolution::Npm { name, version, .. } => format!("{name}@{version}"),
+                other => other.to_string(),
+            };
+            write!(
+                f,
+                "{} requires peer dependency {}, but found {found}",
+                self.dependent, self.requested
+            )
+        } else {
+            write!(
+                f,
+                "{} requires peer dependency {}, but it is missing",
+                self.dependent, self.requested
+            )
+        }
+    }
+}
+
+/// A difference between the resolved tree and the one last installed to
+/// `node_modules`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeDrift {
+    /// A package that should be installed at `path`, but isn't.
+    Missing { path: String, wanted: String },
+    /// A package installed at `path` that's no longer part of the tree.
+    Extraneous { path: String, found: String },
+    /// A package installed at `path` that doesn't match the resolved one.
+    Changed {
+        path: String,
+        wanted: String,
+        found: String,
+    },
+    /// A package at `path` that's a symlink to `target`, outside of
+    /// `node_modules`. These are left as-is rather than being checked.
+    Linked { path: String, target: PathBuf },
+}
+
+impl TreeDrift {
+    /// Path to the affected package, relative to the project root.
+    pub fn path(&self) -> &str {
+        match self {
+            TreeDrift::Missing { path, .. }
+            | TreeDrift::Extraneous { path, .. }
+            | TreeDrift::Changed { path, .. }
+            | TreeDrift::Linked { path, .. } => path,
+        }
+    }
+
+    /// Whether this entry only reports a linked package, rather than an
+    /// actual difference from the resolved tree.
+    pub fn is_linked(&self) -> bool {
+        matches!(self, TreeDrift::Linked { .. })
+    }
+}
+
+impl fmt::Display for TreeDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreeDrift::Missing { path, wanted } => {
+                write!(f, "{path}: {wanted} is not installed")
+            }
+            TreeDrift::Extraneous { path, found } => {
+                write!(f, "{path}: {found} is installed, but no longer needed")
+            }
+            TreeDrift::Changed {
+                path,
+                wanted,
+                found,
+            } => write!(f, "{path}: {found} is installed, but {wanted} is wanted"),
+            TreeDrift::Linked { path, target } => {
+                write!(f, "{path}: linked to {}", target.display())
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Edge {
     pub(crate) requested: PackageSpec,
@@ -211,7 +337,7 @@ impl Graph {
             })
             .collect::<Result<IndexMap<_, _>, NodeMaintainerError>>()?;
         Ok(Lockfile {
-            version: 1,
+            version: LOCKFILE_VERSION,
             root,
             packages,
         })
@@ -308,6 +434,62 @@ impl Graph {
         path
     }
 
+    /// Finds all peer dependencies in the tree that are either missing or
+    /// resolved to a version that doesn't satisfy their requirement. Missing
+    /// peers that are marked as optional are not reported.
+    pub(crate) fn peer_conflicts(&self) -> Result<Vec<PeerConflict>, NodeMaintainerError> {
+        let mut conflicts = Vec::new();
+        for idx in self.inner.node_indices() {
+            let node = &self.inner[idx];
+            for (name, (requested, optional)) in &node.peer_reqs {
+                let found = match self.resolve_dep(idx, name) {
+                    Some(found_idx) => {
+                        let found = self.inner[found_idx].package.resolved();
+                        if found.satisfies(requested)? {
+                            continue;
+                        }
+                        Some(found.clone())
+                    }
+                    None if *optional => continue,
+                    None => None,
+                };
+                conflicts.push(PeerConflict {
+                    dependent: node.package.name().to_string(),
+                    requested: requested.clone(),
+                    found,
+                });
+            }
+        }
+        Ok(conflicts)
+    }
+
+    /// Every registry tarball the tree needs, one entry per `name@version`,
+    /// sorted by name. The root package is left out, the same way it is in
+    /// the lockfile, as are directory and git dependencies.
+    pub(crate) fn tarball_urls(&self) -> Vec<(String, Url, Option<Integrity>)> {
+        let mut urls = self
+            .inner
+            .node_indices()
+            .filter(|idx| *idx != self.root)
+            .filter_map(|idx| match self.inner[idx].package.resolved() {
+                PackageResolution::Npm {
+                    name,
+                    version,
+                    tarball,
+                    integrity,
+                } => Some((
+                    format!("{name}@{version}"),
+                    tarball.clone(),
+                    integrity.clone(),
+                )),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        urls.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        urls.dedup_by(|a, b| a.0 == b.0 && a.1 == b.1);
+        urls
+    }
+
     pub(crate) fn node_path_string(&self, node_idx: NodeIndex) -> String {
         self.node_path(node_idx)
             .iter()
@@ -316,6 +498,101 @@ impl Graph {
             .join("/node_modules/")
     }
 
+    /// Makes sure no two different packages would be written to the same
+    /// path in `node_modules`. Paths are compared case-insensitively, since
+    /// that's how plenty of filesystems will treat them.
+    pub(crate) fn validate_layout(&self) -> Result<(), NodeMaintainerError> {
+        let mut paths = HashMap::new();
+        for idx in self.inner.node_indices() {
+            if idx == self.root {
+                continue;
+            }
+            let path = UniCase::new(self.node_path_string(idx));
+            if let Some(other) = paths.insert(path.clone(), idx) {
+                let (first, second) = (&self.inner[other], &self.inner[idx]);
+                if first.package.resolved() != second.package.resolved() {
+                    return Err(NodeMaintainerError::LayoutCollision(
+                        format!("node_modules/{path}"),
+                        format!("{:?}", first.package.resolved()),
+                        format!("{:?}", second.package.resolved()),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares the resolved tree against `actual`, the tree recorded the last
+    /// time `node_modules` was written. Packages are considered changed using
+    /// the same criteria pruning does, so an empty result means applying
+    /// would have nothing to do. If `root` is given, packages symlinked into
+    /// its `node_modules` from elsewhere are reported as linked instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn drift(
+        &self,
+        actual: Option<&Lockfile>,
+        root: Option<&Path>,
+    ) -> Result<Vec<TreeDrift>, NodeMaintainerError> {
+        fn describe(node: &LockfileNode) -> String {
+            match (&node.version, &node.resolved) {
+                (Some(version), _) => format!("{}@{version}", node.name),
+                (None, Some(resolved)) => format!("{}@{resolved}", node.name),
+                (None, None) => node.name.to_string(),
+            }
+        }
+
+        let ideal = self.to_lockfile()?;
+        let empty = IndexMap::new();
+        let actual = actual.map(|tree| &tree.packages).unwrap_or(&empty);
+        let node_modules = root.map(|root| root.join("node_modules"));
+        let linked = |path: &UniCase<String>| {
+            node_modules.as_ref().and_then(|node_modules| {
+                crate::linkers::linked_package(&node_modules.join(path.as_str()), node_modules)
+            })
+        };
+        let mut drift = Vec::new();
+        for (path, wanted) in &ideal.packages {
+            let path_string = format!("node_modules/{path}");
+            if let Some(target) = linked(path) {
+                drift.push(TreeDrift::Linked {
+                    path: path_string,
+                    target,
+                });
+                continue;
+            }
+            match actual.get(path) {
+                None => drift.push(TreeDrift::Missing {
+                    path: path_string,
+                    wanted: describe(wanted),
+                }),
+                Some(found) if found != wanted => drift.push(TreeDrift::Changed {
+                    path: path_string,
+                    wanted: describe(wanted),
+                    found: describe(found),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (path, found) in actual {
+            if ideal.packages.contains_key(path) {
+                continue;
+            }
+            if let Some(target) = linked(path) {
+                drift.push(TreeDrift::Linked {
+                    path: format!("node_modules/{path}"),
+                    target,
+                });
+            } else {
+                drift.push(TreeDrift::Extraneous {
+                    path: format!("node_modules/{path}"),
+                    found: describe(found),
+                });
+            }
+        }
+        drift.sort_by(|a, b| a.path().cmp(b.path()));
+        Ok(drift)
+    }
+
     /// Validate that file system hierarchy (parent -> children) is compatible
     /// with graph edges (dependent -> dependency).
     #[cfg(debug_assertions)]
@@ -399,6 +676,15 @@ impl Graph {
             };
             deps.insert(name.to_string(), requested.requested().clone());
         }
+        // Peers aren't edges unless they were installed for the package, so
+        // they're recorded separately to be checked again on the next run.
+        let mut peer_deps_meta = IndexMap::new();
+        for (name, (requested, optional)) in &node.peer_reqs {
+            peer_deps.insert(name.to_string(), requested.requested());
+            if *optional {
+                peer_deps_meta.insert(name.to_string(), PeerDependencyMeta { optional: true });
+            }
+        }
         Ok(LockfileNode {
             name: UniCase::new(node.package.name().to_string()),
             is_root,
@@ -408,7 +694,12 @@ impl Graph {
             dependencies: prod_deps,
             dev_dependencies: dev_deps,
             peer_dependencies: peer_deps,
+            peer_dependencies_meta: peer_deps_meta,
             optional_dependencies: opt_deps,
+            os: node.os.clone(),
+            cpu: node.cpu.clone(),
+            libc: node.libc.clone(),
+            platform_known: true,
             integrity: match node.package.resolved() {
                 PackageResolution::Npm { ref integrity, .. } => integrity.clone(),
                 _ => None,
@@ -435,3 +726,61 @@ impl<'a> Iterator for NodeParentIterator<'a> {
         }
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use nassun::{Nassun, NassunOpts};
+
+    use super::*;
+
+    #[test]
+    fn layout_collision() -> Result<(), NodeMaintainerError> {
+        let nassun = NassunOpts::new().build();
+        let mut graph = Graph::default();
+        let root = graph.inner.add_node(Node::new(
+            UniCase::new("".into()),
+            Nassun::dummy_from_manifest(CorgiManifest::default()),
+            CorgiManifest::default(),
+            true,
+        )?);
+        graph.root = root;
+        for version in ["1.0.0", "2.0.0"] {
+            let package = nassun.resolve_from(
+                "a".into(),
+                format!("a@{version}").parse()?,
+                PackageResolution::Npm {
+                    name: "a".into(),
+                    version: version.parse()?,
+                    tarball: format!("https://example.com/-/a-{version}.tgz")
+                        .parse()
+                        .unwrap(),
+                    integrity: None,
+                },
+            )?;
+            let idx = graph.inner.add_node(Node::new(
+                UniCase::new("a".into()),
+                package,
+                CorgiManifest::default(),
+                false,
+            )?);
+            graph[idx].idx = idx;
+            graph[idx].root = root;
+            graph[idx].parent = Some(root);
+            // The second `a` clobbers the first one, but both are still in
+            // the graph and would both get extracted.
+            graph[root].children.insert(UniCase::new("a".into()), idx);
+        }
+
+        let err = graph.validate_layout().unwrap_err();
+        assert!(matches!(
+            err,
+            NodeMaintainerError::LayoutCollision(ref path, ref first, ref second)
+                if path == "node_modules/a" && first == "a@1.0.0" && second == "a@2.0.0"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Both a@1.0.0 and a@2.0.0 would be installed to node_modules/a."
+        );
+        Ok(())
+    }
+}
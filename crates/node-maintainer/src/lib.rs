@@ -4,21 +4,36 @@
 pub use nassun::Nassun;
 #[cfg(not(target_arch = "wasm32"))]
 pub use nassun::{NassunError, NassunOpts};
+pub use oro_common::{Warning, WarningCollector, WarningKind};
 
 pub use error::*;
+pub use graph::{PeerConflict, TreeDrift};
 pub use into_kdl::IntoKdl;
+#[cfg(not(target_arch = "wasm32"))]
+pub use lock::*;
 pub use lockfile::*;
 #[cfg(not(target_arch = "wasm32"))]
 pub use maintainer::*;
+pub use platform::*;
+pub use resolver::HoistMode;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
 
+mod catalogs;
+#[cfg(not(target_arch = "wasm32"))]
+mod debug_dump;
 mod error;
 mod graph;
 mod into_kdl;
 mod linkers;
+#[cfg(not(target_arch = "wasm32"))]
+mod lock;
 mod lockfile;
 mod maintainer;
+mod platform;
+mod resolutions;
 mod resolver;
+#[cfg(not(target_arch = "wasm32"))]
+mod trim;
 #[cfg(target_arch = "wasm32")]
 pub use wasm::*;
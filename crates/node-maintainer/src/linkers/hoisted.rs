@@ -10,6 +10,7 @@ use futures::{StreamExt, TryStreamExt};
 use nassun::ExtractMode;
 use oro_common::BuildManifest;
 use petgraph::stable_graph::NodeIndex;
+use tracing::Instrument;
 use unicase::UniCase;
 use walkdir::WalkDir;
 
@@ -74,6 +75,14 @@ impl HoistedLinker {
                         entry.path().display()
                     )
                 })?;
+                let entry_path: PathBuf = entry.path().into();
+                if ty.is_symlink() && super::linked_package(&entry_path, &prefix).is_some() {
+                    tracing::debug!(
+                        "Leaving linked package at {} in place.",
+                        entry_path.display()
+                    );
+                    continue;
+                }
                 if ty.is_dir() {
                     async_std::fs::remove_dir_all(entry.path()).await.io_context(|| format!("Failed to rimraf contents of directory at {} while pruning node_modules.", entry.path().display()))?;
                 } else if ty.is_file() {
@@ -147,6 +156,18 @@ impl HoistedLinker {
                     .file_name()
                     == nm_osstr
                 {
+                    if entry.path_is_symlink()
+                        && super::linked_package(entry_path, &prefix).is_some()
+                    {
+                        // Linked packages (like workspace packages) are
+                        // managed elsewhere, so they're never extraneous.
+                        tracing::trace!(
+                            "Leaving linked package at {} in place.",
+                            entry_path.display()
+                        );
+                        return false;
+                    }
+
                     let entry_subpath_path = entry_path
                         .strip_prefix(&prefix)
                         .expect("this should definitely be under the prefix");
@@ -244,7 +265,21 @@ impl HoistedLinker {
         let start = std::time::Instant::now();
 
         let root = &self.opts.root;
-        let stream = futures::stream::iter(graph.inner.node_indices());
+        // Packages are extracted one depth level at a time, so a nested
+        // package never lands in its parent's directory before the parent
+        // itself does. Otherwise, the parent would see its target directory
+        // already there and skip its own extraction.
+        let mut levels: Vec<Vec<NodeIndex>> = Vec::new();
+        for idx in graph.inner.node_indices() {
+            if idx == graph.root {
+                continue;
+            }
+            let depth = graph.node_path(idx).len() - 1;
+            if levels.len() <= depth {
+                levels.resize_with(depth + 1, Vec::new);
+            }
+            levels[depth].push(idx);
+        }
         let concurrent_count = Arc::new(AtomicUsize::new(0));
         let actually_extracted = Arc::new(AtomicUsize::new(0));
         let pending_rebuild = self.pending_rebuild.clone();
@@ -265,84 +300,98 @@ impl HoistedLinker {
         } else {
             ExtractMode::AutoHardlink
         };
-        stream
-            .map(|idx| {
-                Ok((
-                    idx,
-                    concurrent_count.clone(),
-                    total_completed.clone(),
-                    actually_extracted.clone(),
-                    pending_rebuild.clone(),
-                ))
-            })
-            .try_for_each_concurrent(
-                self.opts.concurrency,
-                move |(
-                    child_idx,
-                    concurrent_count,
-                    total_completed,
-                    actually_extracted,
-                    pending_rebuild,
-                )| async move {
-                    if child_idx == graph.root {
-                        return Ok(());
-                    }
-
-                    concurrent_count.fetch_add(1, atomic::Ordering::SeqCst);
-                    let subdir = graph
-                        .node_path(child_idx)
-                        .iter()
-                        .map(|x| x.to_string())
-                        .collect::<Vec<_>>()
-                        .join("/node_modules/");
-                    let target_dir = root.join("node_modules").join(&subdir);
-
-                    let start = std::time::Instant::now();
-
-                    if !target_dir.exists() {
-                        graph[child_idx]
-                            .package
-                            .extract_to_dir(&target_dir, extract_mode)
+        for level in levels {
+            futures::stream::iter(level)
+                .map(|idx| {
+                    Ok((
+                        idx,
+                        concurrent_count.clone(),
+                        total_completed.clone(),
+                        actually_extracted.clone(),
+                        pending_rebuild.clone(),
+                    ))
+                })
+                .try_for_each_concurrent(
+                    self.opts.extract_concurrency,
+                    move |(
+                        child_idx,
+                        concurrent_count,
+                        total_completed,
+                        actually_extracted,
+                        pending_rebuild,
+                    )| async move {
+                        concurrent_count.fetch_add(1, atomic::Ordering::SeqCst);
+                        let subdir = graph
+                            .node_path(child_idx)
+                            .iter()
+                            .map(|x| x.to_string())
+                            .collect::<Vec<_>>()
+                            .join("/node_modules/");
+                        let target_dir = root.join("node_modules").join(&subdir);
+
+                        let start = std::time::Instant::now();
+
+                        if !target_dir.exists() {
+                            let pkg = &graph[child_idx].package;
+                            // This covers fetching the tarball, too, since
+                            // it's streamed straight into the extraction.
+                            let span = tracing::trace_span!(
+                                "extract_package",
+                                name = %pkg.name(),
+                                resolved = %pkg.resolved()
+                            );
+                            super::extract_package(
+                                pkg,
+                                &target_dir,
+                                extract_mode,
+                                self.opts.extract_filter.as_ref(),
+                                self.opts.trim_patterns.as_deref(),
+                                self.opts.on_extract_bytes.as_ref(),
+                            )
+                            .instrument(span)
                             .await?;
-                        actually_extracted.fetch_add(1, atomic::Ordering::SeqCst);
-                        let target_dir = target_dir.clone();
-                        let build_mani = async_std::task::spawn_blocking(move || {
-                            BuildManifest::from_path(target_dir.join("package.json")).map_err(|e| {
-                                NodeMaintainerError::BuildManifestReadError(
-                                    target_dir.join("package.json"),
-                                    e,
+                            actually_extracted.fetch_add(1, atomic::Ordering::SeqCst);
+                            let target_dir = target_dir.clone();
+                            let build_mani = async_std::task::spawn_blocking(move || {
+                                BuildManifest::from_path(target_dir.join("package.json")).map_err(
+                                    |e| {
+                                        NodeMaintainerError::BuildManifestReadError(
+                                            target_dir.join("package.json"),
+                                            e,
+                                        )
+                                    },
                                 )
                             })
-                        })
-                        .await?;
-                        if build_mani.scripts.contains_key("preinstall")
-                            || build_mani.scripts.contains_key("install")
-                            || build_mani.scripts.contains_key("postinstall")
-                            || build_mani.scripts.contains_key("prepare")
-                            || !build_mani.bin.is_empty()
-                        {
-                            pending_rebuild.lock().await.insert(child_idx);
+                            .await?;
+                            if build_mani.scripts.contains_key("preinstall")
+                                || build_mani.scripts.contains_key("install")
+                                || build_mani.scripts.contains_key("postinstall")
+                                || build_mani.scripts.contains_key("prepare")
+                                || !build_mani.bin.is_empty()
+                            {
+                                pending_rebuild.lock().await.insert(child_idx);
+                            }
                         }
-                    }
 
-                    let elapsed = start.elapsed();
+                        let elapsed = start.elapsed();
 
-                    if let Some(on_extract) = &self.opts.on_extract_progress {
-                        on_extract(&graph[child_idx].package, elapsed);
-                    }
+                        if let Some(on_extract) = &self.opts.on_extract_progress {
+                            on_extract(&graph[child_idx].package, elapsed);
+                        }
 
-                    tracing::trace!(
-                        in_flight = concurrent_count.fetch_sub(1, atomic::Ordering::SeqCst) - 1,
-                        "Extracted {} to {} in {:?}ms. {}/{total} done.",
-                        graph[child_idx].package.name(),
-                        target_dir.display(),
-                        elapsed.as_micros() / 1000,
-                        total_completed.fetch_add(1, atomic::Ordering::SeqCst) + 1,
-                    );
-                    Ok::<_, NodeMaintainerError>(())
-                },
-            )
-            .await?;
+                        tracing::trace!(
+                            in_flight = concurrent_count.fetch_sub(1, atomic::Ordering::SeqCst) - 1,
+                            "Extracted {} to {} in {:?}ms. {}/{total} done.",
+                            graph[child_idx].package.name(),
+                            target_dir.display(),
+                            elapsed.as_micros() / 1000,
+                            total_completed.fetch_add(1, atomic::Ordering::SeqCst) + 1,
+                        );
+                        Ok::<_, NodeMaintainerError>(())
+                    },
+                )
+                .await?;
+        }
         let meta = node_modules.join(META_FILE_NAME);
         std::fs::write(&meta, graph.to_kdl()?.to_string())
             .io_context(|| format!("Failed to write Orogene meta file to {}.", meta.display()))?;
@@ -373,7 +422,9 @@ impl HoistedLinker {
                 async_std::fs::remove_dir_all(entry.path()).await.io_context(|| format!("Failed to remove directory at {} while clearing out existing node_modules/.bin directories.", entry.path().display()))?;
             }
         }
-        futures::stream::iter(self.pending_rebuild.lock().await.iter().copied())
+        // Every `.bin` directory was just cleared out, so bins need to be
+        // relinked for the whole tree, not just for newly-extracted packages.
+        futures::stream::iter(graph.inner.node_indices())
             .map(|idx| Ok((idx, linked.clone())))
             .try_for_each_concurrent(self.opts.concurrency, move |(idx, linked)| async move {
                 if idx == graph.root {
@@ -395,6 +446,10 @@ impl HoistedLinker {
                     parent.parent().expect("must have parent").join(".bin")
                 };
 
+                // Skip anything that didn't actually make it to disk.
+                if !package_dir.join("package.json").exists() {
+                    return Ok(());
+                }
                 let build_mani = BuildManifest::from_path(package_dir.join("package.json"))
                     .map_err(|e| {
                         NodeMaintainerError::BuildManifestReadError(
@@ -13,6 +13,7 @@ use nassun::ExtractMode;
 use oro_common::BuildManifest;
 use petgraph::{stable_graph::NodeIndex, visit::EdgeRef, Direction};
 use ssri::Integrity;
+use tracing::Instrument;
 
 use crate::{error::IoContext, graph::Graph, NodeMaintainerError, META_FILE_NAME, STORE_DIR_NAME};
 
@@ -74,6 +75,10 @@ impl IsolatedLinker {
                         entry.path().display()
                     )
                 })?;
+                if ty.is_symlink() && super::linked_package(path.as_ref(), &prefix).is_some() {
+                    tracing::debug!("Leaving linked package at {} in place.", path.display());
+                    continue;
+                }
                 if ty.is_dir() {
                     async_std::fs::remove_dir_all(&path).await.io_context(|| format!("Failed to rimraf contents of directory at {} while pruning node_modules.", entry.path().display()))?;
                 } else if ty.is_file() {
@@ -173,6 +178,17 @@ impl IsolatedLinker {
                                         entry.path().display()
                                     )
                                 })?;
+                                if ty.is_symlink()
+                                    && super::linked_package(path.as_ref(), prefix_ref).is_some()
+                                {
+                                    // Linked packages (like workspace
+                                    // packages) are managed elsewhere.
+                                    tracing::debug!(
+                                        "Leaving linked package at {} in place.",
+                                        path.display()
+                                    );
+                                    return Ok(());
+                                }
                                 if ty.is_file() {
                                     async_std::fs::remove_file(&path).await.io_context(|| {
                                         format!(
@@ -376,7 +392,7 @@ impl IsolatedLinker {
                 ))
             })
             .try_for_each_concurrent(
-                self.opts.concurrency,
+                self.opts.extract_concurrency,
                 move |(
                     child_idx,
                     concurrent_count,
@@ -404,14 +420,31 @@ impl IsolatedLinker {
 
                     let start = std::time::Instant::now();
 
-                    if !target_dir.exists() {
-                        graph[child_idx]
-                            .package
-                            .extract_to_dir(&target_dir, extract_mode)
-                            .await?;
+                    let newly_extracted = !target_dir.exists();
+                    if newly_extracted {
+                        // This covers fetching the tarball, too, since
+                        // it's streamed straight into the extraction.
+                        let span = tracing::trace_span!(
+                            "extract_package",
+                            name = %pkg.name(),
+                            resolved = %pkg.resolved()
+                        );
+                        super::extract_package(
+                            pkg,
+                            &target_dir,
+                            extract_mode,
+                            self.opts.extract_filter.as_ref(),
+                            self.opts.trim_patterns.as_deref(),
+                            self.opts.on_extract_bytes.as_ref(),
+                        )
+                        .instrument(span)
+                        .await?;
                         actually_extracted.fetch_add(1, atomic::Ordering::SeqCst);
+                    }
+
+                    let build_mani = {
                         let target_dir = target_dir.clone();
-                        let build_mani = async_std::task::spawn_blocking(move || {
+                        async_std::task::spawn_blocking(move || {
                             BuildManifest::from_path(target_dir.join("package.json")).map_err(|e| {
                                 NodeMaintainerError::BuildManifestReadError(
                                     target_dir.join("package.json"),
@@ -419,17 +452,21 @@ impl IsolatedLinker {
                                 )
                             })
                         })
-                        .await?;
-                        if build_mani.scripts.contains_key("preinstall")
+                        .await?
+                    };
+                    if newly_extracted
+                        && (build_mani.scripts.contains_key("preinstall")
                             || build_mani.scripts.contains_key("install")
                             || build_mani.scripts.contains_key("postinstall")
-                            || build_mani.scripts.contains_key("prepare")
-                        {
-                            pending_rebuild.lock().await.insert(child_idx);
-                        }
-                        if !build_mani.bin.is_empty() {
-                            pending_bin_link.lock().await.push(child_idx);
-                        }
+                            || build_mani.scripts.contains_key("prepare"))
+                    {
+                        pending_rebuild.lock().await.insert(child_idx);
+                    }
+                    // Bins get relinked even for packages that were already
+                    // in the store, in case `.bin` directories went missing
+                    // since they were extracted.
+                    if !build_mani.bin.is_empty() {
+                        pending_bin_link.lock().await.push(child_idx);
                     }
 
                     self.link_deps(
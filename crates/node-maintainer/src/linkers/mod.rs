@@ -15,7 +15,9 @@ use hoisted::HoistedLinker;
 #[cfg(not(target_arch = "wasm32"))]
 use isolated::IsolatedLinker;
 #[cfg(not(target_arch = "wasm32"))]
-use oro_common::BuildManifest;
+use nassun::{package::Package, ExtractFilter, ExtractMode};
+#[cfg(not(target_arch = "wasm32"))]
+use oro_common::{BuildManifest, WarningCollector, WarningKind};
 #[cfg(not(target_arch = "wasm32"))]
 use oro_script::OroScript;
 #[cfg(not(target_arch = "wasm32"))]
@@ -23,8 +25,8 @@ use petgraph::stable_graph::NodeIndex;
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::{
-    error::IoContext, graph::Graph, Lockfile, NodeMaintainerError, ProgressHandler, PruneProgress,
-    ScriptLineHandler, ScriptStartHandler,
+    error::IoContext, graph::Graph, ExtractBytesHandler, Lockfile, NodeMaintainerError,
+    ProgressHandler, PruneProgress, ScriptLineHandler, ScriptStartHandler,
 };
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -35,15 +37,25 @@ mod isolated;
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) struct LinkerOptions {
     pub(crate) concurrency: usize,
+    pub(crate) extract_concurrency: usize,
     pub(crate) actual_tree: Option<Lockfile>,
     pub(crate) script_concurrency: usize,
     pub(crate) cache: Option<PathBuf>,
     pub(crate) prefer_copy: bool,
+    pub(crate) bin_links: bool,
+    pub(crate) extract_filter: Option<ExtractFilter>,
+    /// Patterns for files to remove from packages after extracting them, if
+    /// installs are being trimmed.
+    pub(crate) trim_patterns: Option<Vec<String>>,
+    /// Extra environment variables for lifecycle scripts, by package name.
+    pub(crate) script_env: HashMap<String, HashMap<String, String>>,
     pub(crate) root: PathBuf,
     pub(crate) on_prune_progress: Option<PruneProgress>,
     pub(crate) on_extract_progress: Option<ProgressHandler>,
+    pub(crate) on_extract_bytes: Option<ExtractBytesHandler>,
     pub(crate) on_script_start: Option<ScriptStartHandler>,
     pub(crate) on_script_line: Option<ScriptLineHandler>,
+    pub(crate) warnings: WarningCollector,
 }
 
 pub(crate) enum Linker {
@@ -71,32 +83,74 @@ impl Linker {
         Self::Null
     }
 
+    /// The tree recorded the last time `node_modules` was written, if any.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn actual_tree(&self) -> Option<&Lockfile> {
+        match self {
+            Self::Isolated(isolated) => isolated.opts.actual_tree.as_ref(),
+            Self::Hoisted(hoisted) => hoisted.opts.actual_tree.as_ref(),
+            Self::Null => None,
+        }
+    }
+
+    /// The project root that `node_modules` gets written to.
     #[cfg(not(target_arch = "wasm32"))]
+    pub fn root(&self) -> Option<&Path> {
+        match self {
+            Self::Isolated(isolated) => Some(&isolated.opts.root),
+            Self::Hoisted(hoisted) => Some(&hoisted.opts.root),
+            Self::Null => None,
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tracing::instrument(
+        level = "trace",
+        name = "prune",
+        skip_all,
+        fields(packages = graph.inner.node_count(), pruned = tracing::field::Empty, duration_ms = tracing::field::Empty)
+    )]
     pub async fn prune(
         &self,
         #[allow(dead_code)] graph: &Graph,
     ) -> Result<usize, NodeMaintainerError> {
-        match self {
+        let start = std::time::Instant::now();
+        let count = match self {
             #[cfg(not(target_arch = "wasm32"))]
             Self::Isolated(isolated) => isolated.prune(graph).await,
             #[cfg(not(target_arch = "wasm32"))]
             Self::Hoisted(hoisted) => hoisted.prune(graph).await,
             Self::Null => Ok(0),
-        }
+        }?;
+        let span = tracing::Span::current();
+        span.record("pruned", count);
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        Ok(count)
     }
 
     #[cfg(not(target_arch = "wasm32"))]
+    #[tracing::instrument(
+        level = "trace",
+        name = "extract",
+        skip_all,
+        fields(packages = graph.inner.node_count(), extracted = tracing::field::Empty, duration_ms = tracing::field::Empty)
+    )]
     pub async fn extract(
         &self,
         #[allow(dead_code)] graph: &Graph,
     ) -> Result<usize, NodeMaintainerError> {
-        match self {
+        let start = std::time::Instant::now();
+        let count = match self {
             #[cfg(not(target_arch = "wasm32"))]
             Self::Isolated(isolated) => isolated.extract(graph).await,
             #[cfg(not(target_arch = "wasm32"))]
             Self::Hoisted(hoisted) => hoisted.extract(graph).await,
             Self::Null => Ok(0),
-        }
+        }?;
+        let span = tracing::Span::current();
+        span.record("extracted", count);
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        Ok(count)
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -110,7 +164,14 @@ impl Linker {
         if !ignore_scripts {
             self.run_scripts(graph, "preinstall").await?;
         }
-        self.link_bins(graph).await?;
+        let bin_links = match self {
+            Self::Isolated(isolated) => isolated.opts.bin_links,
+            Self::Hoisted(hoisted) => hoisted.opts.bin_links,
+            Self::Null => false,
+        };
+        if bin_links {
+            self.link_bins(graph).await?;
+        }
         if !ignore_scripts {
             self.run_scripts(graph, "install").await?;
             self.run_scripts(graph, "postinstall").await?;
@@ -274,6 +335,7 @@ impl Linker {
             let root = root.clone();
             let event = event.to_owned();
             let event_clone = event.clone();
+            let env = opts.script_env.get(&name).cloned().unwrap_or_default();
             let span = tracing::info_span!("script");
             let _span_enter = span.enter();
             if let Some(on_script_start) = &opts.on_script_start {
@@ -281,8 +343,11 @@ impl Linker {
             }
             std::mem::drop(_span_enter);
             let mut script = match async_std::task::spawn_blocking(move || {
-                OroScript::new(package_dir, event_clone)?
-                    .workspace_path(root)
+                env.into_iter()
+                    .fold(
+                        OroScript::new(package_dir, event_clone)?.workspace_path(root),
+                        |script, (key, value)| script.env(key, value),
+                    )
                     .spawn()
             })
             .await
@@ -290,7 +355,10 @@ impl Linker {
                 Ok(script) => script,
                 Err(e) if is_optional => {
                     let e: NodeMaintainerError = e.into();
-                    tracing::debug!("Error in optional dependency script: {}", e);
+                    opts.warnings.push(
+                        WarningKind::SkippedOptional,
+                        format!("{name}: {event} script failed: {e}"),
+                    );
                     return Ok(());
                 }
                 Err(e) => return Err(e.into()),
@@ -304,6 +372,7 @@ impl Linker {
             let stdout_span = span;
             let stderr_span = stdout_span.clone();
             let event_clone = event.clone();
+            let failed_event = event.clone();
             let stdout_resolved = graph[idx].package.resolved().clone();
             let stderr_resolved = stdout_resolved.clone();
             let join = futures::try_join!(
@@ -349,7 +418,10 @@ impl Linker {
             match join {
                 Ok(_) => {}
                 Err(e) if is_optional => {
-                    tracing::debug!("Error in optional dependency script: {}", e);
+                    opts.warnings.push(
+                        WarningKind::SkippedOptional,
+                        format!("{name}: {failed_event} script failed: {e}"),
+                    );
                     return Ok(());
                 }
                 Err(e) => return Err(e),
@@ -479,6 +551,116 @@ pub(crate) fn link_bin(from: &Path, to: &Path) -> Result<(), NodeMaintainerError
     Ok(())
 }
 
+/// Suffix for the directories packages get extracted into before being
+/// moved into place.
+#[cfg(not(target_arch = "wasm32"))]
+const EXTRACT_TEMP_SUFFIX: &str = ".oro-extracting";
+
+/// Extracts `package` to `target_dir`, by way of a sibling directory that's
+/// renamed into place once extraction has finished. A package that failed
+/// partway through (or whose extraction got killed) never shows up at
+/// `target_dir`. The temporary directory's name only depends on
+/// `target_dir`, so leftovers from an interrupted run get cleared out the
+/// next time around. When installs are being trimmed, `trim_patterns` are
+/// applied before the package is moved into place. `on_bytes` hears about
+/// every chunk of package data written along the way.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn extract_package(
+    package: &Package,
+    target_dir: &Path,
+    extract_mode: ExtractMode,
+    filter: Option<&ExtractFilter>,
+    trim_patterns: Option<&[String]>,
+    on_bytes: Option<&ExtractBytesHandler>,
+) -> Result<(), NodeMaintainerError> {
+    let parent = target_dir
+        .parent()
+        .expect("packages are always extracted into a node_modules directory");
+    let temp_dir = parent.join(format!(
+        ".{}{EXTRACT_TEMP_SUFFIX}",
+        target_dir
+            .file_name()
+            .expect("package directories always have a name")
+            .to_string_lossy()
+    ));
+    if async_std::path::Path::new(&temp_dir).exists().await {
+        async_std::fs::remove_dir_all(&temp_dir)
+            .await
+            .io_context(|| {
+                format!(
+                    "Failed to remove leftover extraction directory at {}.",
+                    temp_dir.display()
+                )
+            })?;
+    }
+    let mut extracted = match (filter, on_bytes) {
+        (filter, Some(on_bytes)) => {
+            let on_bytes = on_bytes.clone();
+            let pkg = package.clone();
+            package
+                .extract_to_dir_with_progress(
+                    &temp_dir,
+                    extract_mode,
+                    filter.cloned(),
+                    Arc::new(move |bytes| on_bytes(&pkg, bytes)),
+                )
+                .await
+        }
+        (Some(filter), None) => {
+            package
+                .extract_to_dir_filtered(&temp_dir, extract_mode, filter.clone())
+                .await
+        }
+        (None, None) => package.extract_to_dir(&temp_dir, extract_mode).await,
+    }
+    .map(|_| ())
+    .map_err(NodeMaintainerError::from);
+    if let (Ok(()), Some(patterns)) = (&extracted, trim_patterns) {
+        let dir = temp_dir.clone();
+        let patterns = patterns.to_vec();
+        extracted =
+            async_std::task::spawn_blocking(move || crate::trim::trim_package(&dir, &patterns))
+                .await;
+    }
+    if let Err(e) = extracted {
+        if let Err(cleanup) = async_std::fs::remove_dir_all(&temp_dir).await {
+            if cleanup.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(
+                    "Failed to clean up partially-extracted package at {}: {cleanup}",
+                    temp_dir.display()
+                );
+            }
+        }
+        return Err(e);
+    }
+    async_std::fs::rename(&temp_dir, target_dir)
+        .await
+        .io_context(|| {
+            format!(
+                "Failed to move extracted package from {} to {}.",
+                temp_dir.display(),
+                target_dir.display()
+            )
+        })
+}
+
+/// If the package at `path` is a symlink to somewhere outside of
+/// `node_modules` (for example, a workspace package that was linked in),
+/// returns the directory it points to. Linked packages are left alone when
+/// pruning and extracting.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn linked_package(path: &Path, node_modules: &Path) -> Option<PathBuf> {
+    if !path.symlink_metadata().ok()?.file_type().is_symlink() {
+        return None;
+    }
+    let target = path.canonicalize().ok()?;
+    if target.starts_with(node_modules.canonicalize().ok()?) {
+        None
+    } else {
+        Some(target)
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) fn mkdirp(
     path: &Path,
@@ -0,0 +1,88 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use fs2::FileExt;
+
+use crate::error::{IoContext, NodeMaintainerError};
+
+/// Name of the lock file in the project root that's locked while
+/// `node_modules` and the lockfile are being modified.
+pub const PROJECT_LOCK_FILE: &str = ".oro-lock";
+
+/// Advisory lock held while a project's `node_modules` and lockfile are
+/// being modified, so concurrent installs into the same project fail fast
+/// instead of corrupting the tree. The shared content cache is not covered
+/// by this lock, since cacache is already safe for concurrent use.
+///
+/// The lock is taken on [`PROJECT_LOCK_FILE`] through the OS, so it's
+/// released when this value is dropped, or as soon as the process holding
+/// it exits, even if it gets killed. The file itself is left in place.
+#[derive(Debug)]
+pub struct ProjectLock {
+    path: PathBuf,
+    file: std::fs::File,
+}
+
+impl ProjectLock {
+    /// Acquires the lock for the project at `root`, failing with
+    /// [`NodeMaintainerError::ProjectLocked`] if another process already
+    /// holds it.
+    pub fn acquire(root: impl AsRef<Path>) -> Result<Self, NodeMaintainerError> {
+        let root = root.as_ref();
+        std::fs::create_dir_all(root).io_context(|| {
+            format!(
+                "Failed to create project directory at {} while acquiring project lock.",
+                root.display()
+            )
+        })?;
+        let path = root.join(PROJECT_LOCK_FILE);
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&path)
+            .io_context(|| format!("Failed to open project lock at {}.", path.display()))?;
+        match file.try_lock_exclusive() {
+            Ok(()) => {}
+            Err(e)
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.raw_os_error() == fs2::lock_contended_error().raw_os_error() =>
+            {
+                return Err(NodeMaintainerError::ProjectLocked(path));
+            }
+            Err(e) => {
+                return Err(NodeMaintainerError::IoError(
+                    format!("Failed to lock project lock at {}.", path.display()),
+                    e,
+                ))
+            }
+        }
+        // The process id is only there to help with debugging. The OS lock
+        // is what actually keeps other processes out.
+        file.set_len(0)
+            .and_then(|_| write!(file, "{}", std::process::id()))
+            .io_context(|| {
+                format!(
+                    "Failed to write process id to project lock at {}.",
+                    path.display()
+                )
+            })?;
+        Ok(Self { path, file })
+    }
+
+    /// Path to the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ProjectLock {
+    fn drop(&mut self) {
+        if let Err(e) = self.file.unlock() {
+            tracing::warn!(
+                "Failed to release project lock at {}: {e}",
+                self.path.display()
+            );
+        }
+    }
+}
@@ -2,7 +2,7 @@ use indexmap::IndexMap;
 use kdl::{KdlDocument, KdlNode};
 use nassun::{client::Nassun, package::Package, PackageResolution};
 use node_semver::Version;
-use oro_common::CorgiManifest;
+use oro_common::{CorgiManifest, PeerDependencyMeta};
 use oro_package_spec::PackageSpec;
 use serde::{Deserialize, Serialize};
 use ssri::Integrity;
@@ -10,6 +10,13 @@ use unicase::UniCase;
 
 use crate::{error::NodeMaintainerError, graph::DepType, IntoKdl};
 
+/// Current version of the `package-lock.kdl` format. Lockfiles using older
+/// versions are migrated to this one, in-memory, when they're read.
+///
+/// * v1: the original layout.
+/// * v2: packages record their `os`, `cpu` and `libc` fields.
+pub const LOCKFILE_VERSION: u64 = 2;
+
 /// A representation of a resolved lockfile.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Lockfile {
@@ -40,55 +47,171 @@ impl Lockfile {
         version_node.push(self.version as i64);
         doc.nodes_mut().push(version_node);
         doc.nodes_mut().push(self.root.to_kdl());
-        let mut packages = self.packages.iter().collect::<Vec<_>>();
-        packages.sort_by(|(a, _), (b, _)| a.cmp(b));
-        for (_, pkg) in packages {
+        // Packages are sorted by name, then version, then path, so the output
+        // is stable regardless of the order the graph was built in. Names
+        // and paths are compared case-sensitively as a tie-breaker, since
+        // `UniCase` alone would consider `Foo` and `foo` equal.
+        let mut packages = self.packages.values().collect::<Vec<_>>();
+        packages.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then_with(|| a.name.as_str().cmp(b.name.as_str()))
+                .then_with(|| a.version.cmp(&b.version))
+                .then_with(|| {
+                    a.path
+                        .iter()
+                        .map(|p| p.as_str())
+                        .cmp(b.path.iter().map(|p| p.as_str()))
+                })
+        });
+        for pkg in packages {
             doc.nodes_mut().push(pkg.to_kdl());
         }
         doc.fmt();
         doc
     }
 
+    /// JSON Schema describing `package-lock.kdl`, for use by external
+    /// validators and editors.
+    ///
+    /// KDL nodes are described as JSON properties named after the node: a
+    /// node's single argument is its value, and a node's children are an
+    /// object. `pkg` nodes can repeat, so they're described as an array,
+    /// where each package's `path` holds the node's arguments.
+    pub fn json_schema() -> serde_json::Value {
+        let dependencies = serde_json::json!({
+            "type": "object",
+            "description": "Map of dependency names to the requested specifier.",
+            "additionalProperties": { "type": "string" },
+        });
+        let mut node_properties = serde_json::json!({
+            "version": {
+                "type": "string",
+                "description": "Semver version of the package.",
+            },
+            "resolved": {
+                "type": "string",
+                "description": "Where the package was resolved to: a tarball URL, a git URL, or a local path.",
+            },
+            "integrity": {
+                "type": "string",
+                "description": "Subresource Integrity string for the package's tarball.",
+            },
+        });
+        for key in PLATFORM_KEYS {
+            node_properties[key] = serde_json::json!({
+                "type": "array",
+                "description": format!("The package's `{key}` field, which limits where it's installed."),
+                "items": { "type": "string" },
+            });
+        }
+        for dep_type in &DEP_TYPES {
+            node_properties[dep_type_key(dep_type)] = dependencies.clone();
+        }
+        node_properties[PEER_DEPS_META_KEY] = serde_json::json!({
+            "type": "object",
+            "description": "Peer dependencies that are marked optional, each set to \"optional\".",
+            "additionalProperties": { "const": "optional" },
+        });
+        let mut pkg_properties = node_properties.clone();
+        pkg_properties["path"] = serde_json::json!({
+            "type": "array",
+            "description": "Names of the packages leading up to this one in `node_modules`, ending with its own name.",
+            "items": { "type": "string" },
+            "minItems": 1,
+        });
+        serde_json::json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "package-lock.kdl",
+            "description": "Orogene lockfile, describing a fully-resolved dependency tree.",
+            "type": "object",
+            "required": ["lockfile-version", "root"],
+            "properties": {
+                "lockfile-version": {
+                    "type": "integer",
+                    "description": "Version of the lockfile format.",
+                    "const": LOCKFILE_VERSION,
+                },
+                "root": {
+                    "type": "object",
+                    "description": "The project itself.",
+                    "properties": node_properties,
+                    "additionalProperties": false,
+                },
+                "pkg": {
+                    "type": "array",
+                    "description": "Every package installed in the tree.",
+                    "items": {
+                        "type": "object",
+                        "properties": pkg_properties,
+                        "required": ["path"],
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "additionalProperties": false,
+        })
+    }
+
     pub fn from_kdl(kdl: impl IntoKdl) -> Result<Self, NodeMaintainerError> {
         let kdl: KdlDocument = kdl.into_kdl()?;
         fn inner(kdl: KdlDocument) -> Result<Lockfile, NodeMaintainerError> {
-            let packages = kdl
-                .nodes()
-                .iter()
-                .filter(|node| node.name().to_string() == "pkg")
-                .map(|node| LockfileNode::from_kdl(node, false))
-                .map(|node| {
-                    let node = node?;
-                    let path_str = node
-                        .path
-                        .iter()
-                        .map(|x| x.to_string())
-                        .collect::<Vec<_>>()
-                        .join("/node_modules/");
-                    Ok((UniCase::from(path_str), node))
-                })
-                .collect::<Result<IndexMap<UniCase<String>, LockfileNode>, NodeMaintainerError>>(
-                )?;
-            Ok(Lockfile {
-                version: kdl
-                    .get_arg("lockfile-version")
-                    .and_then(|v| v.as_i64())
-                    .map(|v| v.try_into())
-                    .transpose()
-                    // TODO: add a miette span here
-                    .map_err(|_| NodeMaintainerError::InvalidLockfileVersion)?
-                    .unwrap_or(1),
-                root: kdl
-                    .get("root")
-                    // TODO: add a miette span here
-                    .ok_or_else(|| NodeMaintainerError::KdlLockMissingRoot(kdl.clone()))
-                    .and_then(|node| LockfileNode::from_kdl(node, true))?,
-                packages,
-            })
+            let version: Option<u64> = kdl
+                .get_arg("lockfile-version")
+                .and_then(|v| v.as_i64())
+                .map(|v| v.try_into())
+                .transpose()
+                // TODO: add a miette span here
+                .map_err(|_| NodeMaintainerError::InvalidLockfileVersion)?;
+            match version {
+                // Lockfiles written before we started versioning them have no
+                // `lockfile-version` node, but otherwise share v1's layout.
+                None | Some(1) => Lockfile::from_kdl_v1(&kdl),
+                Some(2) => Lockfile::from_kdl_v2(&kdl),
+                Some(version) => Err(NodeMaintainerError::UnsupportedLockfileVersion(version)),
+            }
         }
         inner(kdl)
     }
 
+    /// v1 is v2 without any platform info, so it's read the same way, with
+    /// every package's platform marked as unknown.
+    fn from_kdl_v1(kdl: &KdlDocument) -> Result<Self, NodeMaintainerError> {
+        let mut lockfile = Self::from_kdl_v2(kdl)?;
+        for node in std::iter::once(&mut lockfile.root).chain(lockfile.packages.values_mut()) {
+            node.platform_known = false;
+        }
+        Ok(lockfile)
+    }
+
+    fn from_kdl_v2(kdl: &KdlDocument) -> Result<Self, NodeMaintainerError> {
+        let packages = kdl
+            .nodes()
+            .iter()
+            .filter(|node| node.name().to_string() == "pkg")
+            .map(|node| LockfileNode::from_kdl(node, false))
+            .map(|node| {
+                let node = node?;
+                let path_str = node
+                    .path
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join("/node_modules/");
+                Ok((UniCase::from(path_str), node))
+            })
+            .collect::<Result<IndexMap<UniCase<String>, LockfileNode>, NodeMaintainerError>>()?;
+        Ok(Lockfile {
+            version: LOCKFILE_VERSION,
+            root: kdl
+                .get("root")
+                // TODO: add a miette span here
+                .ok_or_else(|| NodeMaintainerError::KdlLockMissingRoot(kdl.clone()))
+                .and_then(|node| LockfileNode::from_kdl(node, true))?,
+            packages,
+        })
+    }
+
     pub fn from_npm(npm: impl AsRef<str>) -> Result<Self, NodeMaintainerError> {
         let pkglock: NpmPackageLock = serde_json::from_str(npm.as_ref())?;
         fn inner(npm: NpmPackageLock) -> Result<Lockfile, NodeMaintainerError> {
@@ -128,6 +251,26 @@ impl Lockfile {
     }
 }
 
+/// Dependency types, in the order they're written to a lockfile node.
+const DEP_TYPES: [DepType; 4] = [DepType::Prod, DepType::Dev, DepType::Peer, DepType::Opt];
+
+/// Manifest fields that restrict which platforms a package is installed on.
+const PLATFORM_KEYS: [&str; 3] = ["os", "cpu", "libc"];
+
+/// Name of the child node that lists which peer dependencies are optional.
+const PEER_DEPS_META_KEY: &str = "peer-dependencies-meta";
+
+/// Name of the child node that holds dependencies of type `dep_type`.
+fn dep_type_key(dep_type: &DepType) -> &'static str {
+    use DepType::*;
+    match dep_type {
+        Prod => "dependencies",
+        Dev => "dev-dependencies",
+        Peer => "peer-dependencies",
+        Opt => "optional-dependencies",
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct LockfileNode {
     pub name: UniCase<String>,
@@ -139,7 +282,15 @@ pub struct LockfileNode {
     pub dependencies: IndexMap<String, String>,
     pub dev_dependencies: IndexMap<String, String>,
     pub peer_dependencies: IndexMap<String, String>,
+    pub peer_dependencies_meta: IndexMap<String, PeerDependencyMeta>,
     pub optional_dependencies: IndexMap<String, String>,
+    pub os: Vec<String>,
+    pub cpu: Vec<String>,
+    pub libc: Vec<String>,
+    /// Whether `os`, `cpu` and `libc` were actually recorded for this
+    /// package. Lockfiles from before v2 didn't record them, so they're
+    /// unknown, rather than unrestricted.
+    pub platform_known: bool,
 }
 
 impl From<LockfileNode> for CorgiManifest {
@@ -151,7 +302,14 @@ impl From<LockfileNode> for CorgiManifest {
             dev_dependencies: value.dev_dependencies,
             peer_dependencies: value.peer_dependencies,
             optional_dependencies: value.optional_dependencies,
+            peer_dependencies_meta: value.peer_dependencies_meta,
+            resolutions: IndexMap::new(),
+            catalog: IndexMap::new(),
+            catalogs: IndexMap::new(),
             bundled_dependencies: None,
+            os: value.os,
+            cpu: value.cpu,
+            libc: value.libc,
         }
     }
 }
@@ -179,7 +337,7 @@ impl LockfileNode {
                     name: self.name.to_string(),
                     path: path.clone(),
                 };
-                nassun.resolve_from(self.name.to_string(), spec, resolution)
+                nassun.resolve_from(self.name.to_string(), spec, resolution)?
             }
             PackageSpec::Npm { name, .. } => {
                 let version = if let Some(ref version) = self.version {
@@ -196,7 +354,7 @@ impl LockfileNode {
                             .map_err(|e| NodeMaintainerError::UrlParseError(url.clone(), e))?,
                         integrity: self.integrity.clone(),
                     };
-                    nassun.resolve_from(self.name.to_string(), spec, resolution)
+                    nassun.resolve_from(self.name.to_string(), spec, resolution)?
                 } else {
                     nassun.resolve(spec.to_string()).await?
                 }
@@ -207,7 +365,7 @@ impl LockfileNode {
                         name: self.name.to_string(),
                         info: info.clone(),
                     };
-                    nassun.resolve_from(self.name.to_string(), spec, resolution)
+                    nassun.resolve_from(self.name.to_string(), spec, resolution)?
                 } else {
                     nassun.resolve(spec.to_string()).await?
                 }
@@ -215,6 +373,8 @@ impl LockfileNode {
             PackageSpec::Alias { .. } => {
                 unreachable!("Alias should have already been resolved by the .target() call above.")
             }
+            // Lockfiles always record what a catalog entry resolved to.
+            PackageSpec::Catalog { .. } => return Ok(None),
         };
         Ok(Some(package))
     }
@@ -272,6 +432,11 @@ impl LockfileNode {
             dev_dependencies: Self::from_kdl_deps(&children, &DepType::Dev)?,
             optional_dependencies: Self::from_kdl_deps(&children, &DepType::Opt)?,
             peer_dependencies: Self::from_kdl_deps(&children, &DepType::Peer)?,
+            peer_dependencies_meta: Self::from_kdl_peers_meta(&children),
+            os: Self::from_kdl_platform(&children, "os"),
+            cpu: Self::from_kdl_platform(&children, "cpu"),
+            libc: Self::from_kdl_platform(&children, "libc"),
+            platform_known: true,
         })
     }
 
@@ -279,13 +444,7 @@ impl LockfileNode {
         children: &KdlDocument,
         dep_type: &DepType,
     ) -> Result<IndexMap<String, String>, NodeMaintainerError> {
-        use DepType::*;
-        let type_name = match dep_type {
-            Prod => "dependencies",
-            Dev => "dev-dependencies",
-            Peer => "peer-dependencies",
-            Opt => "optional-dependencies",
-        };
+        let type_name = dep_type_key(dep_type);
         let mut deps = IndexMap::new();
         if let Some(node) = children.get(type_name) {
             if let Some(children) = node.children() {
@@ -299,6 +458,41 @@ impl LockfileNode {
         Ok(deps)
     }
 
+    /// Reads one of the `os`, `cpu` or `libc` nodes, whose arguments are
+    /// the values from the package's manifest.
+    fn from_kdl_platform(children: &KdlDocument, key: &str) -> Vec<String> {
+        children
+            .get(key)
+            .map(|node| {
+                node.entries()
+                    .iter()
+                    .filter(|e| e.name().is_none())
+                    .filter_map(|e| e.value().as_string())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Reads `peer-dependencies-meta`, where each optional peer is written
+    /// as `name "optional"`.
+    fn from_kdl_peers_meta(children: &KdlDocument) -> IndexMap<String, PeerDependencyMeta> {
+        let mut meta = IndexMap::new();
+        if let Some(children) = children
+            .get(PEER_DEPS_META_KEY)
+            .and_then(|node| node.children())
+        {
+            for dep in children.nodes() {
+                let optional = dep.get(0).and_then(|val| val.as_string()) == Some("optional");
+                meta.insert(
+                    dep.name().value().to_string(),
+                    PeerDependencyMeta { optional },
+                );
+            }
+        }
+        meta
+    }
+
     fn to_kdl(&self) -> KdlNode {
         let mut kdl_node = if self.is_root {
             KdlNode::new("root")
@@ -326,41 +520,55 @@ impl LockfileNode {
                 }
             }
         }
-        if !self.dependencies.is_empty() {
-            kdl_node
-                .ensure_children()
-                .nodes_mut()
-                .push(self.to_kdl_deps(&DepType::Prod, &self.dependencies));
-        }
-        if !self.dev_dependencies.is_empty() {
-            kdl_node
-                .ensure_children()
-                .nodes_mut()
-                .push(self.to_kdl_deps(&DepType::Dev, &self.dev_dependencies));
+        for (key, values) in PLATFORM_KEYS.iter().zip([&self.os, &self.cpu, &self.libc]) {
+            if !values.is_empty() {
+                let mut pnode = KdlNode::new(*key);
+                for value in values {
+                    pnode.push(value.clone());
+                }
+                kdl_node.ensure_children().nodes_mut().push(pnode);
+            }
         }
-        if !self.peer_dependencies.is_empty() {
-            kdl_node
-                .ensure_children()
-                .nodes_mut()
-                .push(self.to_kdl_deps(&DepType::Peer, &self.peer_dependencies));
+        for dep_type in &DEP_TYPES {
+            let deps = self.deps(dep_type);
+            if !deps.is_empty() {
+                kdl_node
+                    .ensure_children()
+                    .nodes_mut()
+                    .push(self.to_kdl_deps(dep_type, deps));
+            }
         }
-        if !self.optional_dependencies.is_empty() {
-            kdl_node
-                .ensure_children()
-                .nodes_mut()
-                .push(self.to_kdl_deps(&DepType::Opt, &self.optional_dependencies));
+        let mut optional_peers = self
+            .peer_dependencies_meta
+            .iter()
+            .filter(|(_, meta)| meta.optional)
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        if !optional_peers.is_empty() {
+            optional_peers.sort();
+            let mut meta_node = KdlNode::new(PEER_DEPS_META_KEY);
+            for name in optional_peers {
+                let mut pnode = KdlNode::new(name.clone());
+                pnode.push("optional");
+                meta_node.ensure_children().nodes_mut().push(pnode);
+            }
+            kdl_node.ensure_children().nodes_mut().push(meta_node);
         }
         kdl_node
     }
 
-    fn to_kdl_deps(&self, dep_type: &DepType, deps: &IndexMap<String, String>) -> KdlNode {
+    fn deps(&self, dep_type: &DepType) -> &IndexMap<String, String> {
         use DepType::*;
-        let type_name = match dep_type {
-            Prod => "dependencies",
-            Dev => "dev-dependencies",
-            Peer => "peer-dependencies",
-            Opt => "optional-dependencies",
-        };
+        match dep_type {
+            Prod => &self.dependencies,
+            Dev => &self.dev_dependencies,
+            Peer => &self.peer_dependencies,
+            Opt => &self.optional_dependencies,
+        }
+    }
+
+    fn to_kdl_deps(&self, dep_type: &DepType, deps: &IndexMap<String, String>) -> KdlNode {
+        let type_name = dep_type_key(dep_type);
         let mut deps_node = KdlNode::new(type_name);
         for (name, requested) in deps {
             let children = deps_node.ensure_children();
@@ -416,6 +624,11 @@ impl LockfileNode {
             dev_dependencies: npm.dev_dependencies.clone(),
             optional_dependencies: npm.optional_dependencies.clone(),
             peer_dependencies: npm.peer_dependencies.clone(),
+            peer_dependencies_meta: npm.peer_dependencies_meta.clone(),
+            os: npm.os.clone(),
+            cpu: npm.cpu.clone(),
+            libc: npm.libc.clone(),
+            platform_known: true,
         })
     }
 }
@@ -450,4 +663,12 @@ pub struct NpmPackageLockEntry {
     pub optional_dependencies: IndexMap<String, String>,
     #[serde(default)]
     pub peer_dependencies: IndexMap<String, String>,
+    #[serde(default)]
+    pub peer_dependencies_meta: IndexMap<String, PeerDependencyMeta>,
+    #[serde(default)]
+    pub os: Vec<String>,
+    #[serde(default)]
+    pub cpu: Vec<String>,
+    #[serde(default)]
+    pub libc: Vec<String>,
 }
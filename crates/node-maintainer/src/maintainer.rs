@@ -1,23 +1,33 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
-#[cfg(not(target_arch = "wasm32"))]
-use async_std::fs;
 use nassun::client::{Nassun, NassunOpts};
+#[cfg(not(target_arch = "wasm32"))]
+use nassun::package::ExtractFilter;
 use nassun::package::Package;
-use oro_common::CorgiManifest;
+use oro_common::{CorgiManifest, Warning, WarningCollector, WarningKind};
+use ssri::Integrity;
 use unicase::UniCase;
 use url::Url;
 
+use crate::catalogs::Catalogs;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::debug_dump::{write_resolver_dump, ResolverInputs};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::error::IoContext;
 use crate::error::NodeMaintainerError;
-use crate::graph::{Graph, Node};
+use crate::graph::{Graph, Node, PeerConflict};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::graph::TreeDrift;
 use crate::linkers::Linker;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::linkers::LinkerOptions;
-use crate::resolver::Resolver;
+use crate::platform::Platform;
+use crate::resolutions::Resolutions;
+use crate::resolver::{HoistMode, Resolver};
 use crate::{IntoKdl, Lockfile};
 
 pub const DEFAULT_CONCURRENCY: usize = 50;
@@ -28,8 +38,22 @@ pub const META_FILE_NAME: &str = ".orogene-meta.kdl";
 #[cfg(not(target_arch = "wasm32"))]
 pub const STORE_DIR_NAME: &str = ".oro-store";
 
+/// Files and directories left out of packages by
+/// [`NodeMaintainerOptions::trim_install`], unless other patterns are given
+/// with [`NodeMaintainerOptions::trim_patterns`].
+#[cfg(not(target_arch = "wasm32"))]
+pub const DEFAULT_TRIM_PATTERNS: &[&str] = &[
+    "/test/",
+    "/tests/",
+    "__tests__/",
+    "/.github/",
+    "*.md",
+    "*.map",
+];
+
 pub type ProgressAdded = Arc<dyn Fn() + Send + Sync>;
 pub type ProgressHandler = Arc<dyn Fn(&Package, Duration) + Send + Sync>;
+pub type ExtractBytesHandler = Arc<dyn Fn(&Package, usize) + Send + Sync>;
 pub type PruneProgress = Arc<dyn Fn(&Path) + Send + Sync>;
 pub type ScriptStartHandler = Arc<dyn Fn(&Package, &str) + Send + Sync>;
 pub type ScriptLineHandler = Arc<dyn Fn(&str) + Send + Sync>;
@@ -39,7 +63,22 @@ pub struct NodeMaintainerOptions {
     nassun_opts: NassunOpts,
     nassun: Option<Nassun>,
     concurrency: usize,
+    extract_concurrency: Option<usize>,
     locked: bool,
+    update: Option<Vec<UniCase<String>>>,
+    offline_first: bool,
+    auto_install_peers: bool,
+    dedupe_peer_deps: bool,
+    legacy_peer_deps: bool,
+    allow_peer_conflicts: bool,
+    hoist: HoistMode,
+    max_depth: Option<usize>,
+    platform: Platform,
+    warnings: WarningCollector,
+    fix_lockfile: bool,
+    strict_layout: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    debug_resolver: Option<PathBuf>,
     kdl_lock: Option<Lockfile>,
     npm_lock: Option<Lockfile>,
 
@@ -50,8 +89,20 @@ pub struct NodeMaintainerOptions {
     #[allow(dead_code)]
     cache: Option<PathBuf>,
     #[allow(dead_code)]
+    store_dir: Option<PathBuf>,
+    #[allow(dead_code)]
     prefer_copy: bool,
     #[allow(dead_code)]
+    bin_links: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    extract_filter: Option<ExtractFilter>,
+    #[cfg(not(target_arch = "wasm32"))]
+    trim_install: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    trim_patterns: Option<Vec<String>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    script_env: HashMap<String, HashMap<String, String>>,
+    #[allow(dead_code)]
     validate: bool,
     #[allow(dead_code)]
     root: Option<PathBuf>,
@@ -64,6 +115,8 @@ pub struct NodeMaintainerOptions {
     #[allow(dead_code)]
     on_extract_progress: Option<ProgressHandler>,
     #[allow(dead_code)]
+    on_extract_bytes: Option<ExtractBytesHandler>,
+    #[allow(dead_code)]
     on_script_start: Option<ScriptStartHandler>,
     #[allow(dead_code)]
     on_script_line: Option<ScriptLineHandler>,
@@ -83,6 +136,27 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Configure where the content-addressable store lives, separately from
+    /// the metadata cache. Defaults to the [`NodeMaintainerOptions::cache`]
+    /// directory.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn store_dir(mut self, store_dir: impl AsRef<Path>) -> Self {
+        self.nassun_opts = self.nassun_opts.store_dir(store_dir.as_ref());
+        self.store_dir = Some(PathBuf::from(store_dir.as_ref()));
+        self
+    }
+
+    /// Configure where packuments and other HTTP responses are cached,
+    /// separately from the content store. Defaults to the
+    /// [`NodeMaintainerOptions::cache`] directory.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn metadata_cache_dir(mut self, metadata_cache_dir: impl AsRef<Path>) -> Self {
+        self.nassun_opts = self
+            .nassun_opts
+            .metadata_cache_dir(metadata_cache_dir.as_ref());
+        self
+    }
+
     /// Controls number of concurrent operations during various apply steps
     /// (resolution fetches, extractions, etc). Tuning this might help reduce
     /// memory usage.
@@ -91,6 +165,15 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Controls number of packages that will be extracted at the same time.
+    /// Each extraction keeps several files open, so lowering this can help
+    /// avoid running out of file descriptors on large trees. Defaults to
+    /// the value of `concurrency`.
+    pub fn extract_concurrency(mut self, concurrency: usize) -> Self {
+        self.extract_concurrency = Some(concurrency);
+        self
+    }
+
     /// Make the resolver error if the newly-resolved tree would defer from
     /// an existing lockfile.
     pub fn locked(mut self, locked: bool) -> Self {
@@ -98,6 +181,162 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Re-resolve the named packages to the newest versions allowed by their
+    /// dependents, instead of reusing the versions recorded in the lockfile
+    /// or an existing `node_modules`. If `names` is empty, every package is
+    /// re-resolved.
+    pub fn update<S: AsRef<str>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.update = Some(
+            names
+                .into_iter()
+                .map(|name| UniCase::new(name.as_ref().to_string()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Resolve packages from the lockfile whenever possible, even for
+    /// dist-tags and git dependencies that would normally be re-checked
+    /// against their source. When the lockfile covers the whole manifest, no
+    /// registry metadata is requested at all.
+    pub fn offline_first(mut self, offline_first: bool) -> Self {
+        self.offline_first = offline_first;
+        self
+    }
+
+    /// Install peer dependencies that nothing else in the tree provides,
+    /// like npm v7+ does, using the version each package asked for. Peers
+    /// marked optional through `peerDependenciesMeta` are left out. Defaults
+    /// to `true`.
+    pub fn auto_install_peers(mut self, auto_install_peers: bool) -> Self {
+        self.auto_install_peers = auto_install_peers;
+        self
+    }
+
+    /// Install missing peer dependencies, picking a single version for each
+    /// of them that satisfies every package that asked for it. Peers that no
+    /// one version can satisfy are still reported as conflicts.
+    pub fn dedupe_peer_deps(mut self, dedupe_peer_deps: bool) -> Self {
+        self.dedupe_peer_deps = dedupe_peer_deps;
+        self
+    }
+
+    /// Resolve the tree the way npm did before v7: never install missing
+    /// peer dependencies, and only warn about peers resolved to versions
+    /// their dependents don't accept, instead of failing. Useful for older
+    /// trees that can't be resolved otherwise.
+    pub fn legacy_peer_deps(mut self, legacy_peer_deps: bool) -> Self {
+        self.legacy_peer_deps = legacy_peer_deps;
+        self
+    }
+
+    /// Record peers resolved to versions their dependents don't accept as
+    /// warnings, instead of failing. Unlike
+    /// [`NodeMaintainerOptions::legacy_peer_deps`], peers are otherwise
+    /// resolved as usual, and callers can decide which conflicts matter to
+    /// them with [`NodeMaintainer::peer_conflicts`].
+    pub fn allow_peer_conflicts(mut self, allow_peer_conflicts: bool) -> Self {
+        self.allow_peer_conflicts = allow_peer_conflicts;
+        self
+    }
+
+    /// Controls how far up the tree dependencies are placed. Defaults to
+    /// [`HoistMode::Full`]. Use [`HoistMode::None`] to keep transitive
+    /// dependencies out of the top-level `node_modules`, so they can't be
+    /// required by accident.
+    pub fn hoist(mut self, hoist: HoistMode) -> Self {
+        self.hoist = hoist;
+        self
+    }
+
+    /// Resolve optional dependencies for a different operating system than
+    /// the current one, using Node's names for it (`darwin`, `linux`,
+    /// `win32`, etc). See [`KNOWN_OS`](crate::KNOWN_OS). Targeting Linux
+    /// from elsewhere assumes `glibc`, unless
+    /// [`NodeMaintainerOptions::target_libc`] says otherwise.
+    pub fn target_os(mut self, os: impl AsRef<str>) -> Self {
+        self.platform.os = os.as_ref().into();
+        if self.platform.os != "linux" {
+            self.platform.libc = None;
+        } else if self.platform.libc.is_none() {
+            self.platform.libc = Some("glibc".into());
+        }
+        self
+    }
+
+    /// Resolve optional dependencies for a different CPU architecture than
+    /// the current one, using Node's names for it (`x64`, `arm64`, etc). See
+    /// [`KNOWN_CPU`](crate::KNOWN_CPU).
+    pub fn target_cpu(mut self, cpu: impl AsRef<str>) -> Self {
+        self.platform.cpu = cpu.as_ref().into();
+        self
+    }
+
+    /// Resolve optional dependencies for a different C standard library
+    /// (`glibc` or `musl`) than the current one. Only packages installed for
+    /// Linux are checked against this.
+    pub fn target_libc(mut self, libc: impl AsRef<str>) -> Self {
+        self.platform.libc = Some(libc.as_ref().into());
+        self
+    }
+
+    /// Records warnings in `warnings` instead of a fresh collector, so
+    /// callers can gather them from several sources into one report. Only
+    /// affects the [`Nassun`] instance if it's built by these options.
+    pub fn warnings(mut self, warnings: WarningCollector) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Discard a `package-lock.kdl` that can't be parsed and resolve as if it
+    /// weren't there, instead of failing with
+    /// [`NodeMaintainerError::CorruptLockfile`].
+    pub fn fix_lockfile(mut self, fix_lockfile: bool) -> Self {
+        self.fix_lockfile = fix_lockfile;
+        self
+    }
+
+    /// Check that no two different packages in the resolved tree would be
+    /// written to the same path in `node_modules`, and error out if they
+    /// would, instead of letting one silently overwrite the other.
+    pub fn strict_layout(mut self, strict_layout: bool) -> Self {
+        self.strict_layout = strict_layout;
+        self
+    }
+
+    /// Write a JSON dump of everything the resolver worked with to `path`
+    /// once resolution is done: its inputs, the versions available for each
+    /// package, the versions it picked, and the final graph. Credentials in
+    /// URLs are scrubbed, so the dump can be attached to bug reports.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn debug_resolver(mut self, path: impl AsRef<Path>) -> Self {
+        self.debug_resolver = Some(path.as_ref().into());
+        self
+    }
+
+    /// Inputs to record in the `debug_resolver` dump, if one was asked for.
+    /// The root manifest is left for the caller to fill in.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn resolver_dump_inputs(&self, used_lockfile: bool) -> Option<ResolverInputs> {
+        self.debug_resolver.as_ref()?;
+        Some(ResolverInputs {
+            root: CorgiManifest::default(),
+            used_lockfile,
+            locked: self.locked,
+            update: self
+                .update
+                .as_ref()
+                .map(|names| names.iter().map(|name| name.to_string()).collect()),
+            offline_first: self.offline_first,
+            auto_install_peers: self.auto_install_peers,
+            dedupe_peer_deps: self.dedupe_peer_deps,
+            legacy_peer_deps: self.legacy_peer_deps,
+            hoist: format!("{:?}", self.hoist),
+            max_depth: self.max_depth,
+            platform: self.platform.clone(),
+        })
+    }
+
     /// Controls number of concurrent script executions while running
     /// `run_script`. This option is separate from `concurrency` because
     /// executing concurrent scripts is a much heavier operation.
@@ -106,6 +345,23 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Set an environment variable for `package`'s lifecycle scripts, on
+    /// top of the usual ones. Some packages, like `sharp`, read their build
+    /// settings from the environment while installing.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn script_env(
+        mut self,
+        package: impl Into<String>,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.script_env
+            .entry(package.into())
+            .or_default()
+            .insert(key.into(), value.into());
+        self
+    }
+
     /// Configure the KDL lockfile that NodeMaintainer will use.
     ///
     /// If this option is not specified, NodeMaintainer will try to read the
@@ -183,6 +439,32 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Whether version ranges can resolve to pre-release versions, even if
+    /// the range doesn't mention a pre-release itself. Defaults to `false`.
+    pub fn allow_prerelease(mut self, allow_prerelease: bool) -> Self {
+        self.nassun_opts = self.nassun_opts.allow_prerelease(allow_prerelease);
+        self
+    }
+
+    /// Directory of pre-downloaded `.tgz` files (for example, a mirror of
+    /// [`NodeMaintainer::tarball_urls`]) to extract packages from instead of
+    /// downloading them. Tarballs missing from the directory are an error
+    /// unless [`NodeMaintainerOptions::allow_network_fallback`] is set.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn local_tarball_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.nassun_opts = self.nassun_opts.local_tarball_dir(dir);
+        self
+    }
+
+    /// Whether tarballs missing from
+    /// [`NodeMaintainerOptions::local_tarball_dir`] can be downloaded from
+    /// the registry instead. Defaults to `false`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn allow_network_fallback(mut self, allow: bool) -> Self {
+        self.nassun_opts = self.nassun_opts.allow_network_fallback(allow);
+        self
+    }
+
     /// Provide a pre-configured Nassun instance. Using this option will
     /// disable all other nassun-related configurations.
     pub fn nassun(mut self, nassun: Nassun) -> Self {
@@ -202,6 +484,58 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Whether to link package executables into `node_modules/.bin`
+    /// directories while rebuilding. Defaults to `true`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn bin_links(mut self, bin_links: bool) -> Self {
+        self.bin_links = bin_links;
+        self
+    }
+
+    /// Only write out package files for which `filter` returns `true`. The
+    /// filter receives each file's path relative to its package's root. For
+    /// example, this can be used to skip `*.md` files or `test/` directories.
+    /// `package.json` files are always written.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn extract_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&Path) -> bool + Send + Sync + 'static,
+    {
+        self.extract_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Leave out files that packages rarely need at runtime, like tests,
+    /// docs, and source maps, right after extracting them. What gets left out
+    /// is decided by [`DEFAULT_TRIM_PATTERNS`], or by
+    /// [`NodeMaintainerOptions::trim_patterns`] if it was called.
+    ///
+    /// Trimming is conservative: a package's `package.json`, its license
+    /// files, and its `main` and `bin` files (along with the directories
+    /// they're in) are always kept. Packages with `"orogene": { "trim":
+    /// false }` in their `package.json` aren't trimmed at all.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn trim_install(mut self, trim_install: bool) -> Self {
+        self.trim_install = trim_install;
+        self
+    }
+
+    /// Patterns for the files and directories to leave out when
+    /// [`NodeMaintainerOptions::trim_install`] is on, instead of
+    /// [`DEFAULT_TRIM_PATTERNS`]. Patterns are matched against file and
+    /// directory names at any depth, with `*` matching any characters. A
+    /// trailing `/` only matches directories, and a leading `/` only matches
+    /// at the top of the package.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn trim_patterns<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.trim_patterns = Some(patterns.into_iter().map(Into::into).collect());
+        self
+    }
+
     /// Use the hoisted installation mode, where all dependencies and their
     /// transitive dependencies are installed as high up in the `node_modules`
     /// tree as possible. This can potentially mean that packages have access
@@ -230,6 +564,33 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Trusts an additional root certificate, in DER format, when talking to
+    /// registries.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_root_certificate(
+        mut self,
+        der: impl AsRef<[u8]>,
+    ) -> Result<Self, NodeMaintainerError> {
+        self.nassun_opts = self.nassun_opts.add_root_certificate(der)?;
+        Ok(self)
+    }
+
+    /// Trusts every certificate in the PEM-encoded CA bundle at `path` when
+    /// talking to registries.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn ca_bundle(mut self, path: impl AsRef<Path>) -> Result<Self, NodeMaintainerError> {
+        self.nassun_opts = self.nassun_opts.ca_bundle(path)?;
+        Ok(self)
+    }
+
+    /// Skips TLS certificate validation entirely, which lets anyone on the
+    /// network impersonate a registry. Only use this for testing.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.nassun_opts = self.nassun_opts.danger_accept_invalid_certs(accept);
+        self
+    }
+
     pub fn on_resolution_added<F>(mut self, f: F) -> Self
     where
         F: Fn() + Send + Sync + 'static,
@@ -264,6 +625,18 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Called with a package and a byte count every time a chunk of that
+    /// package's contents is written out while it's being extracted. Useful
+    /// for reporting download progress for individual tarballs.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn on_extract_bytes<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Package, usize) + Send + Sync + 'static,
+    {
+        self.on_extract_bytes = Some(Arc::new(f));
+        self
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub fn on_script_start<F>(mut self, f: F) -> Self
     where
@@ -293,13 +666,26 @@ impl NodeMaintainerOptions {
         if let Some(root) = &self.root {
             let kdl_lock = root.join("package-lock.kdl");
             if kdl_lock.exists() {
-                match async_std::fs::read_to_string(&kdl_lock)
+                let contents = async_std::fs::read_to_string(&kdl_lock)
                     .await
-                    .io_context(|| format!("Failed to read {}", kdl_lock.display()))
-                    .and_then(Lockfile::from_kdl)
-                {
+                    .io_context(|| format!("Failed to read {}", kdl_lock.display()))?;
+                // Unlike the npm lockfiles below, a broken `package-lock.kdl`
+                // is an error, since we're the ones that wrote it.
+                match Lockfile::from_kdl(contents) {
                     Ok(lock) => return Ok(Some(lock)),
-                    Err(e) => tracing::debug!("Failed to parse existing package-lock.kdl: {}", e),
+                    Err(e @ NodeMaintainerError::UnsupportedLockfileVersion(_)) => return Err(e),
+                    Err(e) if self.fix_lockfile => {
+                        self.warnings.push(
+                            WarningKind::Lockfile,
+                            format!(
+                                "Discarded {}, which could not be parsed: {e}",
+                                kdl_lock.display()
+                            ),
+                        );
+                    }
+                    Err(e) => {
+                        return Err(NodeMaintainerError::CorruptLockfile(kdl_lock, Box::new(e)))
+                    }
                 }
             }
             let npm_lock = root.join("package-lock.json");
@@ -336,16 +722,39 @@ impl NodeMaintainerOptions {
         root: CorgiManifest,
     ) -> Result<NodeMaintainer, NodeMaintainerError> {
         let lockfile = self.get_lockfile().await?;
-        let nassun = self.nassun.unwrap_or_else(|| self.nassun_opts.build());
+        #[cfg(not(target_arch = "wasm32"))]
+        let dump_inputs =
+            self.resolver_dump_inputs(lockfile.is_some())
+                .map(|inputs| ResolverInputs {
+                    root: root.clone(),
+                    ..inputs
+                });
+        let nassun = self
+            .nassun
+            .unwrap_or_else(|| self.nassun_opts.warnings(self.warnings.clone()).build());
         let root_pkg = Nassun::dummy_from_manifest(root.clone());
         let proj_root = self.root.unwrap_or_else(|| PathBuf::from("."));
+        let resolutions = Resolutions::from_manifest(&root)?;
+        let catalogs = Catalogs::from_manifest(&root);
         let mut resolver = Resolver {
             nassun,
             graph: Default::default(),
             concurrency: self.concurrency,
             locked: self.locked,
+            update: self.update,
+            offline_first: self.offline_first,
+            auto_install_peers: self.auto_install_peers,
+            dedupe_peer_deps: self.dedupe_peer_deps,
+            legacy_peer_deps: self.legacy_peer_deps,
+            allow_peer_conflicts: self.allow_peer_conflicts,
+            hoist: self.hoist,
+            max_depth: self.max_depth,
+            platform: self.platform,
+            warnings: self.warnings.clone(),
             root: &proj_root,
             actual_tree: None,
+            resolutions,
+            catalogs,
             on_resolution_added: self.on_resolution_added,
             on_resolve_progress: self.on_resolve_progress,
         };
@@ -356,22 +765,56 @@ impl NodeMaintainerOptions {
             true,
         )?);
         resolver.graph[node].root = node;
-        let (graph, _actual_tree) = resolver.run_resolver(lockfile).await?;
+        let result = resolver.run_resolver(lockfile).await;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (Some(path), Some(inputs)) = (&self.debug_resolver, dump_inputs) {
+            // Failed resolutions are what dumps are most useful for, so
+            // whatever was resolved before the error gets written, too.
+            let (graph, error) = match &result {
+                Ok((graph, _)) => (graph, None),
+                Err(e) => (&resolver.graph, Some(e)),
+            };
+            if let Err(e) = write_resolver_dump(path, inputs, graph, error).await {
+                if result.is_ok() {
+                    return Err(e);
+                }
+                tracing::warn!("{e}");
+            }
+        }
+        let (graph, _actual_tree) = result?;
+        if self.strict_layout {
+            graph.validate_layout()?;
+        }
         #[cfg(not(target_arch = "wasm32"))]
         let linker_opts = LinkerOptions {
             actual_tree: _actual_tree,
             concurrency: self.concurrency,
+            extract_concurrency: self.extract_concurrency.unwrap_or(self.concurrency),
             script_concurrency: self.script_concurrency,
-            cache: self.cache,
+            cache: self.store_dir.or(self.cache),
             prefer_copy: self.prefer_copy,
+            bin_links: self.bin_links,
+            script_env: self.script_env,
+            extract_filter: self.extract_filter,
+            trim_patterns: self.trim_install.then(|| {
+                self.trim_patterns.unwrap_or_else(|| {
+                    DEFAULT_TRIM_PATTERNS
+                        .iter()
+                        .map(|p| p.to_string())
+                        .collect()
+                })
+            }),
             root: proj_root,
             on_prune_progress: self.on_prune_progress,
             on_extract_progress: self.on_extract_progress,
+            on_extract_bytes: self.on_extract_bytes,
             on_script_start: self.on_script_start,
             on_script_line: self.on_script_line,
+            warnings: self.warnings.clone(),
         };
         let nm = NodeMaintainer {
             graph,
+            warnings: self.warnings,
             #[cfg(target_arch = "wasm32")]
             linker: Linker::null(),
             #[cfg(not(target_arch = "wasm32"))]
@@ -393,20 +836,41 @@ impl NodeMaintainerOptions {
         root_spec: impl AsRef<str>,
     ) -> Result<NodeMaintainer, NodeMaintainerError> {
         let lockfile = self.get_lockfile().await?;
-        let nassun = self.nassun_opts.build();
+        #[cfg(not(target_arch = "wasm32"))]
+        let dump_inputs = self.resolver_dump_inputs(lockfile.is_some());
+        let nassun = self.nassun_opts.warnings(self.warnings.clone()).build();
         let root_pkg = nassun.resolve(root_spec).await?;
+        let corgi = root_pkg.corgi_metadata().await?.manifest;
+        #[cfg(not(target_arch = "wasm32"))]
+        let dump_inputs = dump_inputs.map(|inputs| ResolverInputs {
+            root: corgi.clone(),
+            ..inputs
+        });
         let proj_root = self.root.unwrap_or_else(|| PathBuf::from("."));
+        let resolutions = Resolutions::from_manifest(&corgi)?;
+        let catalogs = Catalogs::from_manifest(&corgi);
         let mut resolver = Resolver {
             nassun,
             graph: Default::default(),
             concurrency: self.concurrency,
             locked: self.locked,
+            update: self.update,
+            offline_first: self.offline_first,
+            auto_install_peers: self.auto_install_peers,
+            dedupe_peer_deps: self.dedupe_peer_deps,
+            legacy_peer_deps: self.legacy_peer_deps,
+            allow_peer_conflicts: self.allow_peer_conflicts,
+            hoist: self.hoist,
+            max_depth: self.max_depth,
+            platform: self.platform,
+            warnings: self.warnings.clone(),
             root: &proj_root,
             actual_tree: None,
+            resolutions,
+            catalogs,
             on_resolution_added: self.on_resolution_added,
             on_resolve_progress: self.on_resolve_progress,
         };
-        let corgi = root_pkg.corgi_metadata().await?.manifest;
         let node = resolver.graph.inner.add_node(Node::new(
             UniCase::new("".to_string()),
             root_pkg,
@@ -414,22 +878,56 @@ impl NodeMaintainerOptions {
             true,
         )?);
         resolver.graph[node].root = node;
-        let (graph, _actual_tree) = resolver.run_resolver(lockfile).await?;
+        let result = resolver.run_resolver(lockfile).await;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (Some(path), Some(inputs)) = (&self.debug_resolver, dump_inputs) {
+            // Failed resolutions are what dumps are most useful for, so
+            // whatever was resolved before the error gets written, too.
+            let (graph, error) = match &result {
+                Ok((graph, _)) => (graph, None),
+                Err(e) => (&resolver.graph, Some(e)),
+            };
+            if let Err(e) = write_resolver_dump(path, inputs, graph, error).await {
+                if result.is_ok() {
+                    return Err(e);
+                }
+                tracing::warn!("{e}");
+            }
+        }
+        let (graph, _actual_tree) = result?;
+        if self.strict_layout {
+            graph.validate_layout()?;
+        }
         #[cfg(not(target_arch = "wasm32"))]
         let linker_opts = LinkerOptions {
             actual_tree: _actual_tree,
             concurrency: self.concurrency,
+            extract_concurrency: self.extract_concurrency.unwrap_or(self.concurrency),
             script_concurrency: self.script_concurrency,
-            cache: self.cache,
+            cache: self.store_dir.or(self.cache),
             prefer_copy: self.prefer_copy,
+            bin_links: self.bin_links,
+            script_env: self.script_env,
+            extract_filter: self.extract_filter,
+            trim_patterns: self.trim_install.then(|| {
+                self.trim_patterns.unwrap_or_else(|| {
+                    DEFAULT_TRIM_PATTERNS
+                        .iter()
+                        .map(|p| p.to_string())
+                        .collect()
+                })
+            }),
             root: proj_root,
             on_prune_progress: self.on_prune_progress,
             on_extract_progress: self.on_extract_progress,
+            on_extract_bytes: self.on_extract_bytes,
             on_script_start: self.on_script_start,
             on_script_line: self.on_script_line,
+            warnings: self.warnings.clone(),
         };
         let nm = NodeMaintainer {
             graph,
+            warnings: self.warnings,
             #[cfg(target_arch = "wasm32")]
             linker: Linker::null(),
             #[cfg(not(target_arch = "wasm32"))]
@@ -443,6 +941,21 @@ impl NodeMaintainerOptions {
         nm.graph.validate()?;
         Ok(nm)
     }
+
+    /// Like [`NodeMaintainerOptions::resolve_spec`], but only follows
+    /// dependencies `depth` levels down from the root package. A depth of
+    /// `1` resolves the root's direct dependencies without any of their own.
+    ///
+    /// The resulting graph is partial, so it's meant for previews and
+    /// inspection rather than for writing out `node_modules` or lockfiles.
+    pub async fn resolve_shallow(
+        mut self,
+        root_spec: impl AsRef<str>,
+        depth: usize,
+    ) -> Result<NodeMaintainer, NodeMaintainerError> {
+        self.max_depth = Some(depth);
+        self.resolve_spec(root_spec).await
+    }
 }
 
 impl Default for NodeMaintainerOptions {
@@ -451,19 +964,45 @@ impl Default for NodeMaintainerOptions {
             nassun_opts: Default::default(),
             nassun: None,
             concurrency: DEFAULT_CONCURRENCY,
+            extract_concurrency: None,
             kdl_lock: None,
             npm_lock: None,
             locked: false,
+            update: None,
+            offline_first: false,
+            auto_install_peers: true,
+            dedupe_peer_deps: false,
+            legacy_peer_deps: false,
+            allow_peer_conflicts: false,
+            hoist: HoistMode::default(),
+            max_depth: None,
+            platform: Platform::current(),
+            warnings: WarningCollector::new(),
+            fix_lockfile: false,
+            strict_layout: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            debug_resolver: None,
             script_concurrency: DEFAULT_SCRIPT_CONCURRENCY,
             cache: None,
+            store_dir: None,
             hoisted: false,
             prefer_copy: false,
+            bin_links: true,
+            #[cfg(not(target_arch = "wasm32"))]
+            extract_filter: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            trim_install: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            trim_patterns: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            script_env: HashMap::new(),
             validate: false,
             root: None,
             on_resolution_added: None,
             on_resolve_progress: None,
             on_prune_progress: None,
             on_extract_progress: None,
+            on_extract_bytes: None,
             on_script_start: None,
             on_script_line: None,
         }
@@ -473,6 +1012,7 @@ impl Default for NodeMaintainerOptions {
 /// Resolves and manages `node_modules` for a given project.
 pub struct NodeMaintainer {
     pub(crate) graph: Graph,
+    warnings: WarningCollector,
     #[allow(dead_code)]
     linker: Linker,
 }
@@ -501,13 +1041,33 @@ impl NodeMaintainer {
         Self::builder().resolve_spec(root_spec).await
     }
 
+    /// Resolves a partial [`NodeMaintainer`] for `root_spec`, only following
+    /// dependencies `depth` levels down. See
+    /// [`NodeMaintainerOptions::resolve_shallow`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn resolve_shallow(
+        root_spec: impl AsRef<str>,
+        depth: usize,
+    ) -> Result<NodeMaintainer, NodeMaintainerError> {
+        Self::builder().resolve_shallow(root_spec, depth).await
+    }
+
     /// Writes the contents of a `package-lock.kdl` file to the file path.
+    ///
+    /// The lockfile is written to a temporary file first and then renamed
+    /// over `path`, so an interrupted write never leaves a truncated
+    /// lockfile behind, and concurrent writers never interleave: whichever
+    /// rename happens last wins.
     #[cfg(not(target_arch = "wasm32"))]
     pub async fn write_lockfile(&self, path: impl AsRef<Path>) -> Result<(), NodeMaintainerError> {
-        let path = path.as_ref();
-        fs::write(path, self.graph.to_kdl()?.to_string())
-            .await
-            .io_context(|| format!("Failed to write lockfile to {}", path.display()))?;
+        let path = path.as_ref().to_owned();
+        let kdl = self.graph.to_kdl()?.to_string();
+        async_std::task::spawn_blocking(move || {
+            stage_lockfile(&path, &kdl)
+                .and_then(|staged| staged.persist(&path).map_err(|e| e.error))
+                .io_context(|| format!("Failed to write lockfile to {}", path.display()))
+        })
+        .await?;
         Ok(())
     }
 
@@ -528,6 +1088,38 @@ impl NodeMaintainer {
         self.graph.package_at_path(path)
     }
 
+    /// Lists the `name@version`, tarball URL, and integrity (when known) of
+    /// every registry package in the resolved tree, without downloading
+    /// anything. Useful for pre-fetching or mirroring tarballs for offline
+    /// installs.
+    pub fn tarball_urls(&self) -> Vec<(String, Url, Option<Integrity>)> {
+        self.graph.tarball_urls()
+    }
+
+    /// Peer dependencies that are missing from the tree, or were resolved to
+    /// an incompatible version. Missing peers marked as `optional` in
+    /// `peerDependenciesMeta` are not included.
+    pub fn peer_conflicts(&self) -> Result<Vec<PeerConflict>, NodeMaintainerError> {
+        self.graph.peer_conflicts()
+    }
+
+    /// Differences between the resolved tree and what was last installed to
+    /// `node_modules`. Packages symlinked in from elsewhere are reported as
+    /// [`TreeDrift::Linked`] instead of being compared. Nothing on disk is
+    /// modified.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn drift(&self) -> Result<Vec<TreeDrift>, NodeMaintainerError> {
+        self.graph
+            .drift(self.linker.actual_tree(), self.linker.root())
+    }
+
+    /// Deduplicated warnings recorded so far, such as deprecated packages,
+    /// peer dependency conflicts, and optional dependencies that failed to
+    /// install.
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.warnings.warnings()
+    }
+
     /// Number of unique packages in the dependency tree.
     pub fn package_count(&self) -> usize {
         self.graph.inner.node_count()
@@ -544,7 +1136,9 @@ impl NodeMaintainer {
     /// Extracts the `node_modules/` directory to the project root,
     /// downloading packages as needed. Whether this method creates files or
     /// hard links depends on the current filesystem and the `cache` and
-    /// `prefer_copy` options.
+    /// `prefer_copy` options. Packages are moved into place only once
+    /// they've been fully extracted, so a failed or interrupted extraction
+    /// never leaves a partial package behind.
     #[cfg(not(target_arch = "wasm32"))]
     pub async fn extract(&self) -> Result<usize, NodeMaintainerError> {
         self.linker.extract(&self.graph).await
@@ -557,3 +1151,70 @@ impl NodeMaintainer {
         self.linker.rebuild(&self.graph, ignore_scripts).await
     }
 }
+
+/// Writes `contents` to a temporary file in the same directory as `path` and
+/// flushes it to disk, without touching `path` itself. Persisting the result
+/// atomically replaces `path`.
+#[cfg(not(target_arch = "wasm32"))]
+fn stage_lockfile(path: &Path, contents: &str) -> std::io::Result<tempfile::NamedTempFile> {
+    use std::io::Write;
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut staged = tempfile::Builder::new()
+        .prefix(".package-lock.kdl.")
+        .tempfile_in(dir)?;
+    staged.write_all(contents.as_bytes())?;
+    staged.as_file().sync_all()?;
+    Ok(staged)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interrupted_lockfile_write() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let lockfile = dir.path().join("package-lock.kdl");
+        std::fs::write(&lockfile, "old")?;
+
+        // Simulate a crash between writing the temp file and renaming it,
+        // which leaves the temp file behind.
+        stage_lockfile(&lockfile, "new")?.into_temp_path().keep()?;
+        assert_eq!(std::fs::read_to_string(&lockfile)?, "old");
+
+        // Leftovers from earlier attempts don't get in the way of the next
+        // one.
+        stage_lockfile(&lockfile, "new")?
+            .persist(&lockfile)
+            .map_err(|e| e.error)?;
+        assert_eq!(std::fs::read_to_string(&lockfile)?, "new");
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_lockfile_writes() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let lockfile = dir.path().join("package-lock.kdl");
+        let contents = (0..8)
+            .map(|i| i.to_string().repeat(64 * 1024))
+            .collect::<Vec<_>>();
+        std::thread::scope(|s| {
+            for contents in &contents {
+                let lockfile = &lockfile;
+                s.spawn(move || {
+                    stage_lockfile(lockfile, contents)
+                        .unwrap()
+                        .persist(lockfile)
+                        .unwrap();
+                });
+            }
+        });
+        let written = std::fs::read_to_string(&lockfile)?;
+        assert!(contents.contains(&written), "lockfile writes interleaved");
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use oro_common::CorgiManifest;
+use serde::Serialize;
+
+/// Operating systems packages can restrict themselves to, using Node's names
+/// for them (`process.platform`).
+pub const KNOWN_OS: &[&str] = &[
+    "aix", "android", "cygwin", "darwin", "freebsd", "haiku", "linux", "netbsd", "openbsd",
+    "sunos", "win32",
+];
+
+/// CPU architectures packages can restrict themselves to, using Node's names
+/// for them (`process.arch`).
+pub const KNOWN_CPU: &[&str] = &[
+    "arm", "arm64", "ia32", "loong64", "mips", "mipsel", "ppc", "ppc64", "riscv64", "s390",
+    "s390x", "x64",
+];
+
+/// C standard libraries packages can restrict themselves to. These only
+/// apply on Linux.
+pub const KNOWN_LIBC: &[&str] = &["glibc", "musl"];
+
+/// The platform a tree is being installed for. Optional dependencies whose
+/// `os`, `cpu`, or `libc` fields rule this platform out are skipped during
+/// resolution.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Platform {
+    pub os: String,
+    pub cpu: String,
+    pub libc: Option<String>,
+}
+
+impl Platform {
+    /// The platform Orogene itself is running on.
+    pub fn current() -> Self {
+        let os = match std::env::consts::OS {
+            "macos" => "darwin",
+            "windows" => "win32",
+            "solaris" | "illumos" => "sunos",
+            other => other,
+        };
+        let cpu = match std::env::consts::ARCH {
+            "x86_64" => "x64",
+            "x86" => "ia32",
+            "aarch64" => "arm64",
+            "powerpc" => "ppc",
+            "powerpc64" => "ppc64",
+            "loongarch64" => "loong64",
+            other => other,
+        };
+        let libc = if os != "linux" {
+            None
+        } else if cfg!(target_env = "musl") {
+            Some("musl".into())
+        } else {
+            Some("glibc".into())
+        };
+        Self {
+            os: os.into(),
+            cpu: cpu.into(),
+            libc,
+        }
+    }
+
+    /// Whether a package with this `manifest` can be installed on this
+    /// platform. Follows npm's rules: entries starting with `!` exclude a
+    /// value, and any other entries form an allowlist.
+    pub fn supports(&self, manifest: &CorgiManifest) -> bool {
+        check_list(Some(&self.os), &manifest.os)
+            && check_list(Some(&self.cpu), &manifest.cpu)
+            && check_list(self.libc.as_deref(), &manifest.libc)
+    }
+}
+
+impl Default for Platform {
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+fn check_list(value: Option<&str>, list: &[String]) -> bool {
+    if list.is_empty() {
+        return true;
+    }
+    let mut allowed = false;
+    let mut negated = 0;
+    for entry in list {
+        if let Some(excluded) = entry.strip_prefix('!') {
+            if Some(excluded) == value {
+                return false;
+            }
+            negated += 1;
+        } else {
+            allowed = allowed || Some(entry.as_str()) == value;
+        }
+    }
+    allowed || negated == list.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(os: &[&str], cpu: &[&str], libc: &[&str]) -> CorgiManifest {
+        let strings = |list: &[&str]| list.iter().map(|s| s.to_string()).collect();
+        CorgiManifest {
+            os: strings(os),
+            cpu: strings(cpu),
+            libc: strings(libc),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn supports() {
+        let platform = Platform {
+            os: "linux".into(),
+            cpu: "x64".into(),
+            libc: Some("musl".into()),
+        };
+        assert!(platform.supports(&manifest(&[], &[], &[])));
+        assert!(platform.supports(&manifest(&["linux"], &["x64", "arm64"], &["musl"])));
+        assert!(platform.supports(&manifest(&["!win32"], &[], &[])));
+        assert!(!platform.supports(&manifest(&["darwin"], &[], &[])));
+        assert!(!platform.supports(&manifest(&["!linux"], &[], &[])));
+        assert!(!platform.supports(&manifest(&[], &["arm64"], &[])));
+        assert!(!platform.supports(&manifest(&["linux"], &[], &["glibc"])));
+
+        let darwin = Platform {
+            os: "darwin".into(),
+            cpu: "arm64".into(),
+            libc: None,
+        };
+        assert!(!darwin.supports(&manifest(&[], &[], &["glibc"])));
+        assert!(darwin.supports(&manifest(&[], &[], &["!musl"])));
+    }
+}
@@ -0,0 +1,83 @@
+use nassun::PackageSpec;
+use oro_common::CorgiManifest;
+use unicase::UniCase;
+
+use crate::error::NodeMaintainerError;
+
+/// Yarn-style `resolutions`, which force a particular spec for dependencies
+/// matching a glob-like path, such as `**/lodash` or `a/**/b/lodash`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Resolutions {
+    entries: Vec<Resolution>,
+}
+
+#[derive(Debug, Clone)]
+struct Resolution {
+    /// Dependency path leading up to the package, where `**` matches any
+    /// number of packages, and `*` matches exactly one.
+    path: Vec<UniCase<String>>,
+    name: UniCase<String>,
+    spec: PackageSpec,
+}
+
+impl Resolutions {
+    pub(crate) fn from_manifest(manifest: &CorgiManifest) -> Result<Self, NodeMaintainerError> {
+        let mut entries = Vec::new();
+        for (key, spec) in &manifest.resolutions {
+            let mut segments = Vec::new();
+            let mut scope: Option<&str> = None;
+            for segment in key.split('/').filter(|s| !s.is_empty()) {
+                if let Some(scope) = scope.take() {
+                    segments.push(format!("{scope}/{segment}"));
+                } else if segment.starts_with('@') {
+                    scope = Some(segment);
+                } else {
+                    segments.push(segment.to_string());
+                }
+            }
+            let Some(name) = segments.pop() else {
+                continue;
+            };
+            // A bare package name applies anywhere in the tree.
+            if segments.is_empty() {
+                segments.push("**".into());
+            }
+            entries.push(Resolution {
+                path: segments.into_iter().map(UniCase::new).collect(),
+                spec: format!("{name}@{spec}").parse()?,
+                name: UniCase::new(name),
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    /// Returns the forced spec for dependency `name`, when requested by a
+    /// package at the logical dependency path `path`, if any. Later entries
+    /// win over earlier ones.
+    pub(crate) fn get(
+        &self,
+        path: &[UniCase<String>],
+        name: &UniCase<String>,
+    ) -> Option<&PackageSpec> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|res| &res.name == name && path_matches(&res.path, path))
+            .map(|res| &res.spec)
+    }
+}
+
+fn path_matches(pattern: &[UniCase<String>], path: &[UniCase<String>]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((segment, rest)) if segment.as_str() == "**" => {
+            (0..=path.len()).any(|skip| path_matches(rest, &path[skip..]))
+        }
+        Some((segment, rest)) => match path.split_first() {
+            Some((current, path_rest)) => {
+                (segment.as_str() == "*" || segment == current) && path_matches(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
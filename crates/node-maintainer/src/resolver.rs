@@ -1,32 +1,60 @@
 use std::cmp::Ordering;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Instant;
 
 use async_std::sync::Mutex;
+use dashmap::DashMap;
 #[cfg(not(target_arch = "wasm32"))]
-use colored::Colorize;
-use futures::{StreamExt, TryFutureExt};
+use futures::future::BoxFuture;
+#[cfg(target_arch = "wasm32")]
+use futures::future::LocalBoxFuture;
+use futures::future::{FutureExt, Shared};
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
 use indexmap::IndexMap;
 use nassun::client::Nassun;
 use nassun::package::Package;
-use nassun::PackageSpec;
-use oro_common::{CorgiManifest, CorgiVersionMetadata};
+use nassun::{PackageResolution, PackageSpec, VersionSpec};
+use node_semver::Version;
+use oro_common::{CorgiManifest, CorgiVersionMetadata, WarningCollector, WarningKind};
 use petgraph::stable_graph::NodeIndex;
 use petgraph::visit::EdgeRef;
 use petgraph::Direction;
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
 use unicase::UniCase;
 
+use crate::catalogs::Catalogs;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::error::IoContext;
 use crate::error::NodeMaintainerError;
 use crate::graph::{DepType, Edge, Graph, Node};
 use crate::maintainer::{ProgressAdded, ProgressHandler};
+use crate::platform::Platform;
+use crate::resolutions::Resolutions;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::META_FILE_NAME;
 use crate::{Lockfile, LockfileNode};
 
+/// Controls how far up the tree dependencies are placed, and thus what ends
+/// up directly in the top-level `node_modules`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HoistMode {
+    /// Place every package as high up the tree as it can go without
+    /// conflicting with anything else.
+    #[default]
+    Full,
+    /// Never hoist. Packages are always placed in the `node_modules` of the
+    /// package that depends on them, so only direct dependencies show up at
+    /// the top level.
+    None,
+    /// Only hoist packages installed from local directories, such as
+    /// workspace packages. Everything else is placed like [`HoistMode::None`].
+    WorkspacesOnly,
+}
+
 #[derive(Debug, Clone)]
 struct NodeDependency {
     name: UniCase<String>,
@@ -36,21 +64,49 @@ struct NodeDependency {
     start: Instant,
 }
 
+/// Metadata for a package that was requested ahead of the walk getting to
+/// it. Resolves to `None` if it couldn't be loaded, in which case the walk
+/// just fetches it again and reports the error itself.
+#[cfg(not(target_arch = "wasm32"))]
+type Prefetch<'a> = Shared<BoxFuture<'a, Option<(Package, CorgiVersionMetadata)>>>;
+#[cfg(target_arch = "wasm32")]
+type Prefetch<'a> = Shared<LocalBoxFuture<'a, Option<(Package, CorgiVersionMetadata)>>>;
+
 pub(crate) struct Resolver<'a> {
     pub(crate) nassun: Nassun,
     pub(crate) graph: Graph,
     pub(crate) concurrency: usize,
     pub(crate) locked: bool,
+    pub(crate) update: Option<Vec<UniCase<String>>>,
+    pub(crate) offline_first: bool,
+    pub(crate) auto_install_peers: bool,
+    pub(crate) dedupe_peer_deps: bool,
+    pub(crate) legacy_peer_deps: bool,
+    pub(crate) allow_peer_conflicts: bool,
+    pub(crate) hoist: HoistMode,
+    /// How many levels of dependencies to follow from the root, if limited.
+    pub(crate) max_depth: Option<usize>,
+    /// Platform that optional dependencies must be installable on.
+    pub(crate) platform: Platform,
+    pub(crate) warnings: WarningCollector,
     #[allow(dead_code)]
     pub(crate) root: &'a Path,
     pub(crate) actual_tree: Option<Lockfile>,
+    pub(crate) resolutions: Resolutions,
+    pub(crate) catalogs: Catalogs,
     pub(crate) on_resolution_added: Option<ProgressAdded>,
     pub(crate) on_resolve_progress: Option<ProgressHandler>,
 }
 
 impl<'a> Resolver<'a> {
+    #[tracing::instrument(
+        level = "trace",
+        name = "resolve",
+        skip_all,
+        fields(nodes = Empty, packuments = Empty, memoized = Empty, duration_ms = Empty)
+    )]
     pub(crate) async fn run_resolver(
-        mut self,
+        &mut self,
         lockfile: Option<Lockfile>,
     ) -> Result<(Graph, Option<Lockfile>), NodeMaintainerError> {
         #[cfg(not(target_arch = "wasm32"))]
@@ -66,6 +122,36 @@ impl<'a> Resolver<'a> {
         // Number of dependencies queued for processing in `package_stream`
         let mut in_flight = 0;
 
+        // Number of packuments actually fetched, for reporting.
+        let mut fetched = 0;
+
+        // Number of dependencies resolved from `memo` instead of going
+        // through `nassun`, for reporting.
+        let mut memoized = 0;
+
+        // Packages previously picked for a given spec. Deeply-nested
+        // dependencies often ask for the same spec over and over again,
+        // after the first request for it was already resolved, so this
+        // saves us from re-picking a version from its packument every time.
+        let memo: DashMap<PackageSpec, (Package, CorgiVersionMetadata)> = DashMap::new();
+        let memo = &memo;
+        let nassun = &self.nassun;
+
+        // As soon as a package's metadata comes in, the specs for its own
+        // dependencies are sent to `lookahead_sink`, and (up to
+        // `concurrency` at a time) fetched into `prefetches`. That way, the
+        // next frontier is already on its way while the walk is still
+        // placing the current one. This is skipped when there's a lockfile
+        // or an existing tree to resolve from, or when the walk stops at a
+        // certain depth, since most of what it would fetch would go unused.
+        let lookahead =
+            lockfile.is_none() && self.actual_tree.is_none() && self.max_depth.is_none();
+        let (lookahead_sink, mut lookahead_stream) = futures::channel::mpsc::unbounded();
+        let lookahead_sink = &lookahead_sink;
+        let prefetches: DashMap<PackageSpec, Prefetch> = DashMap::new();
+        let prefetches = &prefetches;
+        let mut prefetching = FuturesUnordered::new();
+
         // Since we queue dependencies for multiple packages at once - it is
         // not unlikely that some of them would be duplicated by currently
         // fetched dependencies. Thus we maintain a mapping from "name@spec" to
@@ -94,167 +180,369 @@ impl<'a> Resolver<'a> {
                 futures::future::ready(maybe_spec)
             })
             .filter_map(|maybe_spec| maybe_spec)
-            .map(|spec| {
-                self.nassun
-                    .resolve_spec(spec.clone())
-                    .map_ok(move |p| (p, spec))
+            // Everything needed to place a package, including its metadata,
+            // is loaded here, so it happens concurrently for the whole
+            // frontier instead of one package at a time in the walk below.
+            .map(|spec| async move {
+                if let Some(entry) = memo.get(&spec) {
+                    let (package, metadata) = entry.value().clone();
+                    return Ok((package, metadata, spec, true));
+                }
+                let prefetch = prefetches.get(&spec).map(|entry| entry.value().clone());
+                let prefetched = match prefetch {
+                    Some(prefetch) => prefetch.await,
+                    None => None,
+                };
+                let (package, metadata) = match prefetched {
+                    Some(prefetched) => prefetched,
+                    None => {
+                        let span = tracing::trace_span!("fetch_packument", spec = %spec);
+                        async {
+                            let package = nassun.resolve_spec(spec.clone()).await?;
+                            let metadata = package.corgi_metadata().await?;
+                            Ok::<_, NodeMaintainerError>((package, metadata))
+                        }
+                        .instrument(span)
+                        .await?
+                    }
+                };
+                if lookahead {
+                    let manifest = &metadata.manifest;
+                    for (name, requested) in manifest
+                        .dependencies
+                        .iter()
+                        .chain(manifest.optional_dependencies.iter())
+                    {
+                        if let Ok(spec) = format!("{name}@{requested}").parse::<PackageSpec>() {
+                            if matches!(spec.target(), PackageSpec::Npm { .. }) {
+                                lookahead_sink.unbounded_send(spec)?;
+                            }
+                        }
+                    }
+                }
+                memo.insert(spec.clone(), (package.clone(), metadata.clone()));
+                Ok::<_, NodeMaintainerError>((package, metadata, spec, false))
             })
-            .buffer_unordered(self.concurrency)
-            .ready_chunks(self.concurrency);
-
-        // Start iterating over the queue. We'll be adding things to it as we find them.
-        while !q.is_empty() || in_flight != 0 {
-            while let Some(node_idx) = q.pop_front() {
-                let mut names = HashSet::new();
-                // Grab all the deps from the current package and fire off a
-                // lookup. These will be resolved concurrently.
-                for (name, (spec, dep_type)) in self.graph[node_idx].dependency_reqs.clone() {
-                    if names.contains(&name) {
+            // Fetches run concurrently, but results come back in the order
+            // they were requested, so network timing can't change which
+            // package gets placed first.
+            .buffered(self.concurrency)
+            .ready_chunks(self.concurrency)
+            .fuse();
+
+        loop {
+            // Start iterating over the queue. We'll be adding things to it as we find them.
+            while !q.is_empty() || in_flight != 0 {
+                while let Some(node_idx) = q.pop_front() {
+                    // Shallow resolutions leave the dependencies of the
+                    // deepest nodes alone.
+                    let depth = self.graph[node_idx].logical_path.len();
+                    if matches!(self.max_depth, Some(max) if depth >= max) {
                         continue;
-                    } else {
-                        names.insert(name.clone());
                     }
+                    let mut names = HashSet::new();
+                    // Grab all the deps from the current package and fire off a
+                    // lookup. These will be resolved concurrently.
+                    for (name, (mut spec, dep_type)) in self.graph[node_idx].dependency_reqs.clone()
+                    {
+                        if names.contains(&name) {
+                            continue;
+                        } else {
+                            names.insert(name.clone());
+                        }
 
-                    let dep = NodeDependency {
-                        name: name.clone(),
-                        spec,
-                        dep_type,
-                        node_idx,
-                        start: Instant::now(),
-                    };
+                        // Nodes get processed again when deduped peers are
+                        // added to them, but their other dependencies are
+                        // already taken care of by then.
+                        if self.graph[node_idx].dependencies.contains_key(&name) {
+                            continue;
+                        }
 
-                    if let Some(handler) = &self.on_resolution_added {
-                        handler();
-                    }
+                        // `catalog:` specs get swapped for the version the
+                        // catalog defines, just as if it had been requested
+                        // directly.
+                        if let PackageSpec::Catalog { name: catalog } = spec.target() {
+                            spec = self.catalogs.get(&name, catalog.as_deref())?;
+                            if let Some(req) = self.graph[node_idx].dependency_reqs.get_mut(&name) {
+                                req.0 = spec.clone();
+                            }
+                        }
 
-                    if let Some(_child_idx) = Self::satisfy_dependency(&mut self.graph, &dep)? {
-                        if let Some(handler) = &self.on_resolve_progress {
-                            handler(&self.graph[_child_idx].package, dep.start.elapsed());
+                        // `resolutions` take precedence over whatever the
+                        // dependent asked for. We rewrite the requirement itself
+                        // so placement checks against the forced spec, too.
+                        if let Some(forced) = self
+                            .resolutions
+                            .get(&self.graph[node_idx].logical_path, &name)
+                        {
+                            spec = forced.clone();
+                            if let Some(req) = self.graph[node_idx].dependency_reqs.get_mut(&name) {
+                                req.0 = spec.clone();
+                            }
                         }
-                    }
-                    // Walk up the current hierarchy to see if we find a
-                    // dependency that already satisfies this request. If so,
-                    // make a new edge and move on.
-                    else {
-                        // If we have a lockfile, first check if there's a
-                        // dep there that would satisfy this.
-                        let lock = if lockfile.is_some() {
-                            &lockfile
-                        } else {
-                            // Fall back to the actual tree lock if it's there.
-                            &self.actual_tree
+
+                        let dep = NodeDependency {
+                            name: name.clone(),
+                            spec,
+                            dep_type,
+                            node_idx,
+                            start: Instant::now(),
                         };
-                        if let Some(kdl_lock) = lock {
-                            if let Some((package, lockfile_node)) = self
-                                .satisfy_from_lockfile(
-                                    &self.graph,
-                                    node_idx,
-                                    kdl_lock,
-                                    &name,
-                                    &dep.spec,
-                                )
-                                .await?
-                            {
-                                let target_path = lockfile_node.path.clone();
 
-                                let child_idx = Self::place_child(
-                                    &mut self.graph,
-                                    &dep,
-                                    package,
-                                    lockfile_node.into(),
-                                    Some(target_path),
-                                )?;
-                                q.push_back(child_idx);
+                        if let Some(handler) = &self.on_resolution_added {
+                            handler();
+                        }
 
-                                if let Some(handler) = &self.on_resolve_progress {
-                                    handler(&self.graph[child_idx].package, dep.start.elapsed());
-                                }
-                                continue;
+                        if let Some(_child_idx) = Self::satisfy_dependency(&mut self.graph, &dep)? {
+                            if let Some(handler) = &self.on_resolve_progress {
+                                handler(&self.graph[_child_idx].package, dep.start.elapsed());
                             }
                         }
+                        // Walk up the current hierarchy to see if we find a
+                        // dependency that already satisfies this request. If so,
+                        // make a new edge and move on.
+                        else {
+                            // If we have a lockfile, first check if there's a
+                            // dep there that would satisfy this. Packages being
+                            // updated always get resolved from scratch.
+                            let lock = if self.should_update(&name) {
+                                None
+                            } else if lockfile.is_some() {
+                                lockfile.as_ref()
+                            } else {
+                                // Fall back to the actual tree lock if it's there.
+                                self.actual_tree.as_ref()
+                            };
+                            if let Some(kdl_lock) = lock {
+                                let locked = match self
+                                    .satisfy_from_lockfile(
+                                        &self.graph,
+                                        node_idx,
+                                        kdl_lock,
+                                        &name,
+                                        &dep.spec,
+                                    )
+                                    .await?
+                                {
+                                    Some((package, lockfile_node)) => {
+                                        let target_path = lockfile_node.path.clone();
+                                        Some((package, lockfile_node, Some(target_path)))
+                                    }
+                                    // Failing that, reuse a compatible version
+                                    // that's already installed somewhere else,
+                                    // rather than picking up a newer one.
+                                    None => self
+                                        .installed_version(kdl_lock, &name, &dep.spec)
+                                        .await?
+                                        .map(|(package, lockfile_node)| {
+                                            (package, lockfile_node, None)
+                                        }),
+                                };
+                                if let Some((package, lockfile_node, target_path)) = locked {
+                                    let platform_known = lockfile_node.platform_known;
+                                    let mut manifest: CorgiManifest = lockfile_node.into();
+                                    if dep.dep_type == DepType::Opt && !platform_known {
+                                        // Lockfiles from before v2 don't say
+                                        // which platforms packages support,
+                                        // so we have to go find out.
+                                        let metadata = package.corgi_metadata().await?;
+                                        manifest.os = metadata.manifest.os;
+                                        manifest.cpu = metadata.manifest.cpu;
+                                        manifest.libc = metadata.manifest.libc;
+                                    }
+                                    // The lockfile may have been written for a
+                                    // different platform than the one we're
+                                    // installing for.
+                                    if dep.dep_type == DepType::Opt
+                                        && !self.platform.supports(&manifest)
+                                    {
+                                        tracing::debug!(
+                                            "Skipping optional dependency {}@{}: unsupported platform.",
+                                            dep.name,
+                                            package.resolved()
+                                        );
+                                        continue;
+                                    }
+                                    let child_idx = Self::place_child(
+                                        &mut self.graph,
+                                        &dep,
+                                        package,
+                                        manifest,
+                                        target_path,
+                                        self.hoist,
+                                    )?;
+                                    q.push_back(child_idx);
+
+                                    if let Some(handler) = &self.on_resolve_progress {
+                                        handler(
+                                            &self.graph[child_idx].package,
+                                            dep.start.elapsed(),
+                                        );
+                                    }
+                                    continue;
+                                }
+                            }
 
-                        // Otherwise, we have to fetch package metadata to
-                        // create a new node (which we'll place later).
-                        in_flight += 1;
-                        package_sink.unbounded_send(dep)?;
-                    };
+                            // Otherwise, we have to fetch package metadata to
+                            // create a new node (which we'll place later).
+                            in_flight += 1;
+                            package_sink.unbounded_send(dep)?;
+                        };
+                    }
                 }
-            }
 
-            // Nothing in flight - don't await the stream
-            if in_flight == 0 {
-                continue;
-            }
+                // Nothing in flight - don't await the stream
+                if in_flight == 0 {
+                    continue;
+                }
 
-            // Order doesn't matter here: each node name is unique, so we
-            // don't have to worry about races messing with placement.
-            if let Some(packages) = package_stream.next().await {
-                for res in packages {
-                    let (package, spec) = res?;
-                    let deps = fetches.lock().await.remove(&spec);
+                // Keep prefetching the next frontier while we wait on the
+                // packages that are actually up next.
+                let packages = loop {
+                    futures::select_biased! {
+                        packages = package_stream.next() => break packages,
+                        spec = lookahead_stream.select_next_some() => {
+                            if prefetching.len() < self.concurrency
+                                && !memo.contains_key(&spec)
+                                && !prefetches.contains_key(&spec)
+                                && !fetches.lock().await.contains_key(&spec)
+                            {
+                                let prefetch = Self::prefetch(nassun, spec.clone());
+                                prefetches.insert(spec, prefetch.clone());
+                                prefetching.push(prefetch);
+                            }
+                        }
+                        _ = prefetching.select_next_some() => {}
+                    }
+                };
+
+                if let Some(packages) = packages {
+                    for res in packages {
+                        let (package, metadata, spec, from_memo) = res?;
+                        if from_memo {
+                            memoized += 1;
+                        } else {
+                            fetched += 1;
+                        }
+                        let deps = fetches.lock().await.remove(&spec);
+
+                        if let Some(deps) = deps {
+                            in_flight -= deps.len();
 
-                    if let Some(deps) = deps {
-                        in_flight -= deps.len();
+                            let CorgiVersionMetadata {
+                                manifest,
+                                #[cfg(not(target_arch = "wasm32"))]
+                                deprecated,
+                                ..
+                            } = &metadata;
 
-                        let CorgiVersionMetadata {
-                            manifest,
                             #[cfg(not(target_arch = "wasm32"))]
-                            deprecated,
-                            ..
-                        } = &package.corgi_metadata().await?;
-
-                        #[cfg(not(target_arch = "wasm32"))]
-                        if let Some(deprecated) = deprecated {
-                            tracing::warn!(
-                                "{} {}@{}: {}",
-                                "deprecated".on_magenta(),
-                                manifest.name.as_ref().unwrap(),
-                                manifest
-                                    .version
-                                    .as_ref()
-                                    .map(|v| v.to_string())
-                                    .unwrap_or_else(|| "unknown".into()),
-                                deprecated
-                            );
-                        }
+                            if let Some(deprecated) = deprecated {
+                                self.warnings.push(
+                                    WarningKind::Deprecated,
+                                    format!(
+                                        "{}@{}: {}",
+                                        manifest.name.as_ref().unwrap(),
+                                        manifest
+                                            .version
+                                            .as_ref()
+                                            .map(|v| v.to_string())
+                                            .unwrap_or_else(|| "unknown".into()),
+                                        deprecated
+                                    ),
+                                );
+                            }
 
-                        for dep in deps {
-                            if let Some(_child_idx) =
-                                Self::satisfy_dependency(&mut self.graph, &dep)?
-                            {
-                                if let Some(handler) = &self.on_resolve_progress {
-                                    handler(&self.graph[_child_idx].package, dep.start.elapsed());
+                            for dep in deps {
+                                // Optional dependencies meant for other
+                                // platforms are left out of the tree
+                                // entirely, the same as npm does.
+                                if dep.dep_type == DepType::Opt && !self.platform.supports(manifest)
+                                {
+                                    tracing::debug!(
+                                        "Skipping optional dependency {}@{}: unsupported platform.",
+                                        dep.name,
+                                        package.resolved()
+                                    );
+                                    continue;
                                 }
-                                continue;
-                            }
 
-                            let child_idx = Self::place_child(
-                                &mut self.graph,
-                                &dep,
-                                package.clone(),
-                                manifest.clone(),
-                                None,
-                            )?;
+                                if let Some(_child_idx) =
+                                    Self::satisfy_dependency(&mut self.graph, &dep)?
+                                {
+                                    if let Some(handler) = &self.on_resolve_progress {
+                                        handler(
+                                            &self.graph[_child_idx].package,
+                                            dep.start.elapsed(),
+                                        );
+                                    }
+                                    continue;
+                                }
 
-                            q.push_back(child_idx);
+                                let child_idx = Self::place_child(
+                                    &mut self.graph,
+                                    &dep,
+                                    package.clone(),
+                                    manifest.clone(),
+                                    None,
+                                    self.hoist,
+                                )?;
 
-                            if let Some(handler) = &self.on_resolve_progress {
-                                handler(&self.graph[child_idx].package, dep.start.elapsed());
+                                q.push_back(child_idx);
+
+                                if let Some(handler) = &self.on_resolve_progress {
+                                    handler(&self.graph[child_idx].package, dep.start.elapsed());
+                                }
                             }
                         }
                     }
+
+                    // We sort the current queue so we consider more shallow
+                    // dependencies first, and we also sort alphabetically.
+                    q.make_contiguous().sort_by(|a_idx, b_idx| {
+                        let a = &self.graph[*a_idx];
+                        let b = &self.graph[*b_idx];
+                        match a.depth(&self.graph).cmp(&b.depth(&self.graph)) {
+                            Ordering::Equal => a.package.name().cmp(b.package.name()),
+                            other => other,
+                        }
+                    })
                 }
+            }
 
-                // We sort the current queue so we consider more shallow
-                // dependencies first, and we also sort alphabetically.
-                q.make_contiguous().sort_by(|a_idx, b_idx| {
-                    let a = &self.graph[*a_idx];
-                    let b = &self.graph[*b_idx];
-                    match a.depth(&self.graph).cmp(&b.depth(&self.graph)) {
-                        Ordering::Equal => a.package.name().cmp(b.package.name()),
-                        other => other,
-                    }
-                })
+            // Peer dependencies are only ever checked against the tree, unless
+            // we've been asked to install the missing ones.
+            let requeued = if self.legacy_peer_deps {
+                break;
+            } else if self.dedupe_peer_deps {
+                self.dedupe_peers().await?
+            } else if self.auto_install_peers {
+                self.install_peers().await?
+            } else {
+                break;
+            };
+            if requeued.is_empty() {
+                break;
+            }
+            q.extend(requeued);
+        }
+
+        // Peers of packages past the depth limit were never resolved, so
+        // there's nothing meaningful to report for partial graphs.
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.max_depth.is_none() {
+            let conflicts = self.graph.peer_conflicts()?;
+            let incompatible = conflicts
+                .iter()
+                .filter(|conflict| conflict.found.is_some())
+                .map(|conflict| format!("  {conflict}"))
+                .collect::<Vec<_>>();
+            if !self.legacy_peer_deps && !self.allow_peer_conflicts && !incompatible.is_empty() {
+                return Err(NodeMaintainerError::PeerConflicts(incompatible.join("\n")));
+            }
+            for conflict in conflicts {
+                self.warnings
+                    .push(WarningKind::PeerConflict, conflict.to_string());
             }
         }
 
@@ -266,13 +554,35 @@ impl<'a> Resolver<'a> {
             }
         }
 
+        let span = Span::current();
+        span.record("nodes", self.graph.inner.node_count());
+        span.record("packuments", fetched);
+        span.record("memoized", memoized);
+        #[cfg(not(target_arch = "wasm32"))]
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+
         #[cfg(not(target_arch = "wasm32"))]
         tracing::debug!(
-            "Resolved graph of {} nodes in {}ms",
+            "Resolved graph of {} nodes ({fetched} packuments fetched, {memoized} memoized) in {}ms",
             self.graph.inner.node_count(),
             start.elapsed().as_millis()
         );
-        Ok((self.graph, self.actual_tree))
+        Ok((std::mem::take(&mut self.graph), self.actual_tree.take()))
+    }
+
+    fn prefetch(nassun: &Nassun, spec: PackageSpec) -> Prefetch<'_> {
+        let span = tracing::trace_span!("fetch_packument", spec = %spec);
+        let prefetch = async move {
+            let package = nassun.resolve_spec(spec).await.ok()?;
+            let metadata = package.corgi_metadata().await.ok()?;
+            Some((package, metadata))
+        }
+        .instrument(span);
+        #[cfg(not(target_arch = "wasm32"))]
+        let prefetch = prefetch.boxed();
+        #[cfg(target_arch = "wasm32")]
+        let prefetch = prefetch.boxed_local();
+        prefetch.shared()
     }
 
     fn satisfy_dependency(
@@ -300,6 +610,121 @@ impl<'a> Resolver<'a> {
         Ok(None)
     }
 
+    /// Picks a single version for each missing peer dependency that
+    /// satisfies every package that asked for it, and adds it to those
+    /// packages' dependencies. Returns the packages that need to be
+    /// processed again as a result. Peers that no single version can satisfy
+    /// are left alone, and get reported as conflicts.
+    async fn dedupe_peers(&mut self) -> Result<Vec<NodeIndex>, NodeMaintainerError> {
+        let mut requeued = Vec::new();
+        for (name, requests) in self.missing_peers() {
+            let packument = match self.nassun.resolve_spec(requests[0].1.clone()).await {
+                Ok(package) => package.corgi_packument().await?,
+                Err(e) => {
+                    tracing::debug!("Couldn't look up versions of peer dependency {name}: {e}");
+                    continue;
+                }
+            };
+            let mut versions = packument.versions.keys().collect::<Vec<_>>();
+            versions.sort();
+            let Some(version) = versions.into_iter().rev().find(|version| {
+                requests
+                    .iter()
+                    .all(|(_, spec)| peer_allows(spec, version, &packument.tags))
+            }) else {
+                tracing::debug!(
+                    "No version of peer dependency {name} satisfies all of: {}",
+                    requests
+                        .iter()
+                        .map(|(_, spec)| spec.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                continue;
+            };
+            let spec: PackageSpec = format!("{name}@{version}").parse()?;
+            for (idx, _) in requests {
+                self.graph[idx]
+                    .dependency_reqs
+                    .insert(name.clone(), (spec.clone(), DepType::Peer));
+                requeued.push(idx);
+            }
+        }
+        Ok(requeued)
+    }
+
+    /// Adds each missing peer dependency to the dependencies of the package
+    /// that asked for it, as requested, and returns those packages so they
+    /// can be processed again. Peers that can't be found at all are left
+    /// alone, and get reported as conflicts.
+    async fn install_peers(&mut self) -> Result<Vec<NodeIndex>, NodeMaintainerError> {
+        let mut requeued = Vec::new();
+        for (name, requests) in self.missing_peers() {
+            for (idx, spec) in requests {
+                if let Err(e) = self.nassun.resolve_spec(spec.clone()).await {
+                    tracing::debug!("Couldn't resolve peer dependency {spec}: {e}");
+                    continue;
+                }
+                self.graph[idx]
+                    .dependency_reqs
+                    .insert(name.clone(), (spec, DepType::Peer));
+                requeued.push(idx);
+            }
+        }
+        Ok(requeued)
+    }
+
+    /// Non-optional peer dependencies that nothing in the tree provides,
+    /// along with the packages asking for each of them.
+    fn missing_peers(&self) -> IndexMap<UniCase<String>, Vec<(NodeIndex, PackageSpec)>> {
+        let mut missing: IndexMap<UniCase<String>, Vec<(NodeIndex, PackageSpec)>> = IndexMap::new();
+        for idx in self.graph.inner.node_indices() {
+            let node = &self.graph[idx];
+            for (name, (requested, optional)) in &node.peer_reqs {
+                if *optional
+                    || node.dependency_reqs.contains_key(name)
+                    || self.graph.resolve_dep(idx, name).is_some()
+                {
+                    continue;
+                }
+                missing
+                    .entry(name.clone())
+                    .or_default()
+                    .push((idx, requested.clone()));
+            }
+        }
+        missing
+    }
+
+    /// Whether `name` was requested for update, meaning any version recorded
+    /// for it in the lockfile should be ignored.
+    fn should_update(&self, name: &UniCase<String>) -> bool {
+        match &self.update {
+            Some(names) => names.is_empty() || names.contains(name),
+            None => false,
+        }
+    }
+
+    /// Whether a locked resolution should be used as-is for a request that
+    /// can't be checked without going to the registry, such as a dist-tag or
+    /// a git ref. Only done in `offline_first` mode.
+    fn trust_lockfile(&self, package: &Package, requested: &PackageSpec) -> bool {
+        if !self.offline_first {
+            return false;
+        }
+        match (package.resolved(), requested.target()) {
+            (
+                PackageResolution::Npm { .. },
+                PackageSpec::Npm {
+                    requested: None | Some(VersionSpec::Tag(_)),
+                    ..
+                },
+            ) => true,
+            (PackageResolution::Git { .. }, PackageSpec::Git(..)) => true,
+            _ => false,
+        }
+    }
+
     async fn satisfy_from_lockfile(
         &self,
         graph: &Graph,
@@ -324,7 +749,9 @@ impl<'a> Resolver<'a> {
             path.pop_back();
             if let Some(lockfile_node) = lockfile.packages().get(&path_str) {
                 if let Some(package) = lockfile_node.to_package(&self.nassun).await? {
-                    if package.resolved().satisfies(requested)? {
+                    if package.resolved().satisfies(requested)?
+                        || self.trust_lockfile(&package, requested)
+                    {
                         return Ok(Some((package, lockfile_node.clone())));
                     } else {
                         // TODO: Log this We found a lockfile node in a place
@@ -343,23 +770,85 @@ impl<'a> Resolver<'a> {
         Ok(None)
     }
 
+    /// Finds the highest version of `name` recorded anywhere in `lockfile`
+    /// that satisfies `requested`, regardless of where it was placed.
+    async fn installed_version(
+        &self,
+        lockfile: &Lockfile,
+        name: &UniCase<String>,
+        requested: &PackageSpec,
+    ) -> Result<Option<(Package, LockfileNode)>, NodeMaintainerError> {
+        let range = match requested.target() {
+            // Aliases could be pointing at a different package entirely.
+            PackageSpec::Npm {
+                name: target_name,
+                requested: Some(VersionSpec::Range(range)),
+                ..
+            } if UniCase::new(target_name) == *name => range,
+            _ => return Ok(None),
+        };
+        let mut candidates = lockfile
+            .packages()
+            .values()
+            .filter(|node| !node.is_root && &node.name == name)
+            .filter_map(|node| node.version.as_ref().map(|version| (version, node)))
+            .filter(|(version, _)| range.satisfies(version))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|(a, _), (b, _)| b.cmp(a));
+        for (_, lockfile_node) in candidates {
+            if let Some(package) = lockfile_node.to_package(&self.nassun).await? {
+                if package.resolved().satisfies(requested)? {
+                    return Ok(Some((package, lockfile_node.clone())));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     fn place_child(
         graph: &mut Graph,
         dep: &NodeDependency,
         package: Package,
         corgi: CorgiManifest,
         target_path: Option<Vec<UniCase<String>>>,
+        hoist: HoistMode,
     ) -> Result<NodeIndex, NodeMaintainerError> {
         let child_name = &dep.name;
         let requested = &dep.spec;
         let dep_type = dep.dep_type;
         let dependent_idx = dep.node_idx;
+        let span = tracing::trace_span!("place", name = %child_name, depth = Empty).entered();
+
+        // A package can legitimately show up again below itself when a
+        // different version of it is in between, but if that's already
+        // happened twice in this chain, the same conflict is repeating and
+        // the nesting would never end.
+        let copies = graph
+            .node_parent_iter(dependent_idx)
+            .filter(|node| {
+                &node.name == child_name && node.package.resolved() == package.resolved()
+            })
+            .count();
+        if copies >= 2 {
+            let id = match package.resolved().npm_version() {
+                Some(version) => format!("{child_name}@{version}"),
+                None => format!("{child_name} ({})", package.resolved()),
+            };
+            return Err(NodeMaintainerError::UnresolvableCycle(
+                id,
+                graph.node_path_string(dependent_idx),
+            ));
+        }
+
         let child_node = Node::new(child_name.clone(), package, corgi, false)?;
         let child_idx = graph.inner.add_node(child_node);
         graph[child_idx].root = graph.root;
         // We needed to generate the node index before setting it in the node,
         // so we do that now.
         graph[child_idx].idx = child_idx;
+        let mut logical_path = graph[dependent_idx].logical_path.clone();
+        logical_path.push(child_name.clone());
+        graph[child_idx].logical_path = logical_path;
 
         // Edges represent the logical dependency relationship (not the
         // hierarchy location).
@@ -373,8 +862,17 @@ impl<'a> Resolver<'a> {
             let len = target.len();
             target.into_iter().take(len - 1).collect::<VecDeque<_>>()
         });
+        let can_hoist = match hoist {
+            HoistMode::Full => true,
+            HoistMode::None => false,
+            HoistMode::WorkspacesOnly => matches!(
+                graph[child_idx].package.resolved(),
+                PackageResolution::Dir { .. }
+            ),
+        };
         let mut target_idx = dependent_idx;
-        let mut parent_idx = Some(dependent_idx);
+        // Without hoisting, the child goes right where its dependent is.
+        let mut parent_idx = can_hoist.then_some(dependent_idx);
         'outer: while let Some(curr_target_idx) = parent_idx {
             if let Some(resolved) = graph.resolve_dep(curr_target_idx, child_name) {
                 for edge_ref in graph.inner.edges_directed(resolved, Direction::Incoming) {
@@ -422,6 +920,7 @@ impl<'a> Resolver<'a> {
             // accordingly.
             child_node.parent = Some(target_idx);
         }
+        span.record("depth", graph[child_idx].depth(graph));
         {
             // Finally, we add the backlink from the parent node to the child.
             let node = &mut graph[target_idx];
@@ -459,3 +958,17 @@ impl<'a> Resolver<'a> {
         Ok(())
     }
 }
+
+/// Whether `version` is allowed by peer dependency request `spec`. Only
+/// registry requests can be checked this way.
+fn peer_allows(spec: &PackageSpec, version: &Version, tags: &HashMap<String, Version>) -> bool {
+    match spec.target() {
+        PackageSpec::Npm { requested, .. } => match requested {
+            None => tags.get("latest") == Some(version),
+            Some(VersionSpec::Tag(tag)) => tags.get(tag) == Some(version),
+            Some(VersionSpec::Version(wanted)) => wanted == version,
+            Some(VersionSpec::Range(range)) => range.satisfies(version),
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,170 @@
+//! Removal of files that packages rarely need at runtime, like tests and
+//! docs, right after they've been extracted.
+
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+
+use oro_common::BuildManifest;
+use walkdir::WalkDir;
+
+use crate::error::{IoContext, NodeMaintainerError};
+
+/// Removes every file and directory in the package at `dir` whose name
+/// matches one of `patterns`. The package's `package.json`, license files,
+/// and its entry points (`main`, `module`, `browser`, `exports` and `bin`,
+/// along with the directories they're in) are always kept.
+///
+/// Packages can opt out of trimming entirely with `"orogene": { "trim":
+/// false }` in their `package.json`.
+pub(crate) fn trim_package(dir: &Path, patterns: &[String]) -> Result<(), NodeMaintainerError> {
+    let manifest = std::fs::read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok());
+    if manifest
+        .as_ref()
+        .and_then(|mani| mani["orogene"]["trim"].as_bool())
+        == Some(false)
+    {
+        return Ok(());
+    }
+    let kept = kept_paths(dir, manifest.as_ref());
+    let mut entries = WalkDir::new(dir).min_depth(1).into_iter();
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let path = entry
+            .path()
+            .strip_prefix(dir)
+            .expect("walked paths are always inside the package");
+        let is_dir = entry.file_type().is_dir();
+        if (!is_dir && is_license(path))
+            || !patterns
+                .iter()
+                .any(|pattern| pattern_matches(pattern, path, is_dir))
+        {
+            continue;
+        }
+        if is_dir {
+            if kept.iter().any(|kept| kept.starts_with(path)) {
+                continue;
+            }
+            entries.skip_current_dir();
+            std::fs::remove_dir_all(entry.path()).io_context(|| {
+                format!(
+                    "Failed to remove trimmed directory at {}.",
+                    entry.path().display()
+                )
+            })?;
+        } else if !kept.iter().any(|kept| kept == path) {
+            std::fs::remove_file(entry.path()).io_context(|| {
+                format!(
+                    "Failed to remove trimmed file at {}.",
+                    entry.path().display()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Paths, relative to the package root, that trimming must never remove.
+fn kept_paths(dir: &Path, manifest: Option<&serde_json::Value>) -> Vec<PathBuf> {
+    let mut kept = vec![PathBuf::from("package.json")];
+    if let Some(main) = manifest.and_then(|mani| mani["main"].as_str()) {
+        let main = normalize(Path::new(main));
+        // `main` can leave out its extension, or point at a directory with
+        // an index file in it.
+        for ext in ["js", "json", "node"] {
+            let mut with_ext = OsString::from(main.as_os_str());
+            with_ext.push(format!(".{ext}"));
+            kept.push(with_ext.into());
+            kept.push(main.join(format!("index.{ext}")));
+        }
+        kept.push(main);
+    }
+    if let Some(mani) = manifest {
+        for field in ["module", "browser", "exports"] {
+            collect_targets(&mani[field], &mut kept);
+        }
+    }
+    if let Ok(build_mani) = BuildManifest::from_path(dir.join("package.json")) {
+        kept.extend(build_mani.bin.values().map(|bin| normalize(bin)));
+    }
+    kept
+}
+
+/// Adds every path in a `module`, `browser`, or `exports` field to `kept`,
+/// going through conditions and subpaths. Subpath patterns like
+/// `./lib/*.js` keep the whole directory they point into.
+fn collect_targets(value: &serde_json::Value, kept: &mut Vec<PathBuf>) {
+    match value {
+        serde_json::Value::String(target) => {
+            let target = target.split_once('*').map(|(dir, _)| dir).unwrap_or(target);
+            let target = normalize(Path::new(target));
+            if !target.as_os_str().is_empty() {
+                kept.push(target);
+            }
+        }
+        serde_json::Value::Array(targets) => {
+            for target in targets {
+                collect_targets(target, kept);
+            }
+        }
+        serde_json::Value::Object(targets) => {
+            for target in targets.values() {
+                collect_targets(target, kept);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether `pattern` matches the file or directory at `path`. `*` matches
+/// any number of characters in a name, a trailing `/` only matches
+/// directories, and a leading `/` only matches right at the package root.
+fn pattern_matches(pattern: &str, path: &Path, is_dir: bool) -> bool {
+    let (pattern, dirs_only) = match pattern.strip_suffix('/') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    if dirs_only && !is_dir {
+        return false;
+    }
+    let Some(name) = path.file_name() else {
+        return false;
+    };
+    let name = name.to_string_lossy();
+    match pattern.strip_prefix('/') {
+        Some(pattern) => path.components().count() == 1 && glob_match(pattern, &name),
+        None => glob_match(pattern, &name),
+    }
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => name
+            .strip_prefix(prefix)
+            .map(|name| {
+                (0..=name.len())
+                    .filter(|i| name.is_char_boundary(*i))
+                    .any(|i| glob_match(rest, &name[i..]))
+            })
+            .unwrap_or(false),
+    }
+}
+
+fn is_license(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    name.starts_with("license") || name.starts_with("licence")
+}
+
+/// `path` without any `.` or leading `/` components, so it can be compared
+/// against walked paths.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect()
+}
@@ -0,0 +1,801 @@
+use std::ffi::OsStr;
+
+use indexmap::IndexMap;
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::{HoistMode, NodeMaintainer};
+use oro_common::CorgiManifest;
+use serde_json::json;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+#[async_std::test]
+async fn extract_filter_skips_files() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        &[
+            ("package.json", r#"{"name": "a", "version": "1.0.0"}"#),
+            ("index.js", "module.exports = 1"),
+            ("README.md", "# a"),
+            ("test/index.js", "assert(true)"),
+        ],
+    )
+    .await?;
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .root(dir.path())
+        .cache(dir.path().join("cache"))
+        .hoisted(true)
+        .extract_filter(|path| {
+            !path.starts_with("test") && path.extension() != Some(OsStr::new("md"))
+        })
+        .resolve_manifest(CorgiManifest {
+            dependencies: IndexMap::from([("a".to_string(), "^1.0.0".to_string())]),
+            ..Default::default()
+        })
+        .await?;
+    nm.extract().await?;
+
+    let pkg_dir = dir.path().join("node_modules").join("a");
+    assert!(pkg_dir.join("package.json").exists());
+    assert!(pkg_dir.join("index.js").exists());
+    assert!(!pkg_dir.join("README.md").exists());
+    assert!(!pkg_dir.join("test").exists());
+    Ok(())
+}
+
+#[async_std::test]
+async fn trim_install_skips_dev_files() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        &[
+            (
+                "package.json",
+                r#"{"name": "a", "version": "1.0.0", "main": "test/harness", "bin": {"a": "bin/a.md"}}"#,
+            ),
+            ("test/harness.js", "module.exports = 1"),
+            ("test/helpers.js", "module.exports = 2"),
+            ("bin/a.md", "#!/usr/bin/env node"),
+            ("lib/index.js", "module.exports = 3"),
+            ("lib/index.js.map", "{}"),
+            ("lib/__tests__/index.test.js", "assert(true)"),
+            ("README.md", "# a"),
+            ("LICENSE.md", "MIT"),
+            (".github/workflows/ci.yml", "on: push"),
+        ],
+    )
+    .await?;
+    // Packages can opt out of being trimmed.
+    mock_packument(
+        &mock_server,
+        "b",
+        &[(
+            "1.0.0",
+            json!({}),
+            tarball(&[
+                (
+                    "package.json",
+                    r#"{"name": "b", "version": "1.0.0", "orogene": {"trim": false}}"#,
+                ),
+                ("README.md", "# b"),
+                ("test/index.js", "assert(true)"),
+            ])?,
+        )],
+    )
+    .await;
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .root(dir.path())
+        .cache(dir.path().join("cache"))
+        .trim_install(true)
+        .resolve_manifest(CorgiManifest {
+            dependencies: IndexMap::from([
+                ("a".to_string(), "^1.0.0".to_string()),
+                ("b".to_string(), "^1.0.0".to_string()),
+            ]),
+            ..Default::default()
+        })
+        .await?;
+    nm.extract().await?;
+
+    let pkg_dir = dir.path().join("node_modules").join("a");
+    for kept in [
+        "package.json",
+        "test/harness.js",
+        "test/helpers.js",
+        "bin/a.md",
+        "lib/index.js",
+        "LICENSE.md",
+    ] {
+        assert!(pkg_dir.join(kept).exists(), "{kept} should be kept");
+    }
+    for trimmed in ["lib/index.js.map", "lib/__tests__", "README.md", ".github"] {
+        assert!(
+            !pkg_dir.join(trimmed).exists(),
+            "{trimmed} should be trimmed"
+        );
+    }
+    let pkg_dir = dir.path().join("node_modules").join("b");
+    for kept in ["README.md", "test/index.js"] {
+        assert!(pkg_dir.join(kept).exists(), "{kept} should be kept");
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn trim_install_keeps_entry_points() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    let manifest = json!({
+        "name": "a",
+        "version": "1.0.0",
+        "module": "test/esm.js",
+        "browser": {"./server.js": "./tests/browser.js", "./fs.js": false},
+        "exports": {
+            ".": {
+                "import": "./test/index.mjs",
+                "require": ["./test/index.cjs"],
+            },
+            "./docs": "./docs/README.md",
+            "./features/*": "./__tests__/features/*.js",
+        },
+    })
+    .to_string();
+    mock_package(
+        &mock_server,
+        &[
+            ("package.json", manifest.as_str()),
+            ("test/esm.js", "export default 1"),
+            ("test/index.mjs", "export default 2"),
+            ("test/index.cjs", "module.exports = 3"),
+            ("tests/browser.js", "module.exports = 4"),
+            ("docs/README.md", "# docs"),
+            ("docs/CHANGELOG.md", "# changes"),
+            ("__tests__/features/a.js", "module.exports = 5"),
+            ("README.md", "# a"),
+        ],
+    )
+    .await?;
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .root(dir.path())
+        .cache(dir.path().join("cache"))
+        .trim_install(true)
+        .resolve_manifest(CorgiManifest {
+            dependencies: IndexMap::from([("a".to_string(), "^1.0.0".to_string())]),
+            ..Default::default()
+        })
+        .await?;
+    nm.extract().await?;
+
+    let pkg_dir = dir.path().join("node_modules").join("a");
+    for kept in [
+        "test/esm.js",
+        "test/index.mjs",
+        "test/index.cjs",
+        "tests/browser.js",
+        "docs/README.md",
+        "__tests__/features/a.js",
+    ] {
+        assert!(pkg_dir.join(kept).exists(), "{kept} should be kept");
+    }
+    for trimmed in ["docs/CHANGELOG.md", "README.md"] {
+        assert!(
+            !pkg_dir.join(trimmed).exists(),
+            "{trimmed} should be trimmed"
+        );
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn extract_reports_bytes_written() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    let files = [
+        ("package.json", r#"{"name": "a", "version": "1.0.0"}"#),
+        ("index.js", "module.exports = 1"),
+        ("lib/util.js", "module.exports = 2"),
+    ];
+    mock_package(&mock_server, &files).await?;
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let written = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let nm = {
+        let written = written.clone();
+        NodeMaintainer::builder()
+            .concurrency(1)
+            .registry(mock_server.uri().parse().into_diagnostic()?)
+            .root(dir.path())
+            .cache(dir.path().join("cache"))
+            .on_extract_bytes(move |pkg, bytes| {
+                assert_eq!(pkg.name(), "a");
+                written.fetch_add(bytes, std::sync::atomic::Ordering::SeqCst);
+            })
+            .resolve_manifest(CorgiManifest {
+                dependencies: IndexMap::from([("a".to_string(), "^1.0.0".to_string())]),
+                ..Default::default()
+            })
+            .await?
+    };
+    nm.extract().await?;
+
+    assert_eq!(
+        written.load(std::sync::atomic::Ordering::SeqCst),
+        files
+            .iter()
+            .map(|(_, contents)| contents.len())
+            .sum::<usize>()
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn extract_nested_packages_concurrently() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    let shared = |version: &str| {
+        tarball(&[(
+            "package.json",
+            &format!(r#"{{"name": "shared", "version": "{version}"}}"#),
+        )])
+    };
+    mock_packument(
+        &mock_server,
+        "shared",
+        &[
+            ("1.0.0", json!({}), shared("1.0.0")?),
+            ("2.0.0", json!({}), shared("2.0.0")?),
+        ],
+    )
+    .await;
+    // Every one of these depends on a version of `shared` that conflicts
+    // with the hoisted one, so they each get their own nested copy.
+    let names = (0..16).map(|i| format!("p{i}")).collect::<Vec<_>>();
+    for name in &names {
+        let manifest = format!(
+            r#"{{"name": "{name}", "version": "1.0.0", "dependencies": {{"shared": "^2.0.0"}}}}"#
+        );
+        mock_packument(
+            &mock_server,
+            name,
+            &[(
+                "1.0.0",
+                json!({ "shared": "^2.0.0" }),
+                tarball(&[("package.json", manifest.as_str()), ("index.js", "")])?,
+            )],
+        )
+        .await;
+    }
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let mut dependencies = IndexMap::from([("shared".to_string(), "^1.0.0".to_string())]);
+    dependencies.extend(
+        names
+            .iter()
+            .map(|name| (name.clone(), "^1.0.0".to_string())),
+    );
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .extract_concurrency(64)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .root(dir.path())
+        .cache(dir.path().join("cache"))
+        .hoisted(true)
+        .resolve_manifest(CorgiManifest {
+            dependencies,
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(nm.extract().await?, 33);
+
+    let node_modules = dir.path().join("node_modules");
+    assert!(node_modules.join("shared").join("package.json").exists());
+    for name in &names {
+        let pkg_dir = node_modules.join(name);
+        assert!(pkg_dir.join("package.json").exists(), "{name} is missing");
+        assert!(pkg_dir.join("index.js").exists(), "{name} is missing files");
+        let nested = std::fs::read_to_string(
+            pkg_dir
+                .join("node_modules")
+                .join("shared")
+                .join("package.json"),
+        )
+        .into_diagnostic()?;
+        assert!(nested.contains("2.0.0"));
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn extract_without_hoisting() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    for (name, dependencies) in [
+        ("a", json!({ "b": "^1.0.0" })),
+        ("b", json!({ "c": "^1.0.0" })),
+        ("c", json!({})),
+    ] {
+        let manifest = json!({
+            "name": name,
+            "version": "1.0.0",
+            "dependencies": dependencies.clone(),
+        });
+        mock_packument(
+            &mock_server,
+            name,
+            &[(
+                "1.0.0",
+                dependencies,
+                tarball(&[("package.json", &manifest.to_string())])?,
+            )],
+        )
+        .await;
+    }
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .root(dir.path())
+        .cache(dir.path().join("cache"))
+        .hoisted(true)
+        .hoist(HoistMode::None)
+        .resolve_manifest(CorgiManifest {
+            dependencies: IndexMap::from([("a".to_string(), "^1.0.0".to_string())]),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(nm.extract().await?, 3);
+
+    let node_modules = dir.path().join("node_modules");
+    let b = node_modules.join("a").join("node_modules").join("b");
+    let c = b.join("node_modules").join("c");
+    assert!(b.join("package.json").exists());
+    assert!(c.join("package.json").exists());
+    assert!(!node_modules.join("b").exists(), "b should not be hoisted");
+    assert!(!node_modules.join("c").exists(), "c should not be hoisted");
+    Ok(())
+}
+
+#[async_std::test]
+async fn extract_from_local_tarballs() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    // Everything comes from the lockfile and the bundle directory, so the
+    // registry should never be asked for anything.
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+    let a = tarball(&[
+        (
+            "package.json",
+            r#"{"name": "a", "version": "1.0.0", "dependencies": {"b": "^2.0.0"}}"#,
+        ),
+        ("index.js", "module.exports = 'a'"),
+    ])?;
+    let b = tarball(&[
+        ("package.json", r#"{"name": "b", "version": "2.0.0"}"#),
+        ("index.js", "module.exports = 'b'"),
+    ])?;
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let bundle = dir.path().join("bundle");
+    std::fs::create_dir_all(&bundle).into_diagnostic()?;
+    std::fs::write(bundle.join("a-1.0.0.tgz"), &a).into_diagnostic()?;
+    std::fs::write(bundle.join("b-2.0.0.tgz"), &b).into_diagnostic()?;
+
+    let uri = mock_server.uri();
+    let lock = format!(
+        r#"
+        lockfile-version 1
+        root {{
+            dependencies {{
+                a "^1.0.0"
+            }}
+        }}
+        pkg "a" {{
+            version "1.0.0"
+            resolved "{uri}/a/-/a-1.0.0.tgz"
+            integrity "{}"
+            dependencies {{
+                b "^2.0.0"
+            }}
+        }}
+        pkg "b" {{
+            version "2.0.0"
+            resolved "{uri}/b/-/b-2.0.0.tgz"
+            integrity "{}"
+        }}
+        "#,
+        ssri::Integrity::from(&a),
+        ssri::Integrity::from(&b),
+    );
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(uri.parse().into_diagnostic()?)
+        .root(dir.path())
+        .cache(dir.path().join("cache"))
+        .hoisted(true)
+        .kdl_lock(lock)?
+        .offline_first(true)
+        .local_tarball_dir(&bundle)
+        .resolve_manifest(CorgiManifest {
+            dependencies: IndexMap::from([("a".to_string(), "^1.0.0".to_string())]),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(nm.extract().await?, 2);
+
+    let node_modules = dir.path().join("node_modules");
+    assert!(node_modules.join("a").join("index.js").exists());
+    assert!(node_modules.join("b").join("index.js").exists());
+    mock_server.verify().await;
+    Ok(())
+}
+
+#[async_std::test]
+async fn local_tarballs_with_colliding_names() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+    let tgz = |name: &str| {
+        let manifest = format!(r#"{{"name": "{name}", "version": "1.0.0"}}"#);
+        let index = format!("module.exports = '{name}'");
+        tarball(&[
+            ("package.json", manifest.as_str()),
+            ("index.js", index.as_str()),
+        ])
+    };
+    let foo = tgz("foo")?;
+    let scoped_foo = tgz("@scope/foo")?;
+    let baz = tgz("@scope/baz")?;
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let bundle = dir.path().join("bundle");
+    std::fs::create_dir_all(&bundle).into_diagnostic()?;
+    // `@scope/foo`'s URL file name is the same as `foo`'s, so it has to be
+    // found under the name `npm pack` gives it instead.
+    std::fs::write(bundle.join("foo-1.0.0.tgz"), &foo).into_diagnostic()?;
+    std::fs::write(bundle.join("scope-foo-1.0.0.tgz"), &scoped_foo).into_diagnostic()?;
+    // A stale tarball under the packed name shouldn't stop us from using
+    // the right one under the URL's name.
+    std::fs::write(bundle.join("scope-baz-1.0.0.tgz"), &foo).into_diagnostic()?;
+    std::fs::write(bundle.join("baz-1.0.0.tgz"), &baz).into_diagnostic()?;
+
+    let uri = mock_server.uri();
+    let lock = format!(
+        r#"
+        lockfile-version 2
+        root {{
+            dependencies {{
+                foo "^1.0.0"
+                "@scope/foo" "^1.0.0"
+                "@scope/baz" "^1.0.0"
+            }}
+        }}
+        pkg "foo" {{
+            version "1.0.0"
+            resolved "{uri}/foo/-/foo-1.0.0.tgz"
+            integrity "{}"
+        }}
+        pkg "@scope/foo" {{
+            version "1.0.0"
+            resolved "{uri}/@scope/foo/-/foo-1.0.0.tgz"
+            integrity "{}"
+        }}
+        pkg "@scope/baz" {{
+            version "1.0.0"
+            resolved "{uri}/@scope/baz/-/baz-1.0.0.tgz"
+            integrity "{}"
+        }}
+        "#,
+        ssri::Integrity::from(&foo),
+        ssri::Integrity::from(&scoped_foo),
+        ssri::Integrity::from(&baz),
+    );
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(uri.parse().into_diagnostic()?)
+        .root(dir.path())
+        .cache(dir.path().join("cache"))
+        .hoisted(true)
+        .kdl_lock(lock)?
+        .offline_first(true)
+        .local_tarball_dir(&bundle)
+        .resolve_manifest(CorgiManifest {
+            dependencies: IndexMap::from([
+                ("foo".to_string(), "^1.0.0".to_string()),
+                ("@scope/foo".to_string(), "^1.0.0".to_string()),
+                ("@scope/baz".to_string(), "^1.0.0".to_string()),
+            ]),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(nm.extract().await?, 3);
+
+    let node_modules = dir.path().join("node_modules");
+    for name in ["foo", "@scope/foo", "@scope/baz"] {
+        assert_eq!(
+            std::fs::read_to_string(node_modules.join(name).join("index.js")).into_diagnostic()?,
+            format!("module.exports = '{name}'")
+        );
+    }
+    mock_server.verify().await;
+    Ok(())
+}
+
+#[async_std::test]
+async fn separate_store_and_metadata_dirs() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        &[
+            ("package.json", r#"{"name": "a", "version": "1.0.0"}"#),
+            ("index.js", "module.exports = 1"),
+        ],
+    )
+    .await?;
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let store = dir.path().join("store");
+    let metadata = dir.path().join("metadata");
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .root(dir.path())
+        .store_dir(&store)
+        .metadata_cache_dir(&metadata)
+        // Setting the general cache last shouldn't override either of them.
+        .cache(dir.path().join("cache"))
+        .hoisted(true)
+        .resolve_manifest(CorgiManifest {
+            dependencies: IndexMap::from([("a".to_string(), "^1.0.0".to_string())]),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(nm.extract().await?, 1);
+
+    let keys = |cache: &std::path::Path| {
+        cacache::list_sync(cache)
+            .map(|entry| entry.map(|entry| entry.key))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .into_diagnostic()
+    };
+    let packument_url = format!("{}/a", mock_server.uri());
+    let store_keys = keys(&store)?;
+    let metadata_keys = keys(&metadata)?;
+    assert!(store_keys
+        .iter()
+        .any(|key| key.starts_with("nassun::package::")));
+    assert!(!store_keys.iter().any(|key| key.ends_with(&packument_url)));
+    assert!(metadata_keys
+        .iter()
+        .any(|key| key.ends_with(&packument_url)));
+    assert!(!metadata_keys
+        .iter()
+        .any(|key| key.starts_with("nassun::package::")));
+    Ok(())
+}
+
+#[async_std::test]
+async fn failed_extraction_leaves_nothing_behind() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    let expected = tarball(&[("package.json", r#"{"name": "a", "version": "1.0.0"}"#)])?;
+    // The registry serves a different tarball than the one the packument
+    // describes, so extraction gets partway through before the integrity
+    // check fails.
+    let served = tarball(&[
+        ("package.json", r#"{"name": "a", "version": "1.0.0"}"#),
+        ("index.js", "module.exports = 'tampered'"),
+    ])?;
+    Mock::given(method("GET"))
+        .and(path("a/-/a-1.0.0.tgz"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(served))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("a"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "a",
+            "dist-tags": { "latest": "1.0.0" },
+            "versions": {
+                "1.0.0": {
+                    "name": "a",
+                    "version": "1.0.0",
+                    "dist": {
+                        "tarball": format!("{}/a/-/a-1.0.0.tgz", mock_server.uri()),
+                        "integrity": ssri::Integrity::from(&expected).to_string(),
+                    }
+                }
+            },
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .root(dir.path())
+        .cache(dir.path().join("cache"))
+        .hoisted(true)
+        .resolve_manifest(CorgiManifest {
+            dependencies: IndexMap::from([("a".to_string(), "^1.0.0".to_string())]),
+            ..Default::default()
+        })
+        .await?;
+    assert!(nm.extract().await.is_err());
+
+    let leftovers = std::fs::read_dir(dir.path().join("node_modules"))
+        .into_diagnostic()?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .into_diagnostic()?;
+    assert!(leftovers.is_empty(), "found leftovers: {leftovers:?}");
+    Ok(())
+}
+
+#[async_std::test]
+async fn extract_from_directory_mirror() -> Result<()> {
+    let mirror = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mirror");
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    // The packuments point at an unresolvable host, so this only passes if
+    // everything comes out of the mirror.
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(url::Url::from_directory_path(&mirror).unwrap())
+        .root(dir.path())
+        .cache(dir.path().join("cache"))
+        .hoisted(true)
+        .resolve_manifest(CorgiManifest {
+            dependencies: IndexMap::from([("a".to_string(), "^1.0.0".to_string())]),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(nm.extract().await?, 2);
+
+    let node_modules = dir.path().join("node_modules");
+    assert_eq!(
+        std::fs::read_to_string(node_modules.join("a/index.js")).into_diagnostic()?,
+        "module.exports = require('@scope/b')"
+    );
+    assert_eq!(
+        std::fs::read_to_string(node_modules.join("@scope/b/index.js")).into_diagnostic()?,
+        "module.exports = 'b'"
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+#[async_std::test]
+async fn script_env_only_for_named_package() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    for name in ["a", "b"] {
+        let manifest = json!({
+            "name": name,
+            "version": "1.0.0",
+            "scripts": { "postinstall": "echo \"[$ORO_TEST_INSTALL_ENV]\" > env.txt" },
+        });
+        mock_packument(
+            &mock_server,
+            name,
+            &[(
+                "1.0.0",
+                json!({}),
+                tarball(&[("package.json", &manifest.to_string())])?,
+            )],
+        )
+        .await;
+    }
+
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .root(dir.path())
+        .cache(dir.path().join("cache"))
+        .hoisted(true)
+        .script_env("a", "ORO_TEST_INSTALL_ENV", "hello")
+        .resolve_manifest(CorgiManifest {
+            dependencies: IndexMap::from([
+                ("a".to_string(), "^1.0.0".to_string()),
+                ("b".to_string(), "^1.0.0".to_string()),
+            ]),
+            ..Default::default()
+        })
+        .await?;
+    nm.extract().await?;
+    nm.rebuild(false).await?;
+
+    let node_modules = dir.path().join("node_modules");
+    assert_eq!(
+        std::fs::read_to_string(node_modules.join("a/env.txt")).into_diagnostic()?,
+        "[hello]\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(node_modules.join("b/env.txt")).into_diagnostic()?,
+        "[]\n"
+    );
+    Ok(())
+}
+
+/// Builds a package tarball made up of `files`.
+fn tarball(files: &[(&str, &str)]) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::default(),
+    ));
+    for (path, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("package/{path}"), contents.as_bytes())
+            .into_diagnostic()?;
+    }
+    builder
+        .into_inner()
+        .into_diagnostic()?
+        .finish()
+        .into_diagnostic()
+}
+
+/// Serves a single-version packument for `a`, along with a tarball made up of
+/// `files`.
+async fn mock_package(mock_server: &MockServer, files: &[(&str, &str)]) -> Result<()> {
+    mock_packument(mock_server, "a", &[("1.0.0", json!({}), tarball(files)?)]).await;
+    Ok(())
+}
+
+/// Serves a packument for `name` with the given versions, dependencies, and
+/// tarballs. The latest version is the last one.
+async fn mock_packument(
+    mock_server: &MockServer,
+    name: &str,
+    versions: &[(&str, serde_json::Value, Vec<u8>)],
+) {
+    let mut packument_versions = serde_json::Map::new();
+    for (version, dependencies, tarball) in versions {
+        let tarball_path = format!("{name}/-/{name}-{version}.tgz");
+        packument_versions.insert(
+            version.to_string(),
+            json!({
+                "name": name,
+                "version": version,
+                "dependencies": dependencies.clone(),
+                "dist": {
+                    "tarball": format!("{}/{tarball_path}", mock_server.uri()),
+                    "integrity": ssri::Integrity::from(tarball).to_string(),
+                }
+            }),
+        );
+        Mock::given(method("GET"))
+            .and(path(tarball_path))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(tarball.clone()))
+            .mount(mock_server)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(path(name))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": name,
+            "dist-tags": { "latest": versions.last().map(|(v, _, _)| *v) },
+            "versions": packument_versions,
+        })))
+        .mount(mock_server)
+        .await;
+}
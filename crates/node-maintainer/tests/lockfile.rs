@@ -0,0 +1,86 @@
+use miette::Result;
+use node_maintainer::{Lockfile, NodeMaintainerError, LOCKFILE_VERSION};
+use pretty_assertions::assert_eq;
+
+const V2_LOCKFILE: &str = include_str!("fixtures/lockfile-v2.kdl");
+const V1_LOCKFILE: &str = include_str!("fixtures/lockfile-v1.kdl");
+const UNVERSIONED_LOCKFILE: &str = include_str!("fixtures/lockfile-unversioned.kdl");
+
+#[test]
+fn read_v2_lockfile() -> Result<()> {
+    let lockfile = Lockfile::from_kdl(V2_LOCKFILE)?;
+    assert_eq!(lockfile.version(), LOCKFILE_VERSION);
+    assert_eq!(lockfile.packages().len(), 2);
+    let c = &lockfile.packages()["c"];
+    assert!(c.platform_known);
+    assert_eq!(c.os, vec!["darwin", "linux"]);
+    assert_eq!(c.cpu, vec!["!ia32"]);
+    assert_eq!(lockfile.to_kdl().to_string(), V2_LOCKFILE);
+    Ok(())
+}
+
+#[test]
+fn migrate_v1_lockfile() -> Result<()> {
+    // v1 lockfiles didn't record platform info, so it's unknown, rather
+    // than unrestricted, until the packages are looked up again.
+    let lockfile = Lockfile::from_kdl(V1_LOCKFILE)?;
+    assert_eq!(lockfile.version(), LOCKFILE_VERSION);
+    assert_eq!(lockfile.packages().len(), 2);
+    assert!(!lockfile.root().platform_known);
+    assert!(lockfile
+        .packages()
+        .values()
+        .all(|node| !node.platform_known && node.os.is_empty()));
+    assert_eq!(
+        lockfile.to_kdl().to_string(),
+        V1_LOCKFILE.replace("lockfile-version 1", "lockfile-version 2")
+    );
+    Ok(())
+}
+
+#[test]
+fn migrate_unversioned_lockfile() -> Result<()> {
+    // Lockfiles from before `lockfile-version` existed get read as v1, and
+    // come out the other end as the current version.
+    let lockfile = Lockfile::from_kdl(UNVERSIONED_LOCKFILE)?;
+    assert_eq!(lockfile.version(), LOCKFILE_VERSION);
+    assert_eq!(lockfile, Lockfile::from_kdl(V1_LOCKFILE)?);
+    assert_eq!(
+        lockfile.to_kdl().to_string(),
+        V1_LOCKFILE.replace("lockfile-version 1", "lockfile-version 2")
+    );
+    Ok(())
+}
+
+#[test]
+fn reject_future_lockfile() {
+    let future = V2_LOCKFILE.replace("lockfile-version 2", "lockfile-version 999");
+    let err = Lockfile::from_kdl(future).unwrap_err();
+    assert!(matches!(
+        err,
+        NodeMaintainerError::UnsupportedLockfileVersion(999)
+    ));
+}
+
+#[test]
+fn lockfile_schema() {
+    let schema = Lockfile::json_schema();
+    let properties = &schema["properties"];
+    assert_eq!(properties["lockfile-version"]["const"], LOCKFILE_VERSION);
+    for field in [
+        "version",
+        "resolved",
+        "integrity",
+        "dependencies",
+        "dev-dependencies",
+        "os",
+    ] {
+        assert!(
+            properties["root"]["properties"].get(field).is_some(),
+            "missing root {field}"
+        );
+    }
+    assert!(properties["pkg"]["items"]["properties"]
+        .get("path")
+        .is_some());
+}
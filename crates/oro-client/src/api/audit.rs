@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+
+use crate::{OroClient, OroClientError};
+
+/// How serious a security advisory is, from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Moderate => "moderate",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A security advisory affecting some versions of a package, as returned by
+/// the `/-/npm/v1/security/advisories/bulk` API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Advisory {
+    pub id: u64,
+    pub url: String,
+    pub title: String,
+    pub severity: Severity,
+    pub vulnerable_versions: String,
+}
+
+impl OroClient {
+    /// Looks up security advisories for `packages`, a map of package names
+    /// to the versions of each that are installed. Packages with no
+    /// advisories are left out of the result.
+    pub async fn bulk_advisories(
+        &self,
+        packages: &BTreeMap<String, Vec<String>>,
+    ) -> Result<BTreeMap<String, Vec<Advisory>>, OroClientError> {
+        let url = self.registry.join("-/npm/v1/security/advisories/bulk")?;
+        let text = self
+            .client_uncached
+            .post(url.clone())
+            .header("X-Oro-Registry", self.registry.to_string())
+            .header(CONTENT_TYPE, "application/json")
+            .body(serde_json::to_string(packages).expect("string maps always serialize"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        serde_json::from_str(&text)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use maplit::btreemap;
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[async_std::test]
+    async fn bulk_advisories() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+
+        Mock::given(method("POST"))
+            .and(path("-/npm/v1/security/advisories/bulk"))
+            .and(body_json(json!({ "oro": ["1.0.0"], "safe": ["2.0.0"] })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "oro": [{
+                    "id": 1,
+                    "url": "https://example.com/advisories/1",
+                    "title": "Prototype pollution",
+                    "severity": "high",
+                    "vulnerable_versions": "<1.0.1",
+                    "cwe": ["CWE-1321"],
+                }],
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let packages = btreemap! {
+            "oro".to_string() => vec!["1.0.0".to_string()],
+            "safe".to_string() => vec!["2.0.0".to_string()],
+        };
+        assert_eq!(
+            client.bulk_advisories(&packages).await?,
+            btreemap! {
+                "oro".to_string() => vec![Advisory {
+                    id: 1,
+                    url: "https://example.com/advisories/1".into(),
+                    title: "Prototype pollution".into(),
+                    severity: Severity::High,
+                    vulnerable_versions: "<1.0.1".into(),
+                }],
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn severity_order() {
+        assert!(Severity::Info < Severity::Low);
+        assert!(Severity::Moderate < Severity::High);
+        assert!(Severity::High < Severity::Critical);
+    }
+}
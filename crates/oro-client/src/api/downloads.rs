@@ -0,0 +1,99 @@
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::{OroClient, OroClientError};
+
+/// Download count for a package over a period, as returned by the
+/// `/downloads/point/<period>/<package>` API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadCount {
+    pub downloads: u64,
+    pub start: String,
+    pub end: String,
+    pub package: String,
+}
+
+impl OroClient {
+    /// Fetches the number of downloads for `package_name` over `period`,
+    /// such as `last-day`, `last-week`, or `last-month`. Returns `None` if
+    /// the registry doesn't support download counts.
+    pub async fn downloads(
+        &self,
+        period: &str,
+        package_name: &str,
+    ) -> Result<Option<DownloadCount>, OroClientError> {
+        let url = self
+            .downloads_api()?
+            .join(&format!("downloads/point/{period}/{package_name}"))?;
+        let res = self
+            .client
+            .get(url.clone())
+            .header("X-Oro-Registry", self.registry.to_string())
+            .send()
+            .await?;
+        if matches!(
+            res.status(),
+            StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        ) {
+            return Ok(None);
+        }
+        let text = res.error_for_status()?.text().await?;
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+    }
+
+    /// The public npm registry serves download counts from a separate host.
+    fn downloads_api(&self) -> Result<Url, OroClientError> {
+        if self.registry.host_str() == Some("registry.npmjs.org") {
+            Ok("https://api.npmjs.org/".parse()?)
+        } else {
+            Ok(self.registry.clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[async_std::test]
+    async fn downloads() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+
+        Mock::given(method("GET"))
+            .and(path("downloads/point/last-week/oro"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "downloads": 1234,
+                "start": "2023-01-01",
+                "end": "2023-01-07",
+                "package": "oro",
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert_eq!(
+            client.downloads("last-week", "oro").await?,
+            Some(DownloadCount {
+                downloads: 1234,
+                start: "2023-01-01".into(),
+                end: "2023-01-07".into(),
+                package: "oro".into(),
+            })
+        );
+        assert_eq!(
+            client.downloads("last-week", "missing").await?,
+            None,
+            "registries without download counts aren't an error"
+        );
+        Ok(())
+    }
+}
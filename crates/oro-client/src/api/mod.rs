@@ -1,5 +1,41 @@
+pub mod audit;
+pub mod downloads;
 pub mod login;
 pub mod logout;
 pub mod packument;
 pub mod ping;
 pub mod stream_external;
+
+/// Serves `body` to every request on a local port, as a chunked response
+/// with no `Content-Length`, so its size isn't known until it's been read.
+#[cfg(test)]
+pub(crate) fn serve_chunked(body: Vec<u8>) -> url::Url {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap())
+        .parse()
+        .unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut byte = [0u8];
+            while !request.ends_with(b"\r\n\r\n") {
+                stream.read_exact(&mut byte).unwrap();
+                request.push(byte[0]);
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n"
+            );
+            for chunk in body.chunks(256) {
+                let _ = write!(stream, "{:x}\r\n", chunk.len());
+                let _ = stream.write_all(chunk);
+                let _ = stream.write_all(b"\r\n");
+            }
+            let _ = stream.write_all(b"0\r\n\r\n");
+        }
+    });
+    url
+}
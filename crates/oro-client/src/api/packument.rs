@@ -1,5 +1,6 @@
+use futures::StreamExt;
 use oro_common::{CorgiPackument, Packument};
-use reqwest::{StatusCode, Url};
+use reqwest::{header::HeaderMap, Response, StatusCode, Url};
 
 use crate::{OroClient, OroClientError};
 
@@ -11,25 +12,75 @@ impl OroClient {
         &self,
         package_name: impl AsRef<str>,
     ) -> Result<Packument, OroClientError> {
-        let url = self.registry.join(package_name.as_ref())?;
+        Ok(self.packument_with_cache_hit(package_name).await?.0)
+    }
+
+    pub async fn corgi_packument(
+        &self,
+        package_name: impl AsRef<str>,
+    ) -> Result<CorgiPackument, OroClientError> {
+        Ok(self.corgi_packument_with_cache_hit(package_name).await?.0)
+    }
+
+    /// Like [`Self::packument`], but also reports whether the response was
+    /// served out of the HTTP cache instead of the registry.
+    pub async fn packument_with_cache_hit(
+        &self,
+        package_name: impl AsRef<str>,
+    ) -> Result<(Packument, bool), OroClientError> {
+        let url = self.packument_url(package_name.as_ref())?;
         tracing::trace!(
             "fetching packument for {} from {}",
             package_name.as_ref(),
             url
         );
-        let text = self.packument_impl(package_name, &url, false).await?;
-        serde_json::from_str(&text)
-            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+        let (body, headers) = self.packument_impl(package_name, &url, false).await?;
+        let text = String::from_utf8_lossy(&body).into_owned();
+        let packument = serde_json::from_str(&text)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))?;
+        Ok((packument, served_from_cache(&headers)))
     }
 
-    pub async fn corgi_packument(
+    /// Like [`Self::corgi_packument`], but also reports whether the response
+    /// was served out of the HTTP cache instead of the registry.
+    pub async fn corgi_packument_with_cache_hit(
         &self,
         package_name: impl AsRef<str>,
-    ) -> Result<CorgiPackument, OroClientError> {
-        let url = self.registry.join(package_name.as_ref())?;
-        let text = self.packument_impl(package_name, &url, true).await?;
-        serde_json::from_str(&text)
-            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+    ) -> Result<(CorgiPackument, bool), OroClientError> {
+        let url = self.packument_url(package_name.as_ref())?;
+        let (body, headers) = self.packument_impl(package_name, &url, true).await?;
+        let text = String::from_utf8_lossy(&body).into_owned();
+        let packument = serde_json::from_str(&text)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))?;
+        Ok((packument, served_from_cache(&headers)))
+    }
+
+    /// Fetches the full packument for `package_name` without parsing it,
+    /// along with the response headers (such as `ETag`). Useful when the
+    /// exact bytes the registry sent are needed, for example to hash them.
+    pub async fn packument_raw(
+        &self,
+        package_name: impl AsRef<str>,
+    ) -> Result<(Vec<u8>, HeaderMap), OroClientError> {
+        let url = self.packument_url(package_name.as_ref())?;
+        self.packument_impl(package_name, &url, false).await
+    }
+
+    /// Like [`Self::packument_raw`], but asks for the abbreviated "corgi"
+    /// packument instead of the full one.
+    pub async fn corgi_packument_raw(
+        &self,
+        package_name: impl AsRef<str>,
+    ) -> Result<(Vec<u8>, HeaderMap), OroClientError> {
+        let url = self.packument_url(package_name.as_ref())?;
+        self.packument_impl(package_name, &url, true).await
+    }
+
+    /// Registry URL for `package_name`'s packument. The `/` in scoped names
+    /// is encoded, as in `@scope%2fname`, since that's the form every
+    /// registry understands.
+    fn packument_url(&self, package_name: &str) -> Result<Url, OroClientError> {
+        Ok(self.registry.join(&package_name.replace('/', "%2f"))?)
     }
 
     async fn packument_impl(
@@ -37,8 +88,8 @@ impl OroClient {
         package_name: impl AsRef<str>,
         url: &Url,
         use_corgi: bool,
-    ) -> Result<String, OroClientError> {
-        Ok(self
+    ) -> Result<(Vec<u8>, HeaderMap), OroClientError> {
+        let mut req = self
             .client
             .get(url.clone())
             .header("X-Oro-Registry", self.registry.to_string())
@@ -49,25 +100,57 @@ impl OroClient {
                 } else {
                     "application/json"
                 },
-            )
-            .send()
-            .await?
-            .error_for_status()
-            .map_err(|err| {
-                if err.status() == Some(StatusCode::NOT_FOUND) {
-                    OroClientError::PackageNotFound(
-                        (*self.registry).clone(),
-                        package_name.as_ref().to_string(),
-                    )
-                } else {
-                    OroClientError::RequestError(err)
-                }
-            })?
-            .text()
-            .await?)
+            );
+        if let Some(max_age) = self.packument_max_age {
+            // `max-stale` lets the HTTP cache serve an entry past the
+            // freshness the registry gave it, and `max-age` caps that at the
+            // age we're willing to accept. Anything older gets revalidated.
+            let secs = max_age.as_secs();
+            req = req.header("Cache-Control", format!("max-age={secs}, max-stale={secs}"));
+        }
+        let res = req.send().await?.error_for_status().map_err(|err| {
+            if err.status() == Some(StatusCode::NOT_FOUND) {
+                OroClientError::PackageNotFound(
+                    (*self.registry).clone(),
+                    package_name.as_ref().to_string(),
+                )
+            } else {
+                OroClientError::RequestError(err)
+            }
+        })?;
+        let headers = res.headers().clone();
+        let body = read_limited(res, url, self.max_response_size).await?;
+        Ok((body, headers))
     }
 }
 
+/// The HTTP cache marks every response it handles with an `x-cache` header,
+/// which is `HIT` when the cached copy was used as-is.
+fn served_from_cache(headers: &HeaderMap) -> bool {
+    headers
+        .get("x-cache")
+        .map(|value| value.as_bytes().eq_ignore_ascii_case(b"hit"))
+        .unwrap_or(false)
+}
+
+/// Reads the whole body of `res`, bailing as soon as it's known to be larger
+/// than `limit` bytes.
+async fn read_limited(res: Response, url: &Url, limit: u64) -> Result<Vec<u8>, OroClientError> {
+    let too_large = || OroClientError::ResponseTooLarge(url.to_string(), limit);
+    if res.content_length().map(|len| len > limit).unwrap_or(false) {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    let mut stream = res.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        body.extend_from_slice(&chunk?);
+        if body.len() as u64 > limit {
+            return Err(too_large());
+        }
+    }
+    Ok(body)
+}
+
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod test {
     use indexmap::IndexMap;
@@ -215,4 +298,106 @@ mod test {
 
         Ok(())
     }
+
+    #[async_std::test]
+    async fn packument_too_large() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::builder()
+            .registry(mock_server.uri().parse().into_diagnostic()?)
+            .max_response_size(1024)
+            .build();
+
+        Mock::given(method("GET"))
+            .and(path("huge-pkg"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&json!({
+                "name": "huge-pkg",
+                "description": "x".repeat(2048),
+                "versions": {}
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert!(matches!(
+            client.packument("huge-pkg").await,
+            Err(OroClientError::ResponseTooLarge(_, 1024))
+        ));
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn packument_too_large_without_content_length() -> Result<()> {
+        let body = json!({
+            "name": "huge-pkg",
+            "description": "x".repeat(2048),
+            "versions": {}
+        });
+        let registry = crate::api::serve_chunked(body.to_string().into_bytes());
+        let client = OroClient::builder()
+            .registry(registry)
+            .max_response_size(1024)
+            .build();
+
+        assert!(matches!(
+            client.packument("huge-pkg").await,
+            Err(OroClientError::ResponseTooLarge(_, 1024))
+        ));
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn scoped_packument_path() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(
+            format!("{}/npm/", mock_server.uri())
+                .parse()
+                .into_diagnostic()?,
+        );
+
+        Mock::given(method("GET"))
+            .and(path("/npm/@scope%2fpkg"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&json!({
+                "name": "@scope/pkg",
+                "versions": {}
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        client.packument("@scope/pkg").await?;
+        client.corgi_packument("@scope/pkg").await?;
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn packument_raw() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+        // Deliberately not how serde_json would format it.
+        let body = "{ \"name\":\"some-pkg\",   \"versions\": {} }";
+
+        Mock::given(method("GET"))
+            .and(path("some-pkg"))
+            .and(header("accept", "application/json"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"deadbeef\"")
+                    .set_body_raw(body, "application/json"),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let (bytes, headers) = client.packument_raw("some-pkg").await?;
+        assert_eq!(bytes, body.as_bytes());
+        assert_eq!(
+            headers.get("etag").and_then(|etag| etag.to_str().ok()),
+            Some("\"deadbeef\"")
+        );
+
+        Ok(())
+    }
 }
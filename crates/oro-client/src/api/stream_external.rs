@@ -7,29 +7,81 @@ use url::Url;
 use crate::{OroClient, OroClientError};
 
 #[cfg(not(target_arch = "wasm32"))]
-type Result = std::result::Result<Box<dyn AsyncRead + Unpin + Send + Sync>, OroClientError>;
+type Stream = Box<dyn AsyncRead + Unpin + Send + Sync>;
 #[cfg(target_arch = "wasm32")]
-type Result = std::result::Result<Box<dyn AsyncRead + Unpin>, OroClientError>;
+type Stream = Box<dyn AsyncRead + Unpin>;
+
+type Result<T = Stream> = std::result::Result<T, OroClientError>;
 
 impl OroClient {
     pub async fn stream_external(&self, url: &Url) -> Result {
-        Ok(Box::new(
-            // NOTE: We don't want to cache these requests. If you want to
-            // cache them, cache them manually.
-            self.client_uncached
-                .get(url.to_string())
-                .header("X-Oro-Registry", self.registry.to_string())
-                .send()
-                .await?
-                .error_for_status()?
-                .bytes_stream()
-                .map(|r| match r {
-                    Ok(bytes) => Ok(bytes),
-                    Err(err) => Err(std::io::Error::new(std::io::ErrorKind::Other, err)),
-                })
-                .into_async_read(),
-        ))
+        Ok(self.stream_external_sized(url).await?.0)
+    }
+
+    /// Like [`OroClient::stream_external`], but also returns the size of the
+    /// response body, if the server sent a `Content-Length` for it.
+    pub async fn stream_external_sized(&self, url: &Url) -> Result<(Stream, Option<u64>)> {
+        // NOTE: We don't want to cache these requests. If you want to
+        // cache them, cache them manually.
+        let res = self
+            .client_uncached
+            .get(url.to_string())
+            .header("X-Oro-Registry", self.registry.to_string())
+            .send()
+            .await?
+            .error_for_status()?;
+        let length = res.content_length();
+        let limit = self.max_tarball_size;
+        if length.map(|len| len > limit).unwrap_or(false) {
+            return Err(OroClientError::ResponseTooLarge(url.to_string(), limit));
+        }
+        // Servers don't have to send a Content-Length, and can send more
+        // than they said they would, so keep count as the body comes in.
+        let mut total = 0u64;
+        let url = url.to_string();
+        let stream = res
+            .bytes_stream()
+            .map(move |r| match r {
+                Ok(bytes) => {
+                    total += bytes.len() as u64;
+                    if total > limit {
+                        Err(std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            OroClientError::ResponseTooLarge(url.clone(), limit),
+                        ))
+                    } else {
+                        Ok(bytes)
+                    }
+                }
+                Err(err) => Err(body_error(err)),
+            })
+            .into_async_read();
+        Ok((Box::new(stream), length))
+    }
+}
+
+/// Turns an error that happened while reading a response body into an
+/// [`std::io::Error`]. The error kind is carried over from the underlying IO
+/// error, if any, so callers can tell dropped connections apart from other
+/// failures.
+fn body_error(err: reqwest::Error) -> std::io::Error {
+    let mut kind = if err.is_timeout() {
+        std::io::ErrorKind::TimedOut
+    } else if err.is_body() {
+        // The connection went away before the whole body came through.
+        std::io::ErrorKind::UnexpectedEof
+    } else {
+        std::io::ErrorKind::Other
+    };
+    let mut source = std::error::Error::source(&err);
+    while let Some(inner) = source {
+        if let Some(io_err) = inner.downcast_ref::<std::io::Error>() {
+            kind = io_err.kind();
+            break;
+        }
+        source = inner.source();
     }
+    std::io::Error::new(kind, err)
 }
 
 #[cfg(test)]
@@ -97,4 +149,54 @@ mod test {
 
         Ok(())
     }
+
+    #[async_std::test]
+    async fn stream_external_too_large() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::builder().max_tarball_size(16).build();
+        let server_url: Url = mock_server.uri().parse().into_diagnostic()?;
+
+        Mock::given(method("GET"))
+            .and(path("big.tgz"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw(vec![0u8; 64], "application/octet-stream"),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert!(matches!(
+            client
+                .stream_external(&server_url.join("big.tgz").into_diagnostic()?)
+                .await,
+            Err(OroClientError::ResponseTooLarge(_, 16))
+        ));
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn stream_external_too_large_without_content_length() -> Result<()> {
+        let client = OroClient::builder().max_tarball_size(16).build();
+        let url = crate::api::serve_chunked(vec![0u8; 64])
+            .join("big.tgz")
+            .into_diagnostic()?;
+
+        // Without a Content-Length, the limit only kicks in once the body
+        // is being read.
+        let (mut reader, length) = client.stream_external_sized(&url).await?;
+        assert_eq!(length, None);
+        let err = reader
+            .read_to_end(&mut Vec::new())
+            .await
+            .expect_err("reading past the limit should fail");
+        assert!(matches!(
+            err.into_inner()
+                .and_then(|inner| inner.downcast::<OroClientError>().ok())
+                .as_deref(),
+            Some(OroClientError::ResponseTooLarge(_, 16))
+        ));
+
+        Ok(())
+    }
 }
@@ -1,13 +1,13 @@
 #[cfg(not(target_arch = "wasm32"))]
 use std::path::{Path, PathBuf};
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 #[cfg(not(target_arch = "wasm32"))]
 use http_cache_reqwest::{CACacheManager, Cache, CacheMode, HttpCache};
 #[cfg(target_arch = "wasm32")]
 use reqwest::Client;
 #[cfg(not(target_arch = "wasm32"))]
-use reqwest::ClientBuilder;
+use reqwest::{Certificate, ClientBuilder};
 #[cfg(not(target_arch = "wasm32"))]
 use reqwest::{NoProxy, Proxy};
 use reqwest_middleware::ClientWithMiddleware;
@@ -21,11 +21,21 @@ use crate::{
     credentials::Credentials,
 };
 
+/// Default maximum size of a registry response (such as a packument), in
+/// bytes.
+pub const DEFAULT_MAX_RESPONSE_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Default maximum size of a tarball download, in bytes.
+pub const DEFAULT_MAX_TARBALL_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
 #[derive(Clone, Debug)]
 pub struct OroClientBuilder {
     registry: Url,
     retries: u32,
     credentials: HashMap<String, Credentials>,
+    packument_max_age: Option<Duration>,
+    max_response_size: u64,
+    max_tarball_size: u64,
     #[cfg(not(target_arch = "wasm32"))]
     cache: Option<PathBuf>,
     #[cfg(not(target_arch = "wasm32"))]
@@ -34,6 +44,10 @@ pub struct OroClientBuilder {
     proxy_url: Option<Proxy>,
     #[cfg(not(target_arch = "wasm32"))]
     no_proxy_domain: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    root_certificates: Vec<Certificate>,
+    #[cfg(not(target_arch = "wasm32"))]
+    accept_invalid_certs: bool,
 }
 
 impl Default for OroClientBuilder {
@@ -41,6 +55,9 @@ impl Default for OroClientBuilder {
         Self {
             registry: Url::parse("https://registry.npmjs.org").unwrap(),
             credentials: HashMap::new(),
+            packument_max_age: None,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            max_tarball_size: DEFAULT_MAX_TARBALL_SIZE,
             #[cfg(not(target_arch = "wasm32"))]
             cache: None,
             #[cfg(not(target_arch = "wasm32"))]
@@ -49,6 +66,10 @@ impl Default for OroClientBuilder {
             proxy_url: None,
             #[cfg(not(target_arch = "wasm32"))]
             no_proxy_domain: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            root_certificates: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            accept_invalid_certs: false,
             #[cfg(not(test))]
             retries: 2,
             #[cfg(test)]
@@ -91,11 +112,41 @@ impl OroClientBuilder {
         self
     }
 
+    /// Sets the credentials to use for requests to `registry`.
+    pub fn credentials(mut self, registry: Url, credentials: Credentials) -> Self {
+        self.credentials
+            .insert(auth_middleware::nerf_dart(&registry), credentials);
+        self
+    }
+
     pub fn retries(mut self, retries: u32) -> Self {
         self.retries = retries;
         self
     }
 
+    /// Serve cached packuments younger than `max_age` without making a
+    /// request at all. Older packuments are revalidated as usual. Only has
+    /// an effect when a cache is configured.
+    pub fn packument_max_age(mut self, max_age: Duration) -> Self {
+        self.packument_max_age = Some(max_age);
+        self
+    }
+
+    /// Maximum size, in bytes, of a registry response such as a packument.
+    /// Larger responses fail with [`OroClientError::ResponseTooLarge`].
+    /// Defaults to [`DEFAULT_MAX_RESPONSE_SIZE`].
+    pub fn max_response_size(mut self, max_size: u64) -> Self {
+        self.max_response_size = max_size;
+        self
+    }
+
+    /// Maximum size, in bytes, of a tarball download. Defaults to
+    /// [`DEFAULT_MAX_TARBALL_SIZE`].
+    pub fn max_tarball_size(mut self, max_size: u64) -> Self {
+        self.max_tarball_size = max_size;
+        self
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub fn cache(mut self, cache: impl AsRef<Path>) -> Self {
         self.cache = Some(PathBuf::from(cache.as_ref()));
@@ -135,6 +186,50 @@ impl OroClientBuilder {
         self
     }
 
+    /// Trusts an additional root certificate, in DER format, on top of the
+    /// system's trust store.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_root_certificate(mut self, der: impl AsRef<[u8]>) -> Result<Self, OroClientError> {
+        let cert = Certificate::from_der(der.as_ref())
+            .map_err(|e| OroClientError::InvalidCertificate("the provided DER data".into(), e))?;
+        self.root_certificates.push(cert);
+        Ok(self)
+    }
+
+    /// Trusts every certificate in a PEM-encoded CA bundle at `path`, on top
+    /// of the system's trust store.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn ca_bundle(mut self, path: impl AsRef<Path>) -> Result<Self, OroClientError> {
+        let path = path.as_ref();
+        let pem = std::fs::read_to_string(path)
+            .map_err(|e| OroClientError::CaBundleReadError(path.into(), e))?;
+        const END: &str = "-----END CERTIFICATE-----";
+        let mut certs = pem
+            .split_inclusive(END)
+            .filter(|chunk| chunk.contains(END))
+            .map(|chunk| Certificate::from_pem(chunk.trim_start().as_bytes()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| OroClientError::InvalidCertificate(path.display().to_string(), e))?;
+        if certs.is_empty() {
+            // Let reqwest explain what's wrong with the file.
+            certs.push(
+                Certificate::from_pem(pem.as_bytes()).map_err(|e| {
+                    OroClientError::InvalidCertificate(path.display().to_string(), e)
+                })?,
+            );
+        }
+        self.root_certificates.extend(certs);
+        Ok(self)
+    }
+
+    /// Skips TLS certificate validation entirely. This makes connections
+    /// trivially interceptable, so it should only ever be used for testing.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
     pub fn build(self) -> OroClient {
         #[cfg(target_arch = "wasm32")]
         let client_raw = Client::new();
@@ -154,6 +249,14 @@ impl OroClientBuilder {
                 client_core = client_core.no_proxy();
             }
 
+            for cert in self.root_certificates {
+                client_core = client_core.add_root_certificate(cert);
+            }
+
+            if self.accept_invalid_certs {
+                client_core = client_core.danger_accept_invalid_certs(true);
+            }
+
             client_core.build().expect("Fail to build HTTP client.")
         };
 
@@ -188,6 +291,9 @@ impl OroClientBuilder {
             registry: Arc::new(self.registry),
             client: client_builder.build(),
             client_uncached: client_uncached_builder.build(),
+            packument_max_age: self.packument_max_age,
+            max_response_size: self.max_response_size,
+            max_tarball_size: self.max_tarball_size,
         }
     }
 
@@ -208,6 +314,9 @@ pub struct OroClient {
     pub(crate) registry: Arc<Url>,
     pub(crate) client: ClientWithMiddleware,
     pub(crate) client_uncached: ClientWithMiddleware,
+    pub(crate) packument_max_age: Option<Duration>,
+    pub(crate) max_response_size: u64,
+    pub(crate) max_tarball_size: u64,
 }
 
 impl OroClient {
@@ -224,6 +333,9 @@ impl OroClient {
             registry: Arc::new(registry),
             client: self.client.clone(),
             client_uncached: self.client_uncached.clone(),
+            packument_max_age: self.packument_max_age,
+            max_response_size: self.max_response_size,
+            max_tarball_size: self.max_tarball_size,
         }
     }
 }
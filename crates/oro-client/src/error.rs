@@ -38,6 +38,14 @@ pub enum OroClientError {
     #[diagnostic(code(oro_client::request_error), url(docsrs))]
     RequestError(#[from] reqwest::Error),
 
+    /// The response body was larger than the client is configured to accept.
+    ///
+    /// This usually means the registry is misbehaving. If the response is
+    /// expected to be this large, raise the limit.
+    #[error("Response from {0} exceeded the maximum allowed size of {1} bytes.")]
+    #[diagnostic(code(oro_client::response_too_large), url(docsrs))]
+    ResponseTooLarge(String, u64),
+
     /// Recived unexpected response.
     #[error("Received unexpected response. \n {0}")]
     #[diagnostic(code(oro_client::response_error), url(docsrs))]
@@ -79,6 +87,24 @@ pub enum OroClientError {
     #[diagnostic(code(oro_client::auth_string_missing_username), url(docsrs))]
     AuthStringMissingUsername(String),
 
+    /// Failed to read a CA bundle file.
+    #[error("Failed to read CA bundle at {0}.")]
+    #[diagnostic(
+        code(oro_client::ca_bundle_read_error),
+        url(docsrs),
+        help("Make sure the file exists and is readable.")
+    )]
+    CaBundleReadError(std::path::PathBuf, #[source] std::io::Error),
+
+    /// A certificate couldn't be parsed.
+    #[error("Invalid certificate in {0}.")]
+    #[diagnostic(
+        code(oro_client::invalid_certificate),
+        url(docsrs),
+        help("CA bundles should contain one or more PEM-encoded certificates.")
+    )]
+    InvalidCertificate(String, #[source] reqwest::Error),
+
     /// Failed to decode base64.
     #[error(transparent)]
     #[diagnostic(code(oro_client::base64_decode_error), url(docsrs))]
@@ -7,8 +7,13 @@ mod credentials;
 mod error;
 mod notify;
 
+pub use api::audit;
+pub use api::downloads;
 pub use api::login;
 pub use api::packument;
 pub use auth_middleware::nerf_dart;
-pub use client::{OroClient, OroClientBuilder};
+pub use client::{
+    OroClient, OroClientBuilder, DEFAULT_MAX_RESPONSE_SIZE, DEFAULT_MAX_TARBALL_SIZE,
+};
+pub use credentials::Credentials;
 pub use error::OroClientError;
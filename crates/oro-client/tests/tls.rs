@@ -0,0 +1,93 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+use miette::{IntoDiagnostic, Result};
+use native_tls::{Identity, TlsAcceptor};
+use oro_client::OroClientBuilder;
+
+const SERVER_CERT: &[u8] = include_bytes!("fixtures/tls/server.pem");
+const SERVER_KEY: &[u8] = include_bytes!("fixtures/tls/server-key.pem");
+const CA_DER: &[u8] = include_bytes!("fixtures/tls/ca.der");
+
+#[async_std::test]
+async fn untrusted_ca_is_rejected() -> Result<()> {
+    let client = client(serve_tls()?).build();
+    assert!(client.ping().await.is_err());
+    Ok(())
+}
+
+#[async_std::test]
+async fn ca_bundle() -> Result<()> {
+    let client = client(serve_tls()?).ca_bundle(fixture("ca.pem"))?.build();
+    assert_eq!(client.ping().await?, "ok");
+    Ok(())
+}
+
+#[async_std::test]
+async fn add_root_certificate() -> Result<()> {
+    let client = client(serve_tls()?).add_root_certificate(CA_DER)?.build();
+    assert_eq!(client.ping().await?, "ok");
+    Ok(())
+}
+
+#[async_std::test]
+async fn danger_accept_invalid_certs() -> Result<()> {
+    let client = client(serve_tls()?)
+        .danger_accept_invalid_certs(true)
+        .build();
+    assert_eq!(client.ping().await?, "ok");
+    Ok(())
+}
+
+#[test]
+fn invalid_ca_bundle() {
+    assert!(OroClientBuilder::new()
+        .ca_bundle(fixture("ca.der"))
+        .is_err());
+    assert!(OroClientBuilder::new()
+        .ca_bundle(fixture("missing.pem"))
+        .is_err());
+}
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/tls")
+        .join(name)
+}
+
+fn client(registry: String) -> OroClientBuilder {
+    OroClientBuilder::new()
+        .registry(registry.parse().unwrap())
+        .retries(0)
+}
+
+/// Starts a bare-bones HTTPS server on localhost, using a certificate signed
+/// by the test CA, that answers every request with `ok`.
+fn serve_tls() -> Result<String> {
+    let identity = Identity::from_pkcs8(SERVER_CERT, SERVER_KEY).into_diagnostic()?;
+    let acceptor = TlsAcceptor::new(identity).into_diagnostic()?;
+    let listener = TcpListener::bind("127.0.0.1:0").into_diagnostic()?;
+    let port = listener.local_addr().into_diagnostic()?.port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let Ok(mut stream) = acceptor.accept(stream) else {
+                continue;
+            };
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok");
+            let _ = stream.shutdown();
+        }
+    });
+    Ok(format!("https://localhost:{port}/"))
+}
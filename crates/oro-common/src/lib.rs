@@ -5,7 +5,9 @@ pub use build_manifest::*;
 pub use manifest::Bin;
 pub use manifest::*;
 pub use packument::*;
+pub use warnings::*;
 
 mod build_manifest;
 mod manifest;
 mod packument;
+mod warnings;
@@ -3,8 +3,10 @@ use std::{collections::HashMap, path::PathBuf};
 use derive_builder::Builder;
 use indexmap::IndexMap;
 use node_semver::{Range, Version};
+use oro_package_spec::GitInfo;
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
+use url::Url;
 
 use crate::{CorgiVersionMetadata, VersionMetadata};
 
@@ -23,8 +25,22 @@ pub struct CorgiManifest {
     pub optional_dependencies: IndexMap<String, String>,
     #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub peer_dependencies: IndexMap<String, String>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub peer_dependencies_meta: IndexMap<String, PeerDependencyMeta>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub resolutions: IndexMap<String, String>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub catalog: IndexMap<String, String>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub catalogs: IndexMap<String, IndexMap<String, String>>,
     #[serde(default, alias = "bundleDependencies", alias = "bundledDependencies")]
     pub bundled_dependencies: Option<BundledDependencies>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub os: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub libc: Vec<String>,
 }
 
 #[derive(Builder, Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -114,11 +130,20 @@ pub struct Manifest {
     ///
     /// [`Repository::Str`][] can contain many different formats (or plain garbage),
     /// we recommend trying to `.parse()` it as oro-package-spec's GitInfo type,
-    /// as it understands most of the relevant formats.
+    /// as it understands most of the relevant formats. If all you need is a
+    /// link, use [`Repository::normalized_url`][].
     #[builder(setter(strip_option), default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub repository: Option<Repository>,
 
+    /// Where to go to support the package's maintainers. Can be a plain URL,
+    /// an object with `type` and `url` fields, or an array of either.
+    ///
+    /// Use [`Funding::urls`][] to get a flat list of URLs.
+    #[builder(setter(strip_option), default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub funding: Option<Funding>,
+
     /// Information about build scripts the package uses.
     ///
     /// Use [`crate::BuildManifest::from_manifest`][] to get a normalized version
@@ -150,10 +175,20 @@ pub struct Manifest {
     #[builder(default)]
     pub cpu: Vec<String>,
 
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub libc: Vec<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(setter(strip_option), default)]
     pub private: Option<bool>,
 
+    /// Package manager this project expects to be installed with, like
+    /// `oro@0.3.34`, optionally followed by a `+<hash>` suffix.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(setter(strip_option), default)]
+    pub package_manager: Option<String>,
+
     #[serde(
         default,
         rename = "publishConfig",
@@ -179,6 +214,29 @@ pub struct Manifest {
     #[builder(default)]
     pub peer_dependencies: IndexMap<String, String>,
 
+    /// Extra information about entries in `peerDependencies`. Currently,
+    /// this is only used to mark peers as optional.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    #[builder(default)]
+    pub peer_dependencies_meta: IndexMap<String, PeerDependencyMeta>,
+
+    /// Yarn-style resolutions, forcing dependencies that match a path
+    /// pattern (like `**/lodash` or `foo/bar`) to a specific spec.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    #[builder(default)]
+    pub resolutions: IndexMap<String, String>,
+
+    /// pnpm-style default catalog, mapping package names to the version that
+    /// `catalog:` dependencies on them should use.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    #[builder(default)]
+    pub catalog: IndexMap<String, String>,
+
+    /// Named catalogs, used by `catalog:<name>` dependencies.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    #[builder(default)]
+    pub catalogs: IndexMap<String, IndexMap<String, String>>,
+
     #[serde(
         default,
         alias = "bundleDependencies",
@@ -206,7 +264,14 @@ impl From<CorgiManifest> for Manifest {
             dev_dependencies: value.dev_dependencies,
             optional_dependencies: value.optional_dependencies,
             peer_dependencies: value.peer_dependencies,
+            peer_dependencies_meta: value.peer_dependencies_meta,
+            resolutions: value.resolutions,
+            catalog: value.catalog,
+            catalogs: value.catalogs,
             bundled_dependencies: value.bundled_dependencies,
+            os: value.os,
+            cpu: value.cpu,
+            libc: value.libc,
             ..Default::default()
         }
     }
@@ -221,7 +286,14 @@ impl From<Manifest> for CorgiManifest {
             dev_dependencies: value.dev_dependencies,
             optional_dependencies: value.optional_dependencies,
             peer_dependencies: value.peer_dependencies,
+            peer_dependencies_meta: value.peer_dependencies_meta,
+            resolutions: value.resolutions,
+            catalog: value.catalog,
+            catalogs: value.catalogs,
             bundled_dependencies: value.bundled_dependencies,
+            os: value.os,
+            cpu: value.cpu,
+            libc: value.libc,
         }
     }
 }
@@ -334,7 +406,7 @@ pub enum Man {
 pub enum Exports {
     Str(String),
     Vec(Vec<String>),
-    Obj(HashMap<String, Exports>),
+    Obj(IndexMap<String, Exports>),
     Other(Value),
 }
 
@@ -343,7 +415,7 @@ pub enum Exports {
 pub enum Imports {
     Str(String),
     Vec(Vec<String>),
-    Obj(HashMap<String, Imports>),
+    Obj(IndexMap<String, Imports>),
     Other(Value),
 }
 
@@ -359,6 +431,194 @@ pub enum Repository {
     },
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PeerDependencyMeta {
+    /// Optional peers are not installed automatically, and are not reported
+    /// when they're missing from the tree.
+    #[serde(default)]
+    pub optional: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Funding {
+    Str(String),
+    Obj {
+        #[serde(rename = "type")]
+        funding_type: Option<String>,
+        url: String,
+    },
+    Vec(Vec<Funding>),
+    Other(Value),
+}
+
+impl Funding {
+    /// All funding URLs declared by this field, in declaration order.
+    pub fn urls(&self) -> Vec<&str> {
+        match self {
+            Funding::Str(url) => vec![url.as_str()],
+            Funding::Obj { url, .. } => vec![url.as_str()],
+            Funding::Vec(fundings) => fundings.iter().flat_map(|f| f.urls()).collect(),
+            Funding::Other(_) => Vec::new(),
+        }
+    }
+}
+
+impl Manifest {
+    /// Resolves `subpath` (`"."` or something like `"./feature"`) through
+    /// this package's `exports` the way Node does, taking the first entry of
+    /// each conditional object that's either `"default"` or listed in
+    /// `conditions`, such as `&["node", "import"]`. Returns the target path
+    /// within the package, like `"./dist/feature.js"`.
+    ///
+    /// Returns `None` if the subpath isn't exported, or if `exports` is
+    /// malformed: objects mixing subpaths with condition names, targets that
+    /// don't start with `./`, or targets that reach outside the package or
+    /// into `node_modules`.
+    pub fn resolve_export(&self, subpath: &str, conditions: &[&str]) -> Option<String> {
+        self.exports.as_ref()?.resolve(subpath, conditions)
+    }
+}
+
+impl Exports {
+    /// Like [`Manifest::resolve_export`], for an `exports` value on its own.
+    pub fn resolve(&self, subpath: &str, conditions: &[&str]) -> Option<String> {
+        if subpath != "." && !subpath.starts_with("./") {
+            return None;
+        }
+        let subpaths = match self {
+            Exports::Obj(map) if map.keys().any(|key| key.starts_with('.')) => {
+                if !map.keys().all(|key| key.starts_with('.')) {
+                    return None;
+                }
+                map
+            }
+            // Anything else is shorthand for `{ ".": exports }`.
+            _ if subpath == "." => return self.resolve_target(None, conditions),
+            _ => return None,
+        };
+        if !subpath.contains('*') {
+            if let Some(target) = subpaths.get(subpath) {
+                return target.resolve_target(None, conditions);
+            }
+        }
+        // Otherwise, look for a `*` pattern. The one with the longest prefix
+        // before the `*` wins, then the longest overall.
+        let (_, target, matched) = subpaths
+            .iter()
+            .filter_map(|(key, target)| {
+                let (base, trailer) = key.split_once('*')?;
+                if trailer.contains('*') {
+                    return None;
+                }
+                let matched = subpath.strip_prefix(base)?.strip_suffix(trailer)?;
+                if matched.is_empty() {
+                    return None;
+                }
+                Some(((base.len(), key.len()), target, matched))
+            })
+            .max_by_key(|(rank, _, _)| *rank)?;
+        target.resolve_target(Some(matched), conditions)
+    }
+
+    fn resolve_target(&self, matched: Option<&str>, conditions: &[&str]) -> Option<String> {
+        match self {
+            Exports::Str(target) => resolve_export_target(target, matched),
+            Exports::Vec(targets) => targets
+                .iter()
+                .find_map(|target| resolve_export_target(target, matched)),
+            Exports::Obj(map) => {
+                // Condition objects can't have subpaths in them.
+                if map.keys().any(|key| key.starts_with('.')) {
+                    return None;
+                }
+                for (condition, target) in map {
+                    if condition != "default" && !conditions.contains(&condition.as_str()) {
+                        continue;
+                    }
+                    // `null` explicitly hides the subpath under this condition.
+                    if let Exports::Other(Value::Null) = target {
+                        return None;
+                    }
+                    if let Some(resolved) = target.resolve_target(matched, conditions) {
+                        return Some(resolved);
+                    }
+                }
+                None
+            }
+            // Arrays that aren't just strings, like fallbacks with conditions.
+            Exports::Other(Value::Array(targets)) => targets
+                .iter()
+                .filter_map(|target| serde_json::from_value::<Exports>(target.clone()).ok())
+                .find_map(|target| target.resolve_target(matched, conditions)),
+            Exports::Other(_) => None,
+        }
+    }
+}
+
+/// Checks that `target` stays within the package, and substitutes `matched`
+/// for its `*`s if it came from a pattern.
+fn resolve_export_target(target: &str, matched: Option<&str>) -> Option<String> {
+    let escapes = |path: &str| {
+        path.split(['/', '\\']).any(|segment| {
+            matches!(segment, "." | "..") || segment.eq_ignore_ascii_case("node_modules")
+        })
+    };
+    if escapes(target.strip_prefix("./")?) {
+        return None;
+    }
+    match matched {
+        Some(matched) if escapes(matched) => None,
+        Some(matched) => Some(target.replace('*', matched)),
+        None => Some(target.into()),
+    }
+}
+
+impl Repository {
+    /// The raw repository URL, exactly as specified in the manifest.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Repository::Str(url) => Some(url),
+            Repository::Obj { url, .. } => url.as_deref(),
+        }
+    }
+
+    /// Expands shorthands (`github:user/repo`, `user/repo`) and `git+`/`git://`
+    /// URLs into a browsable `https` URL. Returns `None` if the URL couldn't
+    /// be understood.
+    pub fn normalized_url(&self) -> Option<Url> {
+        let raw = self.url()?.trim();
+        if let Ok(info) = raw.parse::<GitInfo>() {
+            return match info {
+                GitInfo::Hosted { .. } => info.browse(),
+                GitInfo::Url { url, .. } => https_url(&url),
+                GitInfo::Ssh { ssh, .. } => scp_to_https(&ssh),
+            };
+        }
+        if let Ok(url) = Url::parse(raw) {
+            https_url(&url)
+        } else {
+            scp_to_https(raw)
+        }
+    }
+}
+
+fn https_url(url: &Url) -> Option<Url> {
+    let host = url.host_str()?;
+    let path = url.path().trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    format!("https://{host}{path}").parse().ok()
+}
+
+// Handles scp-style `git@host:owner/repo.git` strings.
+fn scp_to_https(ssh: &str) -> Option<Url> {
+    let (user_host, path) = ssh.split_once(':').or_else(|| ssh.split_once('/'))?;
+    let host = user_host.rsplit('@').next()?;
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    format!("https://{host}/{path}").parse().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -522,4 +782,220 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn repository_normalized_url() -> Result<()> {
+        let repo = Repository::Str("github:user/repo".into());
+        assert_eq!(
+            repo.normalized_url(),
+            Some("https://github.com/user/repo".parse().into_diagnostic()?)
+        );
+
+        let repo = Repository::Str("user/repo".into());
+        assert_eq!(
+            repo.normalized_url(),
+            Some("https://github.com/user/repo".parse().into_diagnostic()?)
+        );
+
+        let repo = Repository::Obj {
+            repo_type: Some("git".into()),
+            url: Some("git+https://github.com/user/repo.git".into()),
+            directory: None,
+        };
+        assert_eq!(
+            repo.normalized_url(),
+            Some("https://github.com/user/repo".parse().into_diagnostic()?)
+        );
+
+        let repo = Repository::Str("git://example.com/some/repo.git".into());
+        assert_eq!(
+            repo.normalized_url(),
+            Some("https://example.com/some/repo".parse().into_diagnostic()?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn funding_str() -> Result<()> {
+        let string = r#"
+{
+    "funding": "https://example.com/donate"
+}
+        "#;
+        let parsed = serde_json::from_str::<Manifest>(string).into_diagnostic()?;
+        let funding = Funding::Str("https://example.com/donate".into());
+        assert_eq!(
+            parsed,
+            ManifestBuilder::default()
+                .funding(funding.clone())
+                .build()
+                .unwrap()
+        );
+        assert_eq!(funding.urls(), vec!["https://example.com/donate"]);
+        Ok(())
+    }
+
+    #[test]
+    fn funding_obj() -> Result<()> {
+        let string = r#"
+{
+    "funding": {
+        "type": "github",
+        "url": "https://github.com/sponsors/someone"
+    }
+}
+        "#;
+        let parsed = serde_json::from_str::<Manifest>(string).into_diagnostic()?;
+        let funding = Funding::Obj {
+            funding_type: Some("github".into()),
+            url: "https://github.com/sponsors/someone".into(),
+        };
+        assert_eq!(
+            parsed,
+            ManifestBuilder::default()
+                .funding(funding.clone())
+                .build()
+                .unwrap()
+        );
+        assert_eq!(funding.urls(), vec!["https://github.com/sponsors/someone"]);
+        Ok(())
+    }
+
+    #[test]
+    fn funding_array() -> Result<()> {
+        let string = r#"
+{
+    "funding": [
+        "https://example.com/donate",
+        {
+            "url": "https://opencollective.com/something"
+        }
+    ]
+}
+        "#;
+        let parsed = serde_json::from_str::<Manifest>(string).into_diagnostic()?;
+        let funding = Funding::Vec(vec![
+            Funding::Str("https://example.com/donate".into()),
+            Funding::Obj {
+                funding_type: None,
+                url: "https://opencollective.com/something".into(),
+            },
+        ]);
+        assert_eq!(
+            parsed,
+            ManifestBuilder::default()
+                .funding(funding.clone())
+                .build()
+                .unwrap()
+        );
+        assert_eq!(
+            funding.urls(),
+            vec![
+                "https://example.com/donate",
+                "https://opencollective.com/something"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn peer_dependencies_meta() -> Result<()> {
+        let string = r#"
+{
+    "peerDependencies": {
+        "react": "^18.0.0"
+    },
+    "peerDependenciesMeta": {
+        "react": {
+            "optional": true
+        }
+    }
+}
+        "#;
+        let parsed = serde_json::from_str::<CorgiManifest>(string).into_diagnostic()?;
+        assert!(parsed.peer_dependencies_meta["react"].optional);
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_export_patterns() -> Result<()> {
+        let manifest = serde_json::from_str::<Manifest>(
+            r#"{
+                "exports": {
+                    ".": "./index.js",
+                    "./features/*": "./src/features/*.js",
+                    "./features/private/*": null,
+                    "./features/*.json": "./data/*.json",
+                    "./package.json": "./package.json"
+                }
+            }"#,
+        )
+        .into_diagnostic()?;
+        let resolve = |subpath| manifest.resolve_export(subpath, &["node", "require"]);
+        assert_eq!(resolve("."), Some("./index.js".into()));
+        assert_eq!(resolve("./package.json"), Some("./package.json".into()));
+        assert_eq!(
+            resolve("./features/x/y"),
+            Some("./src/features/x/y.js".into())
+        );
+        // More specific patterns win over more general ones.
+        assert_eq!(
+            resolve("./features/config.json"),
+            Some("./data/config.json".into())
+        );
+        assert_eq!(resolve("./features/private/secret"), None);
+        assert_eq!(resolve("./features/../../etc/passwd"), None);
+        assert_eq!(resolve("./missing"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_export_conditions() -> Result<()> {
+        let manifest = serde_json::from_str::<Manifest>(
+            r#"{
+                "exports": {
+                    ".": {
+                        "types": "./index.d.ts",
+                        "import": "./index.mjs",
+                        "require": "./index.cjs",
+                        "default": "./index.js"
+                    },
+                    "./sub": [{ "worker": "./sub-worker.js" }, "./sub.js"]
+                }
+            }"#,
+        )
+        .into_diagnostic()?;
+        // Order in the object decides, not order in `conditions`.
+        assert_eq!(
+            manifest.resolve_export(".", &["require", "import"]),
+            Some("./index.mjs".into())
+        );
+        assert_eq!(
+            manifest.resolve_export(".", &["require"]),
+            Some("./index.cjs".into())
+        );
+        assert_eq!(manifest.resolve_export(".", &[]), Some("./index.js".into()));
+        assert_eq!(
+            manifest.resolve_export("./sub", &["worker"]),
+            Some("./sub-worker.js".into())
+        );
+        assert_eq!(
+            manifest.resolve_export("./sub", &[]),
+            Some("./sub.js".into())
+        );
+
+        // Shorthand for `{ ".": ... }`, and invalid configurations.
+        let exports = |json| serde_json::from_str::<Exports>(json).unwrap();
+        assert_eq!(
+            exports(r#"{ "import": "./a.mjs", "default": "./a.js" }"#).resolve(".", &["import"]),
+            Some("./a.mjs".into())
+        );
+        assert_eq!(exports(r#""./a.js""#).resolve("./a", &[]), None);
+        assert_eq!(
+            exports(r#"{ ".": "./a.js", "import": "./a.mjs" }"#).resolve(".", &["import"]),
+            None
+        );
+        assert_eq!(exports(r#""../outside.js""#).resolve(".", &[]), None);
+        Ok(())
+    }
 }
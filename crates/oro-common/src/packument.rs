@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use derive_builder::Builder;
 use node_semver::Version;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -26,14 +27,39 @@ pub struct CorgiPackument {
 pub struct Packument {
     #[serde(default)]
     pub versions: HashMap<Version, VersionMetadata>,
-    #[serde(default)]
-    pub time: HashMap<String, String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_time",
+        serialize_with = "serialize_time"
+    )]
+    pub time: HashMap<String, DateTime<Utc>>,
     #[serde(default, rename = "dist-tags")]
     pub tags: HashMap<String, Version>,
     #[serde(flatten)]
     pub rest: HashMap<String, Value>,
 }
 
+impl Packument {
+    /// When the package was first published, if the registry reports it.
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        self.time.get("created").copied()
+    }
+
+    /// When the package was last changed, if the registry reports it.
+    pub fn modified(&self) -> Option<DateTime<Utc>> {
+        self.time.get("modified").copied()
+    }
+
+    /// The README of the package's latest release, if the registry has one.
+    /// Only full packuments include it.
+    pub fn readme(&self) -> Option<&str> {
+        self.rest
+            .get("readme")
+            .and_then(Value::as_str)
+            .filter(|readme| !readme.is_empty())
+    }
+}
+
 impl From<CorgiPackument> for Packument {
     fn from(value: CorgiPackument) -> Self {
         Packument {
@@ -260,3 +286,84 @@ impl From<Dist> for CorgiDist {
         }
     }
 }
+
+/// Parses the packument's `time` map. Entries that aren't timestamps (like
+/// the `unpublished` object some registries include) are skipped.
+fn deserialize_time<'de, D>(
+    deserializer: D,
+) -> std::result::Result<HashMap<String, DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: HashMap<String, Value> = Deserialize::deserialize(deserializer)?;
+    Ok(raw
+        .into_iter()
+        .filter_map(|(key, val)| {
+            let time = DateTime::parse_from_rfc3339(val.as_str()?).ok()?;
+            Some((key, time.with_timezone(&Utc)))
+        })
+        .collect())
+}
+
+fn serialize_time<S>(
+    time: &HashMap<String, DateTime<Utc>>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_map(time.iter().map(|(key, val)| (key, val.to_rfc3339())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn time_from_json() -> Result<()> {
+        let string = r#"
+{
+    "name": "hello",
+    "dist-tags": { "latest": "1.0.0" },
+    "time": {
+        "created": "2020-01-01T00:00:00.000Z",
+        "modified": "2021-06-15T12:30:00.000+02:00",
+        "1.0.0": "2020-01-02T03:04:05.678Z",
+        "unpublished": { "time": "2021-01-01T00:00:00.000Z", "versions": [] }
+    }
+}
+        "#;
+        let packument: Packument = serde_json::from_str(string).into_diagnostic()?;
+        assert_eq!(
+            packument.created(),
+            Some(Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            packument.modified(),
+            Some(Utc.with_ymd_and_hms(2021, 6, 15, 10, 30, 0).unwrap())
+        );
+        let published: DateTime<Utc> = packument.time["1.0.0"];
+        assert_eq!(published.timestamp_millis(), 1577934245678);
+        assert!(!packument.time.contains_key("unpublished"));
+
+        let roundtrip: Packument =
+            serde_json::from_str(&serde_json::to_string(&packument).into_diagnostic()?)
+                .into_diagnostic()?;
+        assert_eq!(roundtrip.time, packument.time);
+        Ok(())
+    }
+
+    #[test]
+    fn missing_time() -> Result<()> {
+        let packument: Packument =
+            serde_json::from_str(r#"{"name": "hello"}"#).into_diagnostic()?;
+        assert!(packument.time.is_empty());
+        assert_eq!(packument.created(), None);
+        assert_eq!(packument.modified(), None);
+        Ok(())
+    }
+}
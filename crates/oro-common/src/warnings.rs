@@ -0,0 +1,124 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use indexmap::IndexSet;
+use serde::Serialize;
+
+/// What a [`Warning`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WarningKind {
+    /// A package version in the tree has been deprecated by its publisher.
+    Deprecated,
+    /// A peer dependency is missing from the tree, or doesn't match.
+    PeerConflict,
+    /// An optional dependency failed to install, and was left out.
+    SkippedOptional,
+    /// An existing lockfile couldn't be used.
+    Lockfile,
+    /// A dist-tag points somewhere unexpected.
+    DistTag,
+    /// A file was left out of a package because it only differs in case
+    /// from another one, and the filesystem can't tell them apart.
+    CaseCollision,
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            WarningKind::Deprecated => "deprecated",
+            WarningKind::PeerConflict => "peer-conflict",
+            WarningKind::SkippedOptional => "skipped-optional",
+            WarningKind::Lockfile => "lockfile",
+            WarningKind::DistTag => "dist-tag",
+            WarningKind::CaseCollision => "case-collision",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// A non-fatal problem noticed while working on a project, worth telling the
+/// user about once everything's done.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+/// Gathers [`Warning`]s from everywhere they come up, so they can be
+/// reported together at the end instead of getting lost in the logs.
+///
+/// Clones share the same list, and the same warning is only ever kept once,
+/// in the order it was first pushed.
+#[derive(Debug, Clone, Default)]
+pub struct WarningCollector(Arc<Mutex<IndexSet<Warning>>>);
+
+impl WarningCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a warning, unless an identical one was already recorded.
+    pub fn push(&self, kind: WarningKind, message: impl Into<String>) {
+        self.0
+            .lock()
+            .expect("warning list lock poisoned")
+            .insert(Warning {
+                kind,
+                message: message.into(),
+            });
+    }
+
+    /// Every warning recorded so far.
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.0
+            .lock()
+            .expect("warning list lock poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0
+            .lock()
+            .expect("warning list lock poisoned")
+            .is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn dedupes_across_clones() {
+        let collector = WarningCollector::new();
+        let resolver = collector.clone();
+        let linker = collector.clone();
+        resolver.push(WarningKind::Deprecated, "a@1.0.0: use b");
+        linker.push(WarningKind::SkippedOptional, "c@1.0.0: script failed");
+        resolver.push(WarningKind::Deprecated, "a@1.0.0: use b");
+        resolver.push(WarningKind::PeerConflict, "a@1.0.0: use b");
+        assert_eq!(
+            collector
+                .warnings()
+                .iter()
+                .map(|w| w.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "deprecated: a@1.0.0: use b",
+                "skipped-optional: c@1.0.0: script failed",
+                "peer-conflict: a@1.0.0: use b",
+            ]
+        );
+    }
+}
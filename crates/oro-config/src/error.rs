@@ -14,4 +14,20 @@ pub enum OroConfigError {
     #[error(transparent)]
     #[diagnostic(code(oro_config::error), url(docsrs))]
     ConfigParseError(#[from] Box<dyn std::error::Error + Send + Sync>),
+
+    /// A config value referenced an environment variable with `${VAR}`, but
+    /// that variable isn't set. Either set it, or give it a fallback using
+    /// `${VAR:-default}`.
+    #[error("Config value references environment variable `{0}`, but it isn't set.")]
+    #[diagnostic(
+        code(oro_config::undefined_env_var),
+        url(docsrs),
+        help("Set the variable, or provide a fallback with `${{VAR:-default}}`.")
+    )]
+    UndefinedEnvVar(String),
+
+    /// A config value contained a `${` without a matching `}`.
+    #[error("Unterminated `${{` in config value: {0}")]
+    #[diagnostic(code(oro_config::unterminated_interpolation), url(docsrs))]
+    UnterminatedInterpolation(String),
 }
@@ -1,6 +1,8 @@
 use config::{ConfigError, FileStoredFormat, Format, Map, Source, Value, ValueKind};
 use kdl::{KdlDocument, KdlNode, KdlValue};
 
+use crate::error::OroConfigError;
+
 #[derive(Clone, Debug)]
 pub(crate) struct KdlSource(KdlDocument);
 
@@ -14,7 +16,8 @@ impl Source for KdlSource {
         if let Some(config_node) = self.0.get("options") {
             if let Some(children) = config_node.children() {
                 for node in children.nodes() {
-                    map.insert(node.name().value().to_string(), node_value(node));
+                    let value = node_value(node).map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+                    map.insert(node.name().value().to_string(), value);
                 }
             }
         }
@@ -41,9 +44,9 @@ impl FileStoredFormat for KdlFormat {
     }
 }
 
-fn value_kind(value: &KdlValue) -> ValueKind {
-    if let Some(str) = value.as_string() {
-        ValueKind::String(str.into())
+fn value_kind(value: &KdlValue) -> Result<ValueKind, OroConfigError> {
+    Ok(if let Some(str) = value.as_string() {
+        ValueKind::String(interpolate_env(str)?)
     } else if let Some(num) = value.as_i64() {
         ValueKind::I64(num)
     } else if let Some(float) = value.as_f64() {
@@ -52,48 +55,87 @@ fn value_kind(value: &KdlValue) -> ValueKind {
         ValueKind::Boolean(boolean)
     } else {
         ValueKind::Nil
+    })
+}
+
+/// Expands `${VAR}` and `${VAR:-default}` references in a config string
+/// using the current environment. Like in shells, the default is used when
+/// the variable is either unset or empty. A literal `${` can be written as
+/// `$${`.
+fn interpolate_env(input: &str) -> Result<String, OroConfigError> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            out.push_str(&rest[..start - 1]);
+            out.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| OroConfigError::UnterminatedInterpolation(input.into()))?;
+        let expr = &after[..end];
+        let (var, default) = match expr.split_once(":-") {
+            Some((var, default)) => (var, Some(default)),
+            None => (expr, None),
+        };
+        match (std::env::var(var).ok().filter(|v| !v.is_empty()), default) {
+            (Some(value), _) => out.push_str(&value),
+            (None, Some(default)) => out.push_str(default),
+            (None, None) => return Err(OroConfigError::UndefinedEnvVar(var.into())),
+        }
+        rest = &after[end + 1..];
     }
+    out.push_str(rest);
+    Ok(out)
 }
 
-fn map_kind(value: impl Iterator<Item = (String, Value)>) -> ValueKind {
-    ValueKind::Table(value.collect())
+fn map_kind(
+    value: impl Iterator<Item = Result<(String, Value), OroConfigError>>,
+) -> Result<ValueKind, OroConfigError> {
+    Ok(ValueKind::Table(value.collect::<Result<_, _>>()?))
 }
 
-fn array_kind(value: impl Iterator<Item = Value>) -> ValueKind {
-    ValueKind::Array(value.collect())
+fn array_kind(
+    value: impl Iterator<Item = Result<Value, OroConfigError>>,
+) -> Result<ValueKind, OroConfigError> {
+    Ok(ValueKind::Array(value.collect::<Result<_, _>>()?))
 }
 
-fn node_value(node: &KdlNode) -> Value {
+fn node_value(node: &KdlNode) -> Result<Value, OroConfigError> {
     let mut entries = node.entries().iter().filter(|e| e.name().is_none());
     let len = entries.clone().count();
-    if len == 1 {
+    Ok(if len == 1 {
         // foo 1 => { foo: 1 }
         //
         // Technically, this could semantically be an array as well, but we choose
         // to treat single-entries as single values.
         Value::new(
             None,
-            value_kind(entries.next().expect("checked length already").value()),
+            value_kind(entries.next().expect("checked length already").value())?,
         )
     } else if len > 1 {
         // foo 1 2 3 => { foo: [1, 2, 3] }
         Value::new(
             None,
-            array_kind(entries.map(|e| Value::new(None, value_kind(e.value())))),
+            array_kind(entries.map(|e| Ok(Value::new(None, value_kind(e.value())?))))?,
         )
     } else if !node.entries().is_empty() {
         // foo bar=1 => { foo: { bar: 1 } }
         Value::new(
             None,
             map_kind(node.entries().iter().map(|e| {
-                (
+                Ok((
                     e.name()
                         .expect("these should all have names")
                         .value()
                         .to_string(),
-                    Value::new(None, value_kind(e.value())),
-                )
-            })),
+                    Value::new(None, value_kind(e.value())?),
+                ))
+            }))?,
         )
     } else if let Some(children) = node.children() {
         let dash_children = children
@@ -109,7 +151,7 @@ fn node_value(node: &KdlNode) -> Value {
             //   }
             // }
             // => { foo: [1, 2, { bar: 3 }] }
-            Value::new(None, array_kind(children.nodes().iter().map(node_value)))
+            Value::new(None, array_kind(children.nodes().iter().map(node_value))?)
         } else {
             // foo {
             //     bar {
@@ -123,12 +165,12 @@ fn node_value(node: &KdlNode) -> Value {
                     children
                         .nodes()
                         .iter()
-                        .map(|node| (node.name().value().to_string(), node_value(node))),
-                ),
+                        .map(|node| Ok((node.name().value().to_string(), node_value(node)?))),
+                )?,
             )
         }
     } else {
         // Nodes without args or children act the same as "true"
         Value::new(None, ValueKind::Boolean(true))
-    }
+    })
 }
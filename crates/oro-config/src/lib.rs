@@ -12,6 +12,9 @@ use error::OroConfigError;
 
 mod error;
 mod kdl_source;
+mod schema;
+
+pub use schema::config_schema;
 
 pub trait OroConfigLayerExt {
     fn with_negations(self) -> Self;
@@ -221,10 +224,119 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn env_interpolation() -> Result<()> {
+        let dir = tempdir().into_diagnostic()?;
+        let file = dir.path().join("oro.kdl");
+        fs::write(
+            &file,
+            "options{\nregistry \"https://${ORO_TEST_INTERP_HOST}/npm/\"\n}",
+        )
+        .into_diagnostic()?;
+        env::set_var("ORO_TEST_INTERP_HOST", "example.com");
+        let config = OroConfigOptions::new()
+            .env(false)
+            .global_config_file(Some(file))
+            .load();
+        env::remove_var("ORO_TEST_INTERP_HOST");
+        assert_eq!(
+            config?.get_string("registry").into_diagnostic()?,
+            String::from("https://example.com/npm/")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn env_interpolation_default() -> Result<()> {
+        let dir = tempdir().into_diagnostic()?;
+        let file = dir.path().join("oro.kdl");
+        fs::write(
+            &file,
+            "options{\nstore \"${ORO_TEST_INTERP_UNSET:-/tmp/store}\"\n}",
+        )
+        .into_diagnostic()?;
+        let config = OroConfigOptions::new()
+            .env(false)
+            .global_config_file(Some(file))
+            .load()?;
+        assert_eq!(
+            config.get_string("store").into_diagnostic()?,
+            String::from("/tmp/store")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn env_interpolation_escape() -> Result<()> {
+        let dir = tempdir().into_diagnostic()?;
+        let file = dir.path().join("oro.kdl");
+        fs::write(
+            &file,
+            "options{\nstore \"/tmp/$${ORO_TEST_INTERP_ESCAPED}/store\"\n}",
+        )
+        .into_diagnostic()?;
+        let config = OroConfigOptions::new()
+            .env(false)
+            .global_config_file(Some(file))
+            .load()?;
+        assert_eq!(
+            config.get_string("store").into_diagnostic()?,
+            String::from("/tmp/${ORO_TEST_INTERP_ESCAPED}/store")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn env_interpolation_missing_var() -> Result<()> {
+        let dir = tempdir().into_diagnostic()?;
+        let file = dir.path().join("oro.kdl");
+        fs::write(
+            &file,
+            "options{\nstore \"${ORO_TEST_INTERP_MISSING}/store\"\n}",
+        )
+        .into_diagnostic()?;
+        let err = OroConfigOptions::new()
+            .env(false)
+            .global_config_file(Some(file))
+            .load()
+            .expect_err("undefined variables should fail to load");
+        assert!(err.to_string().contains("ORO_TEST_INTERP_MISSING"));
+        Ok(())
+    }
+
     #[test]
     fn missing_config() -> Result<()> {
         let config = OroConfigOptions::new().global(false).env(false).load()?;
         assert!(config.get_string("store").is_err());
         Ok(())
     }
+
+    #[test]
+    fn schema_from_command() {
+        let command = Command::new("oro")
+            .arg(
+                clap::Arg::new("cache_dir")
+                    .long("cache-dir")
+                    .help("Where to cache things"),
+            )
+            .arg(
+                clap::Arg::new("telemetry")
+                    .long("no-telemetry")
+                    .action(clap::ArgAction::SetFalse),
+            )
+            .subcommand(
+                Command::new("apply").arg(
+                    clap::Arg::new("locked")
+                        .long("locked")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+            );
+        let schema = config_schema(&command);
+        let options = &schema["properties"]["options"]["properties"];
+        assert_eq!(options["cache-dir"]["description"], "Where to cache things");
+        assert_eq!(options["telemetry"]["type"], "boolean");
+        assert_eq!(options["telemetry"]["default"], true);
+        assert_eq!(options["locked"]["type"], "boolean");
+        assert!(options.get("help").is_none());
+    }
 }
@@ -0,0 +1,72 @@
+use std::collections::BTreeMap;
+
+use clap::{ArgAction, Command};
+use serde_json::{json, Value};
+
+/// Generates a JSON Schema for `oro.kdl` files, based on the options
+/// accepted by `command` and all of its subcommands.
+///
+/// The `options` node is described as an object whose properties are named
+/// after each option, the same way they're looked up when layering config
+/// into command line arguments.
+pub fn config_schema(command: &Command) -> Value {
+    let mut options = BTreeMap::new();
+    collect_options(command, &mut options);
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "oro.kdl",
+        "description": "Orogene configuration file.",
+        "type": "object",
+        "properties": {
+            "options": {
+                "type": "object",
+                "description": "Default values for command line options.",
+                "properties": options,
+            },
+        },
+    })
+}
+
+fn collect_options(command: &Command, options: &mut BTreeMap<String, Value>) {
+    for arg in command.get_arguments() {
+        if arg.get_long().is_none() || arg.is_hide_set() {
+            continue;
+        }
+        let id = arg.get_id().as_str();
+        if id == "help" || id == "version" {
+            continue;
+        }
+        let name = id.replace('_', "-");
+        if options.contains_key(&name) {
+            continue;
+        }
+        let is_flag = matches!(arg.get_action(), ArgAction::SetTrue | ArgAction::SetFalse);
+        let mut schema = if is_flag {
+            json!({ "type": "boolean" })
+        } else if let ArgAction::Append = arg.get_action() {
+            // Multi-value options can be given as a single value, a list, or
+            // a map of `key=value` pairs.
+            json!({
+                "type": ["string", "array", "object"],
+                "items": { "type": "string" },
+            })
+        } else {
+            json!({ "type": ["string", "number"] })
+        };
+        if let Some(help) = arg.get_help() {
+            schema["description"] = json!(help.to_string());
+        }
+        if let [default] = arg.get_default_values() {
+            let default = default.to_string_lossy();
+            schema["default"] = if is_flag {
+                json!(default == "true")
+            } else {
+                json!(default)
+            };
+        }
+        options.insert(name, schema);
+    }
+    for subcommand in command.get_subcommands() {
+        collect_options(subcommand, options);
+    }
+}
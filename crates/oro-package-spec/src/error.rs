@@ -50,6 +50,18 @@ impl PackageSpecError {
 
         (line_number, column_number)
     }
+
+    /// Whether this error comes from a package name rule that only applies
+    /// to newly-published packages. Older packages on the registry, like
+    /// `JSONStream`, can still break these rules.
+    pub fn is_legacy_name_error(&self) -> bool {
+        matches!(
+            self.kind,
+            SpecErrorKind::NameTooLong(_)
+                | SpecErrorKind::UppercaseName(_)
+                | SpecErrorKind::SpecialCharacterName(_)
+        )
+    }
 }
 
 #[derive(Debug, Error)]
@@ -72,6 +84,22 @@ pub enum SpecErrorKind {
     IncompleteInput,
     #[error("An unspecified error occurred.")]
     Other,
+    #[error("Package names can't be empty.")]
+    EmptyName,
+    #[error("Package names can't start with `{0}`.")]
+    InvalidNameStart(char),
+    #[error("`{0}` is a reserved name and can't be used for a package.")]
+    ReservedName(String),
+    #[error("Invalid scoped package name `{0}`. Scoped names must look like `@scope/name`.")]
+    InvalidScopedName(String),
+    #[error("Package names can only contain URL-safe characters. Found `{0}`.")]
+    UrlUnsafeName(char),
+    #[error("Package names can be at most 214 characters long, but this one is {0}.")]
+    NameTooLong(usize),
+    #[error("Package names can't contain uppercase letters: `{0}`.")]
+    UppercaseName(String),
+    #[error("Package names can't contain special characters like `{0}`.")]
+    SpecialCharacterName(char),
 }
 
 #[derive(Debug)]
@@ -137,6 +137,28 @@ impl GitInfo {
         }
     }
 
+    /// A browsable `https` URL for this repository, suitable for linking to
+    /// from a browser. Only hosted git repositories have one.
+    pub fn browse(&self) -> Option<Url> {
+        use GitHost::*;
+        use GitInfo::*;
+        match self {
+            GitInfo::Url { .. } | Ssh { .. } => None,
+            Hosted {
+                ref host,
+                ref owner,
+                ref repo,
+                ..
+            } => Some(match host {
+                GitHub => format!("https://github.com/{owner}/{repo}"),
+                Gist => format!("https://gist.github.com/{repo}"),
+                GitLab => format!("https://gitlab.com/{owner}/{repo}"),
+                Bitbucket => format!("https://bitbucket.org/{owner}/{repo}"),
+            })
+            .map(|url| url.parse().expect("URL failed to parse")),
+        }
+    }
+
     pub fn tarball(&self) -> Option<Url> {
         use GitHost::*;
         use GitInfo::*;
@@ -15,6 +15,7 @@ use crate::parsers::package;
 
 mod error;
 mod gitinfo;
+mod name;
 mod parsers;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -39,6 +40,11 @@ pub enum PackageSpec {
         requested: Option<VersionSpec>,
     },
     Git(GitInfo),
+    /// A `catalog:` dependency, whose actual version is defined in the root
+    /// project's `catalog` (when `name` is `None`) or `catalogs` field.
+    Catalog {
+        name: Option<String>,
+    },
 }
 
 impl PackageSpec {
@@ -50,7 +56,7 @@ impl PackageSpec {
         use PackageSpec::*;
         match self {
             Alias { spec, .. } => spec.is_npm(),
-            Dir { .. } | Git(..) => false,
+            Dir { .. } | Git(..) | Catalog { .. } => false,
             Npm { .. } => true,
         }
     }
@@ -83,11 +89,22 @@ impl PackageSpec {
         }
     }
 
+    /// Checks that `name` follows npm's package naming rules, returning it
+    /// as-is if it does. Names must be at most 214 characters, can't start
+    /// with `.` or `_`, can't contain uppercase letters or URL-unsafe
+    /// characters, and scoped names must look like `@scope/name`.
+    pub fn normalize_name(name: impl AsRef<str>) -> Result<String, PackageSpecError> {
+        let name = name.as_ref();
+        name::validate_name(name)?;
+        Ok(name.into())
+    }
+
     pub fn requested(&self) -> String {
         use PackageSpec::*;
         match self {
             Dir { path } => format!("{}", path.display()),
             Git(info) => format!("{info}"),
+            Catalog { .. } => format!("{self}"),
             Npm { ref requested, .. } => requested
                 .as_ref()
                 .map(|r| r.to_string())
@@ -117,6 +134,7 @@ impl fmt::Display for PackageSpec {
         match self {
             Dir { path } => write!(f, "{}", path.display()),
             Git(info) => write!(f, "{info}"),
+            Catalog { name } => write!(f, "catalog:{}", name.as_deref().unwrap_or("")),
             Npm {
                 ref name,
                 ref requested,
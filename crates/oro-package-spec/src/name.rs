@@ -0,0 +1,71 @@
+use crate::error::{PackageSpecError, SpecErrorKind};
+
+/// Longest name the npm registry will accept for new packages.
+const MAX_NAME_LENGTH: usize = 214;
+
+/// Names that can never be used for a package.
+const RESERVED_NAMES: &[&str] = &["node_modules", "favicon.ico"];
+
+/// Checks `name` against npm's rules for package names. Rules that only
+/// apply to newly-published packages are checked last, so callers can tell
+/// them apart using [`PackageSpecError::is_legacy_name_error`].
+pub(crate) fn validate_name(name: &str) -> Result<(), PackageSpecError> {
+    let err = |offset: usize, kind: SpecErrorKind| PackageSpecError {
+        input: name.into(),
+        offset,
+        kind,
+    };
+
+    if name.is_empty() {
+        return Err(err(0, SpecErrorKind::EmptyName));
+    }
+    if let Some(first) = name.chars().next().filter(|c| *c == '.' || *c == '_') {
+        return Err(err(0, SpecErrorKind::InvalidNameStart(first)));
+    }
+    if RESERVED_NAMES.contains(&name.to_lowercase().as_str()) {
+        return Err(err(0, SpecErrorKind::ReservedName(name.into())));
+    }
+
+    // Scoped names are checked one component at a time, since the `@` and
+    // `/` separating them aren't allowed anywhere else.
+    let (bare, bare_offset) = if let Some(scoped) = name.strip_prefix('@') {
+        match scoped.split_once('/') {
+            Some((scope, pkg)) if !scope.is_empty() && !pkg.is_empty() => {
+                check_url_safe(scope, 1).map_err(|(i, c)| err(i, c))?;
+                check_url_safe(pkg, scope.len() + 2).map_err(|(i, c)| err(i, c))?;
+                (pkg, scope.len() + 2)
+            }
+            _ => return Err(err(0, SpecErrorKind::InvalidScopedName(name.into()))),
+        }
+    } else {
+        check_url_safe(name, 0).map_err(|(i, c)| err(i, c))?;
+        (name, 0)
+    };
+
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(err(MAX_NAME_LENGTH, SpecErrorKind::NameTooLong(name.len())));
+    }
+    if let Some(idx) = name.find(|c: char| c.is_uppercase()) {
+        return Err(err(idx, SpecErrorKind::UppercaseName(name.into())));
+    }
+    if let Some(idx) = bare.find(|c| matches!(c, '~' | '\'' | '!' | '(' | ')' | '*')) {
+        let c = bare[idx..].chars().next().expect("found above");
+        return Err(err(
+            bare_offset + idx,
+            SpecErrorKind::SpecialCharacterName(c),
+        ));
+    }
+    Ok(())
+}
+
+/// Characters left alone by JavaScript's `encodeURIComponent`, which is what
+/// npm uses to decide whether a name is URL-safe.
+fn check_url_safe(component: &str, offset: usize) -> Result<(), (usize, SpecErrorKind)> {
+    match component.char_indices().find(|(_, c)| {
+        !(c.is_ascii_alphanumeric()
+            || matches!(c, '-' | '_' | '.' | '!' | '~' | '*' | '\'' | '(' | ')'))
+    }) {
+        Some((idx, c)) => Err((offset + idx, SpecErrorKind::UrlUnsafeName(c))),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,28 @@
+use nom::bytes::complete::{tag_no_case as tag, take_till1};
+use nom::combinator::{map, map_res, opt};
+use nom::error::context;
+use nom::sequence::preceded;
+use nom::IResult;
+
+use crate::error::SpecParseError;
+use crate::parsers::util;
+use crate::PackageSpec;
+
+/// catalog-spec := "catalog:" [ not(whitespace)+ ]
+pub(crate) fn catalog_spec(input: &str) -> IResult<&str, PackageSpec, SpecParseError<&str>> {
+    context(
+        "catalog spec",
+        map(
+            preceded(
+                tag("catalog:"),
+                opt(map_res(
+                    take_till1(|c: char| c.is_whitespace()),
+                    util::no_url_encode,
+                )),
+            ),
+            |name| PackageSpec::Catalog {
+                name: name.map(String::from),
+            },
+        ),
+    )(input)
+}
@@ -1,4 +1,5 @@
 pub mod alias;
+pub mod catalog;
 pub mod git;
 pub mod npm;
 pub mod package;
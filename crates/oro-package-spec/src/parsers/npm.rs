@@ -3,7 +3,7 @@ use node_semver::{Range as SemVerRange, Version as SemVerVersion};
 use nom::branch::alt;
 use nom::bytes::complete::{tag_no_case as tag, take_till1};
 use nom::character::complete::char;
-use nom::combinator::{cut, map, map_res, opt};
+use nom::combinator::{cut, eof, map, map_res, opt};
 use nom::error::context;
 use nom::sequence::{delimited, preceded, tuple};
 use nom::IResult;
@@ -45,10 +45,15 @@ pub(crate) fn npm_spec(input: &str) -> IResult<&str, PackageSpec, SpecParseError
 fn version_req(input: &str) -> IResult<&str, VersionSpec, SpecParseError<&str>> {
     context(
         "version requirement",
-        alt((semver_version, semver_range, version_tag)),
+        alt((semver_version, semver_range, version_tag, empty_range)),
     )(input)
 }
 
+/// Nothing after the `@`, like in `foo@`, which npm treats the same as `*`.
+fn empty_range(input: &str) -> IResult<&str, VersionSpec, SpecParseError<&str>> {
+    map(eof, |_| VersionSpec::Range(SemVerRange::any()))(input)
+}
+
 fn semver_version(input: &str) -> IResult<&str, VersionSpec, SpecParseError<&str>> {
     let (input, version) = map_res(take_till1(|_| false), SemVerVersion::parse)(input)?;
     Ok((input, VersionSpec::Version(version)))
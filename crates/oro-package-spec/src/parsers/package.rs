@@ -6,15 +6,16 @@ use nom::sequence::preceded;
 use nom::IResult;
 
 use crate::error::SpecParseError;
-use crate::parsers::{alias, git, npm, path};
+use crate::parsers::{alias, catalog, git, npm, path};
 use crate::PackageSpec;
 
-/// package-spec := alias | ( [ "npm:" ] npm-pkg ) | ( [ "file:" ] path ) | git-pkg
+/// package-spec := alias | catalog-pkg | ( [ "npm:" ] npm-pkg ) | ( [ "file:" ] path ) | git-pkg
 pub(crate) fn package_spec(input: &str) -> IResult<&str, PackageSpec, SpecParseError<&str>> {
     context(
         "package arg",
         alt((
             alias::alias_spec,
+            catalog::catalog_spec,
             preceded(opt(tag("file:")), path::path_spec),
             git::git_spec,
             preceded(opt(tag("npm:")), npm::npm_spec),
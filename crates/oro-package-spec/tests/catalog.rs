@@ -0,0 +1,69 @@
+use oro_package_spec::{PackageSpec, PackageSpecError};
+
+type Result<T> = std::result::Result<T, PackageSpecError>;
+
+fn parse(input: &str) -> Result<PackageSpec> {
+    input.parse()
+}
+
+#[test]
+fn catalog_default() -> Result<()> {
+    let res = parse("catalog:")?;
+    assert_eq!(res, PackageSpec::Catalog { name: None });
+    Ok(())
+}
+
+#[test]
+fn catalog_named() -> Result<()> {
+    let res = parse("catalog:react18")?;
+    assert_eq!(
+        res,
+        PackageSpec::Catalog {
+            name: Some("react18".into())
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn alias_catalog() -> Result<()> {
+    let res = parse("react@catalog:react18")?;
+    assert_eq!(
+        res,
+        PackageSpec::Alias {
+            name: "react".into(),
+            spec: Box::new(PackageSpec::Catalog {
+                name: Some("react18".into())
+            })
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn alias_scoped_catalog() -> Result<()> {
+    let res = parse("@types/node@catalog:")?;
+    assert_eq!(
+        res,
+        PackageSpec::Alias {
+            name: "@types/node".into(),
+            spec: Box::new(PackageSpec::Catalog { name: None })
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn catalog_display() -> Result<()> {
+    assert_eq!(parse("foo@catalog:")?.to_string(), "foo@catalog:");
+    assert_eq!(
+        parse("foo@catalog:react18")?.to_string(),
+        "foo@catalog:react18"
+    );
+    Ok(())
+}
+
+#[test]
+fn catalog_rejects_whitespace() {
+    assert!(parse("foo@catalog:react 18").is_err());
+}
@@ -0,0 +1,94 @@
+use oro_package_spec::{PackageSpec, PackageSpecError, SpecErrorKind};
+
+type Result<T> = std::result::Result<T, PackageSpecError>;
+
+fn invalid(name: &str) -> PackageSpecError {
+    PackageSpec::normalize_name(name).expect_err("name should be invalid")
+}
+
+#[test]
+fn valid_names() -> Result<()> {
+    for name in [
+        "foo",
+        "foo-bar",
+        "foo.bar",
+        "foo_bar",
+        "123",
+        "@scope/foo",
+        "@my-org/foo.bar",
+    ] {
+        assert_eq!(PackageSpec::normalize_name(name)?, name);
+    }
+    Ok(())
+}
+
+#[test]
+fn too_long() {
+    let name = "a".repeat(215);
+    let err = invalid(&name);
+    assert!(matches!(err.kind, SpecErrorKind::NameTooLong(215)));
+    assert!(err.is_legacy_name_error());
+    assert!(PackageSpec::normalize_name("a".repeat(214)).is_ok());
+}
+
+#[test]
+fn uppercase() {
+    let err = invalid("JSONStream");
+    assert!(matches!(err.kind, SpecErrorKind::UppercaseName(_)));
+    assert_eq!(err.offset, 0);
+    assert!(err.is_legacy_name_error());
+}
+
+#[test]
+fn leading_dot_or_underscore() {
+    assert!(matches!(
+        invalid(".foo").kind,
+        SpecErrorKind::InvalidNameStart('.')
+    ));
+    assert!(matches!(
+        invalid("_foo").kind,
+        SpecErrorKind::InvalidNameStart('_')
+    ));
+}
+
+#[test]
+fn url_unsafe() {
+    let err = invalid("foo bar");
+    assert!(matches!(err.kind, SpecErrorKind::UrlUnsafeName(' ')));
+    assert_eq!(err.offset, 3);
+    assert!(!err.is_legacy_name_error());
+    assert!(matches!(
+        invalid("foo/bar").kind,
+        SpecErrorKind::UrlUnsafeName('/')
+    ));
+}
+
+#[test]
+fn special_characters() {
+    let err = invalid("foo!");
+    assert!(matches!(err.kind, SpecErrorKind::SpecialCharacterName('!')));
+    assert!(err.is_legacy_name_error());
+}
+
+#[test]
+fn malformed_scope() {
+    for name in ["@scope", "@/foo", "@scope/", "@"] {
+        assert!(
+            matches!(invalid(name).kind, SpecErrorKind::InvalidScopedName(_)),
+            "{name}"
+        );
+    }
+    assert!(matches!(
+        invalid("@scope/foo/bar").kind,
+        SpecErrorKind::UrlUnsafeName('/')
+    ));
+}
+
+#[test]
+fn reserved_and_empty() {
+    assert!(matches!(invalid("").kind, SpecErrorKind::EmptyName));
+    assert!(matches!(
+        invalid("node_modules").kind,
+        SpecErrorKind::ReservedName(_)
+    ));
+}
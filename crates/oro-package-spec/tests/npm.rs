@@ -231,6 +231,86 @@ fn alternate_version_ranges() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn range_satisfaction() -> Result<()> {
+    for (input, matching, not_matching) in [
+        ("1.2.x", &["1.2.0", "1.2.9"][..], &["1.1.9", "1.3.0"][..]),
+        ("1.x", &["1.0.0", "1.9.9"], &["0.9.9", "2.0.0"]),
+        ("*", &["0.0.1", "1.2.3", "99.0.0"], &["1.0.0-beta.1"]),
+        ("", &["0.0.1", "1.2.3", "99.0.0"], &["1.0.0-beta.1"]),
+        (
+            "1.2.3 - 2.3.4",
+            &["1.2.3", "2.0.0", "2.3.4"],
+            &["1.2.2", "2.3.5"],
+        ),
+        ("1.2 - 2.3", &["1.2.0", "2.3.9"], &["1.1.9", "2.4.0"]),
+    ] {
+        let PackageSpec::Npm {
+            requested: Some(VersionSpec::Range(range)),
+            ..
+        } = parse(&format!("world@{input}"))?
+        else {
+            panic!("world@{input} should parse as a range");
+        };
+        for version in matching {
+            assert!(
+                range.satisfies(&SemVerVersion::parse(version).unwrap()),
+                "{input} should match {version}"
+            );
+        }
+        for version in not_matching {
+            assert!(
+                !range.satisfies(&SemVerVersion::parse(version).unwrap()),
+                "{input} shouldn't match {version}"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn version_ranges_round_trip() -> Result<()> {
+    let versions = [
+        "0.9.0", "1.0.0", "1.2.0", "1.2.3", "1.2.9", "1.3.0", "1.9.9", "2.0.0", "2.3.4", "2.3.5",
+        "2.9.9", "3.0.0",
+    ]
+    .map(|v| SemVerVersion::parse(v).unwrap());
+    for input in [
+        "^1.0.0 || ^2.0.0",
+        "1.2.3 - 2.3.4",
+        "1.2.x",
+        "1.x",
+        "1.2.3 - 2.3.4 || 3.x",
+        "~1.2.3 || >=2.3.5 <3.0.0",
+    ] {
+        let spec = parse(&format!("world@{input}"))?;
+        let printed = spec.to_string();
+        let reparsed = parse(&printed)?;
+        assert_eq!(spec, reparsed, "{input} printed as {printed}");
+        let (
+            PackageSpec::Npm {
+                requested: Some(VersionSpec::Range(original)),
+                ..
+            },
+            PackageSpec::Npm {
+                requested: Some(VersionSpec::Range(printed_range)),
+                ..
+            },
+        ) = (&spec, &reparsed)
+        else {
+            panic!("{input} should parse as a range");
+        };
+        for version in &versions {
+            assert_eq!(
+                original.satisfies(version),
+                printed_range.satisfies(version),
+                "{input} and {printed} disagree about {version}"
+            );
+        }
+    }
+    Ok(())
+}
+
 #[test]
 fn npm_pkg_bad_tag() -> Result<()> {
     let res = parse("hello-world@%&W$@#$");
@@ -19,7 +19,7 @@
 // ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR
 // IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use once_cell::sync::Lazy;
 use regex::Regex;
@@ -32,7 +32,14 @@ static SHEBANG_REGEX: Lazy<Regex> = Lazy::new(|| {
 static DOLLAR_EXPR_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"\$\{?(?P<var>[^$@#?\- \t{}:]+)\}?").unwrap());
 
-pub fn shim_bin(source: &Path, to: &Path) -> std::io::Result<()> {
+/// Interpreters that are usually found on the `PATH`, rather than at the
+/// same absolute path on every machine.
+const KNOWN_INTERPRETERS: &[&str] = &["node", "bun", "deno", "ts-node"];
+
+/// Writes shims at `to` that run the bin at `source`. Returns the paths of
+/// every file it wrote, in the order they were written: the `sh` shim at `to`
+/// itself, then its `.cmd` and `.ps1` counterparts.
+pub fn shim_bin(source: &Path, to: &Path) -> std::io::Result<Vec<PathBuf>> {
     // First, we blow away anything that already exists there.
     // TODO: get rid of .expect()s?
     let from = pathdiff::diff_paths(source, to.parent().expect("must have parent"))
@@ -43,7 +50,9 @@ pub fn shim_bin(source: &Path, to: &Path) -> std::io::Result<()> {
         if let Some(first_line) = lines.next() {
             if let Some(captures) = SHEBANG_REGEX.captures(first_line.trim_end()) {
                 let vars = captures.name("vars").map(|m| m.as_str());
-                let prog = captures.name("prog").map(|m| m.as_str());
+                let prog = captures
+                    .name("prog")
+                    .map(|m| normalize_interpreter(m.as_str()));
                 let args = captures.name("args").map(|m| m.as_str());
                 return write_shim(&from, to, vars, prog, args);
             }
@@ -52,6 +61,20 @@ pub fn shim_bin(source: &Path, to: &Path) -> std::io::Result<()> {
     write_shim(&from, to, None, None, None)
 }
 
+/// Turns shebang programs like `/usr/local/bin/bun` into a plain `bun`, so
+/// shims look up well-known interpreters on the `PATH` instead of wherever
+/// they happened to be installed on the package author's machine. Any other
+/// program is left alone.
+fn normalize_interpreter(prog: &str) -> &str {
+    let name = prog.rsplit(['/', '\\']).next().unwrap_or(prog);
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    KNOWN_INTERPRETERS
+        .iter()
+        .find(|known| **known == name)
+        .copied()
+        .unwrap_or(prog)
+}
+
 fn cleanup_existing(to: &Path) -> std::io::Result<()> {
     if let Ok(meta) = to.metadata() {
         if meta.is_dir() {
@@ -85,11 +108,12 @@ fn write_shim(
     vars: Option<&str>,
     prog: Option<&str>,
     args: Option<&str>,
-) -> std::io::Result<()> {
-    write_cmd_shim(from, to, vars, prog, args)?;
-    write_sh_shim(from, to, vars, prog, args)?;
-    write_pwsh_shim(from, to, vars, prog, args)?;
-    Ok(())
+) -> std::io::Result<Vec<PathBuf>> {
+    Ok(vec![
+        write_sh_shim(from, to, vars, prog, args)?,
+        write_cmd_shim(from, to, vars, prog, args)?,
+        write_pwsh_shim(from, to, vars, prog, args)?,
+    ])
 }
 
 fn write_cmd_shim(
@@ -98,7 +122,7 @@ fn write_cmd_shim(
     vars: Option<&str>,
     prog: Option<&str>,
     args: Option<&str>,
-) -> std::io::Result<()> {
+) -> std::io::Result<PathBuf> {
     let mut cmd = concat!(
         "@ECHO off\r\n",
         "GOTO start\r\n",
@@ -139,9 +163,10 @@ fn write_cmd_shim(
         cmd.push_str(&format!("{target} %*\r\n",));
     }
 
-    std::fs::write(to.with_extension("cmd"), cmd)?;
+    let path = to.with_extension("cmd");
+    std::fs::write(&path, cmd)?;
 
-    Ok(())
+    Ok(path)
 }
 
 fn write_sh_shim(
@@ -150,7 +175,7 @@ fn write_sh_shim(
     vars: Option<&str>,
     prog: Option<&str>,
     args: Option<&str>,
-) -> std::io::Result<()> {
+) -> std::io::Result<PathBuf> {
     let mut sh = concat!(
         "#!/bin/sh\n",
         r#"basedir = $(dirname "$(echo "$0" | sed -e 's,\\,/,g')")"#,
@@ -182,7 +207,7 @@ fn write_sh_shim(
 
     std::fs::write(to, sh)?;
 
-    Ok(())
+    Ok(to.to_path_buf())
 }
 
 fn write_pwsh_shim(
@@ -191,7 +216,7 @@ fn write_pwsh_shim(
     vars: Option<&str>,
     prog: Option<&str>,
     args: Option<&str>,
-) -> std::io::Result<()> {
+) -> std::io::Result<PathBuf> {
     let mut pwsh = concat!(
         "#!/usr/bin/env pwsh\n",
         "$basedir=Split-Path $MyInvocation.MyCommand.Definition -Parent\n",
@@ -248,9 +273,10 @@ fn write_pwsh_shim(
         pwsh.push_str("exit $LASTEXITCODE\n");
     }
 
-    std::fs::write(to.with_extension("ps1"), pwsh)?;
+    let path = to.with_extension("ps1");
+    std::fs::write(&path, pwsh)?;
 
-    Ok(())
+    Ok(path)
 }
 
 fn convert_to_set_commands(variables: &str) -> String {
@@ -71,3 +71,48 @@ fn multiple_variables() {
 fn shebang_with_env_s() {
     assert_fixture!("from.env.S");
 }
+
+#[test]
+fn returns_created_files() {
+    let tempdir = tempfile::tempdir_in(fixtures()).unwrap();
+    let to = tempdir.path().join("shim");
+    let created = oro_shim_bin::shim_bin(&fixtures().join("from.env"), &to).unwrap();
+    assert_eq!(
+        created,
+        vec![
+            to.clone(),
+            to.with_extension("cmd"),
+            to.with_extension("ps1")
+        ]
+    );
+    for path in created {
+        assert!(path.exists(), "{} should exist", path.display());
+    }
+}
+
+#[test]
+fn known_interpreters_use_path() {
+    for (shebang, prog) in [
+        ("#!/usr/bin/env node", "node"),
+        ("#!/usr/local/bin/node", "node"),
+        ("#!/usr/bin/env bun", "bun"),
+        ("#!/usr/local/bin/bun", "bun"),
+        ("#!/home/user/.deno/bin/deno run", "deno"),
+        ("#!/usr/local/bin/ts-node", "ts-node"),
+    ] {
+        let tempdir = tempfile::tempdir().unwrap();
+        let from = tempdir.path().join("from");
+        std::fs::write(&from, format!("{shebang}\nconsole.log('hi')\n")).unwrap();
+        let to = tempdir.path().join("shim");
+        oro_shim_bin::shim_bin(&from, &to).unwrap();
+        let cmd = std::fs::read_to_string(to.with_extension("cmd")).unwrap();
+        assert!(
+            cmd.contains(&format!("IF EXIST \"%dp0%\\{prog}.exe\" (\r\n")),
+            "{shebang}: {cmd}"
+        );
+        assert!(
+            cmd.contains(&format!("  SET \"_prog={prog}\"\r\n")),
+            "{shebang}: {cmd}"
+        );
+    }
+}
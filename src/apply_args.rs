@@ -1,16 +1,50 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-use clap::Args;
+use clap::{builder::PossibleValuesParser, clap_derive::ValueEnum, Args};
+use dialoguer::{theme::ColorfulTheme, Confirm};
 use indicatif::ProgressStyle;
-use miette::Result;
-use node_maintainer::{NodeMaintainer, NodeMaintainerOptions};
-use oro_common::CorgiManifest;
+use is_terminal::IsTerminal;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use node_maintainer::{
+    Nassun, NodeMaintainer, NodeMaintainerError, NodeMaintainerOptions, ProjectLock, KNOWN_CPU,
+    KNOWN_LIBC, KNOWN_OS,
+};
+use node_semver::{Range, Version};
+use oro_client::{audit::Severity, OroClientBuilder};
+use oro_common::{CorgiManifest, Manifest, WarningCollector};
 use rand::seq::IteratorRandom;
+use serde::Serialize;
 use tracing::{Instrument, Span};
 use tracing_indicatif::span_ext::IndicatifSpanExt;
 use url::Url;
 
+use crate::client_args::ClientArgs;
+use crate::error::OroError;
+use crate::format::format_duration;
 use crate::nassun_args::NassunArgs;
+use crate::output::{OutputArgs, OutputFormat};
+
+/// Lowest advisory severity that makes `--audit` fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AuditLevel {
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl From<AuditLevel> for Severity {
+    fn from(level: AuditLevel) -> Self {
+        match level {
+            AuditLevel::Low => Severity::Low,
+            AuditLevel::Moderate => Severity::Moderate,
+            AuditLevel::High => Severity::High,
+            AuditLevel::Critical => Severity::Critical,
+        }
+    }
+}
 
 /// Applies the current project's requested dependencies to `node_modules/`,
 /// adding, removing, and updating dependencies as needed. This command is
@@ -26,6 +60,15 @@ pub struct ApplyArgs {
     )]
     pub apply: bool,
 
+    /// What to do when the `packageManager` field in package.json asks for a
+    /// version of oro that doesn't match this one.
+    ///
+    /// `warn` logs a warning and keeps going, while `error` stops before
+    /// anything is applied. Fields naming a package manager other than oro
+    /// are ignored.
+    #[arg(long, value_name = "LEVEL", value_enum, default_value_t = PackageManagerCheck::Off)]
+    pub package_manager_check: PackageManagerCheck,
+
     /// When extracting packages, prefer to copy files files instead of
     /// linking them.
     ///
@@ -37,7 +80,9 @@ pub struct ApplyArgs {
 
     /// Whether to skip restoring packages into `node_modules` and just
     /// resolve the tree and write the lockfile.
-    #[arg(long)]
+    ///
+    /// Cannot be used with `--no-lockfile`.
+    #[arg(long, conflicts_with = "lockfile")]
     pub lockfile_only: bool,
 
     /// Make the resolver error if the newly-resolved tree would defer from
@@ -45,10 +90,61 @@ pub struct ApplyArgs {
     #[arg(long, visible_alias = "frozen")]
     pub locked: bool,
 
+    /// Discard a `package-lock.kdl` that can't be parsed and resolve a fresh
+    /// one from package.json, instead of erroring.
+    ///
+    /// When running interactively, you'll be asked whether to do this
+    /// instead.
+    #[arg(long)]
+    pub fix_lockfile: bool,
+
+    /// Use the lockfile for every package it covers, without checking
+    /// dist-tags or git refs against the registry or remote.
+    #[arg(long)]
+    pub offline_first: bool,
+
+    /// Skip installing peer dependencies that nothing else in the tree
+    /// provides, and only report them as missing.
+    #[arg(long = "no-auto-install-peers", action = clap::ArgAction::SetFalse)]
+    pub auto_install_peers: bool,
+
+    /// Install missing peer dependencies, using a single version for each
+    /// that satisfies every package that needs it.
+    #[arg(long)]
+    pub dedupe_peer_deps: bool,
+
+    /// Resolve peer dependencies the way npm did before v7: never install
+    /// missing ones, and only warn about conflicting ones instead of failing.
+    #[arg(long)]
+    pub legacy_peer_deps: bool,
+
     /// Skip running install scripts.
     #[arg(long = "no-scripts", alias = "ignore-scripts", action = clap::ArgAction::SetFalse)]
     pub scripts: bool,
 
+    /// Skip creating `node_modules/.bin` entries for package executables.
+    #[arg(long = "no-bin-links", action = clap::ArgAction::SetFalse)]
+    pub bin_links: bool,
+
+    /// Leave out files that packages rarely need at runtime, like tests,
+    /// docs, and source maps, when extracting them.
+    ///
+    /// A package's `package.json`, license files, and `main` and `bin`
+    /// files are always kept. Packages can opt out of trimming with
+    /// `"orogene": { "trim": false }` in their `package.json`.
+    #[arg(long)]
+    pub trim: bool,
+
+    /// File or directory name for `--trim` to leave out, instead of the
+    /// defaults (`/test/`, `/tests/`, `__tests__/`, `/.github/`, `*.md`, and
+    /// `*.map`).
+    ///
+    /// Can be provided multiple times. `*` matches any characters, a
+    /// trailing `/` only matches directories, and a leading `/` only matches
+    /// at the top of a package. Has no effect without `--trim`.
+    #[arg(long, value_name = "PATTERN")]
+    pub trim_pattern: Vec<String>,
+
     /// Default dist-tag to use when resolving package versions.
     #[arg(long, default_value = "latest")]
     pub default_tag: String,
@@ -69,10 +165,19 @@ pub struct ApplyArgs {
     #[arg(long, default_value_t = node_maintainer::DEFAULT_SCRIPT_CONCURRENCY)]
     pub script_concurrency: usize,
 
+    /// Environment variable to set for a specific package's install
+    /// scripts, using `--install-env {sharp}SHARP_IGNORE_GLOBAL_LIBVIPS=1`
+    /// format.
+    ///
+    /// Can be provided multiple times. In an oro.kdl, use an `install-env`
+    /// node with a child for each package, holding its variables.
+    #[arg(long, value_parser = crate::parse_nested_key_value::<String, String, String>)]
+    pub install_env: Vec<(String, String, String)>,
+
     /// Disable writing the lockfile after operations complete.
     ///
     /// Note that lockfiles are only written after all operations complete
-    /// successfully.
+    /// successfully. Cannot be used with `--lockfile-only`.
     #[arg(long = "no-lockfile", action = clap::ArgAction::SetFalse)]
     pub lockfile: bool,
 
@@ -89,6 +194,48 @@ pub struct ApplyArgs {
     #[arg(long)]
     pub hoisted: bool,
 
+    /// Operating system to install optional dependencies for, instead of the
+    /// current one.
+    #[arg(long, value_name = "OS", value_parser = PossibleValuesParser::new(KNOWN_OS.iter().copied()))]
+    pub target_os: Option<String>,
+
+    /// CPU architecture to install optional dependencies for, instead of the
+    /// current one.
+    #[arg(long, value_name = "CPU", value_parser = PossibleValuesParser::new(KNOWN_CPU.iter().copied()))]
+    pub target_cpu: Option<String>,
+
+    /// C standard library to install optional dependencies for, instead of
+    /// the current one. Only affects Linux installs.
+    #[arg(long, value_name = "LIBC", value_parser = PossibleValuesParser::new(KNOWN_LIBC.iter().copied()))]
+    pub target_libc: Option<String>,
+
+    /// Write a JSON report of how long each apply phase took to this path.
+    ///
+    /// The report also includes package counts, cache hits and misses for
+    /// package tarballs, and the total number of tarball bytes downloaded.
+    #[arg(long, value_name = "PATH")]
+    pub report_timings: Option<PathBuf>,
+
+    /// Check the resolved tree against the registry's security advisories
+    /// before installing anything, and fail if any are found.
+    #[arg(long)]
+    pub audit: bool,
+
+    /// Lowest advisory severity that makes `--audit` fail.
+    ///
+    /// Advisories below this level are ignored. Has no effect without
+    /// `--audit`.
+    #[arg(long, value_enum, default_value_t = AuditLevel::Low)]
+    pub audit_level: AuditLevel,
+
+    /// Write a JSON dump of the resolver's inputs, the versions it saw and
+    /// picked, and the final dependency graph to this path.
+    ///
+    /// Credentials in URLs are scrubbed, so the dump can be attached to bug
+    /// reports about resolution problems.
+    #[arg(long, value_name = "PATH")]
+    pub debug_resolver: Option<PathBuf>,
+
     #[arg(from_global)]
     pub registry: Url,
 
@@ -104,14 +251,20 @@ pub struct ApplyArgs {
     #[arg(from_global)]
     pub no_proxy_domain: Option<String>,
 
+    #[arg(from_global)]
+    pub ca_bundle: Option<PathBuf>,
+
+    #[arg(from_global)]
+    pub danger_accept_invalid_certs: bool,
+
     #[arg(from_global)]
     pub retries: u32,
 
     #[arg(from_global)]
     pub auth: Vec<(String, String, String)>,
 
-    #[arg(from_global)]
-    pub json: bool,
+    #[command(flatten)]
+    pub output: OutputArgs,
 
     #[arg(from_global)]
     pub root: PathBuf,
@@ -119,28 +272,107 @@ pub struct ApplyArgs {
     #[arg(from_global)]
     pub cache: Option<PathBuf>,
 
+    #[arg(from_global)]
+    pub store_dir: Option<PathBuf>,
+
+    #[arg(from_global)]
+    pub metadata_cache_dir: Option<PathBuf>,
+
     #[arg(from_global)]
     pub emoji: bool,
+
+    #[arg(from_global)]
+    pub extra_network: bool,
+
+    /// Packages to re-resolve instead of taking their versions from the
+    /// lockfile. Set by commands like `oro update`, rather than on the
+    /// command line. An empty list updates everything.
+    #[arg(skip)]
+    pub update: Option<Vec<String>>,
+
+    /// Record incompatible peer dependencies as warnings instead of failing
+    /// resolution, so the command can decide which ones matter. Set by
+    /// `oro add`, rather than on the command line.
+    #[arg(skip)]
+    pub allow_peer_conflicts: bool,
+
+    /// Collects warnings from resolution, linking, and friends, so they can
+    /// be summarized once the command is done.
+    #[arg(skip)]
+    pub warnings: WarningCollector,
+}
+
+/// How strictly to enforce the `packageManager` field in package.json.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PackageManagerCheck {
+    Off,
+    Warn,
+    Error,
 }
 
 impl ApplyArgs {
     pub async fn execute(&self, manifest: CorgiManifest) -> Result<()> {
+        self.execute_checked(manifest, |_| Ok(())).await
+    }
+
+    /// Like [`Self::execute`], but runs `check` against the resolved tree
+    /// before anything is written to `node_modules/` or the lockfile, and
+    /// stops if it returns an error.
+    pub async fn execute_checked(
+        &self,
+        manifest: CorgiManifest,
+        check: impl FnOnce(&NodeMaintainer) -> Result<()>,
+    ) -> Result<()> {
+        let result = self.apply_checked(manifest, check).await;
+        self.report_warnings();
+        result
+    }
+
+    async fn apply_checked(
+        &self,
+        manifest: CorgiManifest,
+        check: impl FnOnce(&NodeMaintainer) -> Result<()>,
+    ) -> Result<()> {
         let total_time = std::time::Instant::now();
 
+        self.check_package_manager().await?;
+
         if !self.apply {
             tracing::info!("{}Skipping applying node_modules/.", self.emoji_tada(),);
             return Ok(());
         }
 
         let root = &self.root;
+        // Held until we're done touching node_modules/ and the lockfile.
+        let _lock = ProjectLock::acquire(root)?;
+        let nassun = NassunArgs::from_apply_args(self).to_nassun()?;
+        let mut report = TimingReport::default();
+
+        let start = Instant::now();
         let maintainer = self
-            .resolve(manifest, self.configured_maintainer()?)
+            .resolve(manifest, self.configured_maintainer(nassun.clone()))
             .await?;
+        report.phase("resolve", start, Some(maintainer.package_count()));
+        check(&maintainer)?;
+
+        if self.audit && !self.extra_network {
+            tracing::warn!("Skipping --audit, since --no-extra-network is set.");
+        } else if self.audit {
+            self.audit(&maintainer).await?;
+        }
 
         if !self.lockfile_only {
-            self.prune(&maintainer).await?;
-            self.extract(&maintainer).await?;
+            let start = Instant::now();
+            let pruned = self.prune(&maintainer).await?;
+            report.phase("prune", start, Some(pruned));
+
+            let start = Instant::now();
+            let extracted = self.extract(&maintainer).await?;
+            report.phase("extract", start, Some(extracted));
+
+            let start = Instant::now();
             self.rebuild(&maintainer).await?;
+            report.phase("rebuild", start, None);
         } else {
             tracing::info!(
                 "{}Skipping installing node_modules/, only writing lockfile.",
@@ -158,27 +390,156 @@ impl ApplyArgs {
             );
         }
 
+        if let Some(path) = &self.report_timings {
+            report.packages = maintainer.package_count();
+            report.record_cache_stats(&nassun);
+            report.total_ms = total_time.elapsed().as_millis() as u64;
+            async_std::fs::write(
+                path,
+                serde_json::to_string_pretty(&report)
+                    .into_diagnostic()
+                    .wrap_err("apply::report_timings::serialize")?,
+            )
+            .await
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to write timing report to {}", path.display()))?;
+        }
+
         tracing::info!(
-            "{}Applied node_modules/ in {}s. {}",
+            "{}Applied node_modules/ in {}. {}",
             self.emoji_tada(),
-            total_time.elapsed().as_millis() as f32 / 1000.0,
+            format_duration(total_time.elapsed()),
             hackerish_encouragement()
         );
         Ok(())
     }
 
-    fn configured_maintainer(&self) -> Result<NodeMaintainerOptions> {
-        let root = &self.root;
+    async fn check_package_manager(&self) -> Result<()> {
+        if self.package_manager_check == PackageManagerCheck::Off {
+            return Ok(());
+        }
+        let manifest_path = self.root.join("package.json");
+        if !manifest_path.exists() {
+            return Ok(());
+        }
+        let manifest: Manifest = serde_json::from_str(
+            &async_std::fs::read_to_string(&manifest_path)
+                .await
+                .into_diagnostic()?,
+        )
+        .into_diagnostic()?;
+        let Some(wanted) = manifest
+            .package_manager
+            .as_deref()
+            .and_then(|pm| pm.strip_prefix("oro@"))
+        else {
+            return Ok(());
+        };
+        // Corepack-style fields can pin a hash after the version, like
+        // `oro@1.2.3+sha256.abc123`.
+        let wanted = wanted.split('+').next().unwrap_or(wanted);
+        let Ok(range) = wanted.parse::<Range>() else {
+            tracing::warn!(
+                "Ignoring `packageManager` field in package.json: {wanted} is not a valid version range."
+            );
+            return Ok(());
+        };
+        let current: Version = env!("CARGO_PKG_VERSION")
+            .parse()
+            .expect("oro's own version is always valid");
+        if !range.satisfies(&current) {
+            let err = OroError::PackageManagerMismatch(wanted.into(), current.to_string());
+            if self.package_manager_check == PackageManagerCheck::Error {
+                return Err(err.into());
+            }
+            tracing::warn!("{err}");
+        }
+        Ok(())
+    }
+
+    /// Resolves the tree the same way [`Self::execute`] would, and reports
+    /// any differences from what's currently installed in `node_modules/`,
+    /// without changing anything.
+    pub async fn check(&self, manifest: CorgiManifest) -> Result<()> {
+        let result = self.check_tree(manifest).await;
+        self.report_warnings();
+        result
+    }
+
+    async fn check_tree(&self, manifest: CorgiManifest) -> Result<()> {
         let nassun = NassunArgs::from_apply_args(self).to_nassun()?;
+        let maintainer = self
+            .resolve(manifest, self.configured_maintainer(nassun))
+            .await?;
+        let (linked, drift): (Vec<_>, Vec<_>) = maintainer
+            .drift()?
+            .into_iter()
+            .partition(|entry| entry.is_linked());
+        for entry in &linked {
+            tracing::info!("{entry}");
+        }
+        if drift.is_empty() {
+            tracing::info!("{}node_modules/ is up to date.", self.emoji_tada());
+            return Ok(());
+        }
+        for entry in &drift {
+            tracing::warn!("{entry}");
+        }
+        Err(OroError::NodeModulesOutOfDate(drift.len()).into())
+    }
+
+    /// Prints every warning collected during the command in one place, with
+    /// duplicates already weeded out. Nothing is printed if there weren't
+    /// any, whatever the output format.
+    fn report_warnings(&self) {
+        let warnings = self.warnings.warnings();
+        if warnings.is_empty() {
+            return;
+        }
+        match self.output.format() {
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({ "warnings": warnings }))
+                    .expect("warnings always serialize")
+            ),
+            OutputFormat::Parseable => {
+                for warning in &warnings {
+                    println!("{}\t{}", warning.kind, warning.message);
+                }
+            }
+            OutputFormat::Human => {
+                tracing::warn!(
+                    "{} warning{}:",
+                    warnings.len(),
+                    if warnings.len() == 1 { "" } else { "s" }
+                );
+                for warning in &warnings {
+                    tracing::warn!("  {warning}");
+                }
+            }
+        }
+    }
+
+    fn configured_maintainer(&self, nassun: Nassun) -> NodeMaintainerOptions {
+        let root = &self.root;
         let mut nm = NodeMaintainerOptions::new();
         nm = nm
             .nassun(nassun)
+            .warnings(self.warnings.clone())
             .locked(self.locked)
+            .fix_lockfile(self.fix_lockfile)
+            .offline_first(self.offline_first)
+            .auto_install_peers(self.auto_install_peers)
+            .dedupe_peer_deps(self.dedupe_peer_deps)
+            .legacy_peer_deps(self.legacy_peer_deps)
+            .allow_peer_conflicts(self.allow_peer_conflicts)
             .concurrency(self.concurrency)
             .script_concurrency(self.script_concurrency)
             .root(root)
             .prefer_copy(self.prefer_copy)
             .hoisted(self.hoisted)
+            .bin_links(self.bin_links)
+            .trim_install(self.trim)
             .on_resolution_added(move || {
                 Span::current().pb_inc_length(1);
             })
@@ -218,7 +579,37 @@ impl ApplyArgs {
             nm = nm.cache(cache);
         }
 
-        Ok(nm)
+        if let Some(store_dir) = self.store_dir.as_deref() {
+            nm = nm.store_dir(store_dir);
+        }
+
+        if let Some(os) = &self.target_os {
+            nm = nm.target_os(os);
+        }
+        if let Some(cpu) = &self.target_cpu {
+            nm = nm.target_cpu(cpu);
+        }
+        if let Some(libc) = &self.target_libc {
+            nm = nm.target_libc(libc);
+        }
+
+        if let Some(names) = &self.update {
+            nm = nm.update(names);
+        }
+
+        if !self.trim_pattern.is_empty() {
+            nm = nm.trim_patterns(&self.trim_pattern);
+        }
+
+        if let Some(path) = &self.debug_resolver {
+            nm = nm.debug_resolver(path);
+        }
+
+        for (package, key, value) in &self.install_env {
+            nm = nm.script_env(package, key, value);
+        }
+
+        nm
     }
 
     async fn resolve(
@@ -226,6 +617,28 @@ impl ApplyArgs {
         root_manifest: CorgiManifest,
         builder: NodeMaintainerOptions,
     ) -> Result<NodeMaintainer> {
+        match self
+            .resolve_with_progress(root_manifest.clone(), builder.clone())
+            .await
+        {
+            // Offer to start over when the lockfile is broken, but only once
+            // the progress bar is out of the way.
+            Err(NodeMaintainerError::CorruptLockfile(path, err))
+                if self.confirm_fix_lockfile(&path, &err)? =>
+            {
+                Ok(self
+                    .resolve_with_progress(root_manifest, builder.fix_lockfile(true))
+                    .await?)
+            }
+            res => Ok(res?),
+        }
+    }
+
+    async fn resolve_with_progress(
+        &self,
+        root_manifest: CorgiManifest,
+        builder: NodeMaintainerOptions,
+    ) -> Result<NodeMaintainer, NodeMaintainerError> {
         // Set up progress bar and timing stuff.
         let resolve_time = std::time::Instant::now();
         let resolve_span = tracing::debug_span!("resolving");
@@ -248,15 +661,74 @@ impl ApplyArgs {
         std::mem::drop(resolve_span_enter);
         std::mem::drop(resolve_span);
         tracing::info!(
-            "{}Resolved {} packages in {}s.",
+            "{}Resolved {} packages in {}.",
             self.emoji_magnifying_glass(),
             resolved_nm.package_count(),
-            resolve_time.elapsed().as_millis() as f32 / 1000.0
+            format_duration(resolve_time.elapsed())
         );
 
         Ok(resolved_nm)
     }
 
+    /// Asks whether to discard a lockfile that couldn't be parsed. Only ever
+    /// says yes when there's someone at the terminal to answer.
+    fn confirm_fix_lockfile(&self, path: &Path, err: &NodeMaintainerError) -> Result<bool> {
+        if !(std::io::stdin().is_terminal() && std::io::stderr().is_terminal()) {
+            return Ok(false);
+        }
+        tracing::warn!("Failed to parse {}: {err}", path.display());
+        Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Discard it and re-resolve from package.json?")
+            .default(false)
+            .interact()
+            .into_diagnostic()
+    }
+
+    /// Looks up security advisories for every package in the resolved tree,
+    /// logging and failing on any at or above `--audit-level`.
+    async fn audit(&self, maintainer: &NodeMaintainer) -> Result<()> {
+        let mut packages = BTreeMap::<String, Vec<String>>::new();
+        for node in maintainer.to_lockfile()?.packages().values() {
+            if let Some(version) = &node.version {
+                let versions = packages.entry(node.name.to_string()).or_default();
+                let version = version.to_string();
+                if !versions.contains(&version) {
+                    versions.push(version);
+                }
+            }
+        }
+        if packages.is_empty() {
+            return Ok(());
+        }
+
+        let client_args: ClientArgs = NassunArgs::from_apply_args(self).into();
+        let client = OroClientBuilder::try_from(client_args)?
+            .registry(self.registry.clone())
+            .build();
+        let threshold = Severity::from(self.audit_level);
+        let mut found = 0;
+        for (name, advisories) in client.bulk_advisories(&packages).await? {
+            for advisory in advisories.iter().filter(|a| a.severity >= threshold) {
+                found += 1;
+                tracing::warn!(
+                    "{name} {}: {} ({} severity) {}",
+                    advisory.vulnerable_versions,
+                    advisory.title,
+                    advisory.severity,
+                    advisory.url
+                );
+            }
+        }
+        if found > 0 {
+            return Err(OroError::AuditFailed(found, threshold.to_string()).into());
+        }
+        tracing::info!(
+            "{}No advisories at or above {threshold} severity.",
+            self.emoji_magnifying_glass()
+        );
+        Ok(())
+    }
+
     async fn prune(&self, maintainer: &NodeMaintainer) -> Result<usize> {
         // Set up progress bar and timing stuff.
         let prune_time = std::time::Instant::now();
@@ -280,9 +752,9 @@ impl ApplyArgs {
         std::mem::drop(prune_span_enter);
         std::mem::drop(prune_span);
         tracing::info!(
-            "{}Pruned {pruned} packages in {}s.",
+            "{}Pruned {pruned} packages in {}.",
             self.emoji_broom(),
-            prune_time.elapsed().as_millis() as f32 / 1000.0
+            format_duration(prune_time.elapsed())
         );
 
         Ok(pruned)
@@ -311,10 +783,10 @@ impl ApplyArgs {
         std::mem::drop(extract_span_enter);
         std::mem::drop(extract_span);
         tracing::info!(
-            "{}Extracted {extracted} package{} in {}s.",
+            "{}Extracted {extracted} package{} in {}.",
             self.emoji_package(),
             if extracted == 1 { "" } else { "s" },
-            extract_time.elapsed().as_millis() as f32 / 1000.0
+            format_duration(extract_time.elapsed())
         );
 
         Ok(extracted)
@@ -343,15 +815,15 @@ impl ApplyArgs {
             .await?;
         if self.scripts {
             tracing::info!(
-                "{}Ran lifecycle scripts in {}s.",
+                "{}Ran lifecycle scripts in {}.",
                 self.emoji_run(),
-                script_time.elapsed().as_millis() as f32 / 1000.0
+                format_duration(script_time.elapsed())
             );
         } else {
             tracing::info!(
-                "{}Linked script bins in {}s.",
+                "{}Linked script bins in {}.",
                 self.emoji_link(),
-                script_time.elapsed().as_millis() as f32 / 1000.0
+                format_duration(script_time.elapsed())
             );
         }
         Ok(())
@@ -394,6 +866,51 @@ impl ApplyArgs {
     }
 }
 
+/// Report written by `--report-timings`.
+#[derive(Debug, Default, Serialize)]
+struct TimingReport {
+    total_ms: u64,
+    packages: usize,
+    phases: BTreeMap<&'static str, PhaseTiming>,
+    cache_hits: usize,
+    cache_misses: usize,
+    cache_hit_ratio: f64,
+    bytes_downloaded: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct PhaseTiming {
+    duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    packages: Option<usize>,
+}
+
+impl TimingReport {
+    fn phase(&mut self, name: &'static str, start: Instant, packages: Option<usize>) {
+        self.phases.insert(
+            name,
+            PhaseTiming {
+                duration_ms: start.elapsed().as_millis() as u64,
+                packages,
+            },
+        );
+    }
+
+    /// Hits and misses are counted by nassun wherever it looks in the
+    /// packument or tarball cache, so packages that never touch the cache
+    /// (like `file:` and directory dependencies) aren't counted at all.
+    fn record_cache_stats(&mut self, nassun: &Nassun) {
+        let stats = nassun.fetch_stats();
+        self.cache_hits = stats.packument_cache_hits + stats.tarball_cache_hits;
+        self.cache_misses = stats.packument_cache_misses + stats.tarball_cache_misses;
+        self.bytes_downloaded = stats.bytes_downloaded;
+        let lookups = self.cache_hits + self.cache_misses;
+        if lookups > 0 {
+            self.cache_hit_ratio = self.cache_hits as f64 / lookups as f64;
+        }
+    }
+}
+
 // Inspired and brazenly taken from SLIME:
 // https://github.com/slime/slime/blob/e193bc5f3431a2f71f1d7a0e3f28e6dc4dd5de2d/slime.el#L1360-L1375
 fn hackerish_encouragement() -> &'static str {
@@ -11,6 +11,9 @@ pub struct ClientArgs {
     #[arg(from_global)]
     pub cache: Option<PathBuf>,
 
+    #[arg(from_global)]
+    pub metadata_cache_dir: Option<PathBuf>,
+
     #[arg(from_global)]
     pub proxy: bool,
 
@@ -20,6 +23,12 @@ pub struct ClientArgs {
     #[arg(from_global)]
     pub no_proxy_domain: Option<String>,
 
+    #[arg(from_global)]
+    pub ca_bundle: Option<PathBuf>,
+
+    #[arg(from_global)]
+    pub danger_accept_invalid_certs: bool,
+
     #[arg(from_global)]
     pub retries: u32,
 
@@ -31,9 +40,12 @@ impl From<ApplyArgs> for ClientArgs {
     fn from(value: ApplyArgs) -> Self {
         Self {
             cache: value.cache,
+            metadata_cache_dir: value.metadata_cache_dir,
             proxy: value.proxy,
             proxy_url: value.proxy_url,
             no_proxy_domain: value.no_proxy_domain,
+            ca_bundle: value.ca_bundle,
+            danger_accept_invalid_certs: value.danger_accept_invalid_certs,
             retries: value.retries,
             auth: value.auth,
         }
@@ -44,9 +56,12 @@ impl From<NassunArgs> for ClientArgs {
     fn from(value: NassunArgs) -> Self {
         Self {
             cache: value.cache,
+            metadata_cache_dir: value.metadata_cache_dir,
             proxy: value.proxy,
             proxy_url: value.proxy_url,
             no_proxy_domain: value.no_proxy_domain,
+            ca_bundle: value.ca_bundle,
+            danger_accept_invalid_certs: value.danger_accept_invalid_certs,
             retries: value.retries,
             auth: value.auth,
         }
@@ -58,8 +73,9 @@ impl TryFrom<ClientArgs> for OroClientBuilder {
     fn try_from(value: ClientArgs) -> Result<Self, Self::Error> {
         let mut builder = OroClientBuilder::new()
             .retries(value.retries)
-            .proxy(value.proxy);
-        if let Some(cache) = value.cache {
+            .proxy(value.proxy)
+            .danger_accept_invalid_certs(value.danger_accept_invalid_certs);
+        if let Some(cache) = value.metadata_cache_dir.or(value.cache) {
             builder = builder.cache(cache);
         }
         if let Some(domain) = value.no_proxy_domain {
@@ -68,6 +84,9 @@ impl TryFrom<ClientArgs> for OroClientBuilder {
         if let Some(url) = value.proxy_url {
             builder = builder.proxy_url(url)?;
         }
+        if let Some(ca_bundle) = value.ca_bundle {
+            builder = builder.ca_bundle(ca_bundle)?;
+        }
         for (reg, key, val) in &value.auth {
             let url = Url::parse(reg)?;
             if key == "token" {
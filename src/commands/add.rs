@@ -1,13 +1,14 @@
 use async_trait::async_trait;
 use clap::Args;
 use miette::{IntoDiagnostic, Result};
-use nassun::PackageResolution;
+use nassun::{Nassun, PackageResolution};
 use oro_common::CorgiManifest;
 use oro_package_spec::{PackageSpec, VersionSpec};
 use oro_pretty_json::Formatted;
 
 use crate::apply_args::ApplyArgs;
 use crate::commands::OroCommand;
+use crate::error::OroError;
 use crate::nassun_args::NassunArgs;
 
 /// Adds one or more dependencies to the target package.
@@ -31,6 +32,25 @@ pub struct AddCmd {
     #[arg(long, short = 'O', visible_alias = "optional")]
     opt: bool,
 
+    /// Install packages without adding them to package.json.
+    ///
+    /// The packages will still be written to the lockfile, but will be
+    /// pruned by the next apply unless they're added to package.json.
+    #[arg(long = "no-save", action = clap::ArgAction::SetFalse)]
+    save: bool,
+
+    /// Suggest matching `@types/*` packages for added packages that don't
+    /// ship their own TypeScript types.
+    ///
+    /// Defaults to on when running in a terminal.
+    #[arg(long)]
+    suggest_types: bool,
+
+    /// Add packages even if they would introduce peer dependency
+    /// conflicts.
+    #[arg(long)]
+    force: bool,
+
     #[command(flatten)]
     apply: ApplyArgs,
 }
@@ -48,14 +68,28 @@ impl OroCommand for AddCmd {
         use PackageResolution as Pr;
         use PackageSpec as Ps;
         let mut count = 0;
+        let mut typeless = Vec::new();
+        let mut added = Vec::new();
         for spec in &self.specs {
+            let requested: PackageSpec = spec.parse()?;
+            if let Ps::Alias { name, .. } | Ps::Npm { name, .. } = &requested {
+                match PackageSpec::normalize_name(name) {
+                    Ok(_) => {}
+                    // Plenty of older packages predate these rules, and
+                    // can still be installed.
+                    Err(e) if e.is_legacy_name_error() => tracing::debug!("{e}"),
+                    Err(e) => return Err(e.into()),
+                }
+            }
             let pkg = nassun.resolve(spec).await?;
             let name = pkg.name();
-            let requested: PackageSpec = spec.parse()?;
             let resolved_spec = match requested.target() {
                 Ps::Alias { .. } => {
                     unreachable!(".target() ensures this alias is fully resolved");
                 }
+                Ps::Catalog { .. } => {
+                    unreachable!("Catalog specs can't be resolved on their own.");
+                }
                 Ps::Git(info) => {
                     format!("{info}")
                 }
@@ -66,6 +100,13 @@ impl OroCommand for AddCmd {
                     }
                 }
                 Ps::Npm { .. } => {
+                    if self.suggest_types && !name.starts_with("@types/") {
+                        let metadata = pkg.metadata().await?;
+                        let rest = &metadata.manifest._rest;
+                        if !rest.contains_key("types") && !rest.contains_key("typings") {
+                            typeless.push(name.to_string());
+                        }
+                    }
                     let mut from = pkg.from().clone();
                     let resolved = pkg.resolved();
                     let version = if let Pr::Npm { version, .. } = resolved {
@@ -90,6 +131,7 @@ impl OroCommand for AddCmd {
                 "{}Resolved {spec} to {name}@{resolved_spec}.",
                 if self.apply.emoji { "🔍 " } else { "" }
             );
+            added.push(name.to_string());
             self.remove_from_manifest(&mut manifest, name);
             self.add_to_manifest(&mut manifest, name, &resolved_spec);
             count += 1;
@@ -106,8 +148,55 @@ impl OroCommand for AddCmd {
             serde_json::from_str(&oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?)
                 .into_diagnostic()?;
 
-        // Then, we apply the change.
-        self.apply.execute(corgi).await?;
+        // `--force` means we don't care about peer conflicts anywhere in the
+        // tree, not just the ones we'd be adding. Otherwise, conflicts that
+        // were already in the project are left for the check below to skip,
+        // rather than failing resolution outright.
+        if self.force {
+            self.apply.legacy_peer_deps = true;
+        } else {
+            self.apply.allow_peer_conflicts = true;
+        }
+
+        // Then, we apply the change, as long as it doesn't break anyone's
+        // peer dependencies.
+        self.apply
+            .execute_checked(corgi, |maintainer| {
+                if self.force {
+                    return Ok(());
+                }
+                let conflicts = maintainer
+                    .peer_conflicts()?
+                    .into_iter()
+                    .filter(|conflict| conflict.found.is_some())
+                    .filter(|conflict| {
+                        added.contains(&conflict.dependent)
+                            || spec_name(&conflict.requested)
+                                .map(|name| added.iter().any(|added| added == name))
+                                .unwrap_or(false)
+                    })
+                    .map(|conflict| format!("  {conflict}"))
+                    .collect::<Vec<_>>();
+                if conflicts.is_empty() {
+                    Ok(())
+                } else {
+                    Err(OroError::AddPeerConflicts(conflicts.join("\n")).into())
+                }
+            })
+            .await?;
+
+        for name in typeless {
+            self.suggest_types_package(&nassun, &manifest, &name).await;
+        }
+
+        if !self.save {
+            tracing::info!(
+                "{}Installed {count} {} without saving to package.json.",
+                if self.apply.emoji { "📦 " } else { "" },
+                if count == 1 { "package" } else { "packages" }
+            );
+            return Ok(());
+        }
 
         async_std::fs::write(
             self.apply.root.join("package.json"),
@@ -131,6 +220,26 @@ impl OroCommand for AddCmd {
 }
 
 impl AddCmd {
+    /// Adds `specs` to `dependencies`, for commands that accept packages to
+    /// add as a shorthand.
+    pub(crate) fn new(
+        specs: Vec<String>,
+        prefix: String,
+        suggest_types: bool,
+        apply: ApplyArgs,
+    ) -> Self {
+        Self {
+            specs,
+            prefix,
+            dev: false,
+            opt: false,
+            save: true,
+            suggest_types,
+            force: false,
+            apply,
+        }
+    }
+
     fn add_to_manifest(&self, mani: &mut Formatted, name: &str, spec: &str) {
         let deps = self.dep_kind_str();
         tracing::debug!("Adding {name}@{spec} to {deps}.");
@@ -159,6 +268,40 @@ impl AddCmd {
         }
     }
 
+    /// Lets the user know if there's an `@types/*` package available for
+    /// `name`, unless it's already a dependency.
+    async fn suggest_types_package(&self, nassun: &Nassun, mani: &Formatted, name: &str) {
+        let types_name = if let Some((scope, pkg)) =
+            name.strip_prefix('@').and_then(|name| name.split_once('/'))
+        {
+            format!("@types/{scope}__{pkg}")
+        } else {
+            format!("@types/{name}")
+        };
+        let already_added = [
+            "dependencies",
+            "devDependencies",
+            "optionalDependencies",
+            "peerDependencies",
+        ]
+        .iter()
+        .any(|ty| mani.value[ty].get(&types_name).is_some());
+        if already_added {
+            return;
+        }
+        match nassun.resolve(&types_name).await {
+            Ok(_) => {
+                tracing::info!(
+                    "{}{name} doesn't ship with TypeScript types, but {types_name} is available. Add it with `oro add -D {types_name}`.",
+                    if self.apply.emoji { "💡 " } else { "" }
+                );
+            }
+            Err(e) => {
+                tracing::debug!("No types package found for {name}: {e}");
+            }
+        }
+    }
+
     fn dep_kind_str(&self) -> &'static str {
         if self.dev {
             "devDependencies"
@@ -179,3 +322,11 @@ impl AddCmd {
         }
     }
 }
+
+/// Name of the package a peer dependency request refers to.
+fn spec_name(spec: &PackageSpec) -> Option<&str> {
+    match spec {
+        PackageSpec::Alias { name, .. } | PackageSpec::Npm { name, .. } => Some(name),
+        _ => None,
+    }
+}
@@ -4,6 +4,7 @@ use miette::{IntoDiagnostic, Result};
 use oro_common::CorgiManifest;
 
 use crate::apply_args::ApplyArgs;
+use crate::commands::add::AddCmd;
 use crate::commands::OroCommand;
 
 /// Applies the current project's requested dependencies to `node_modules/`,
@@ -14,9 +15,45 @@ use crate::commands::OroCommand;
 /// This command is automatically executed by a number of Orogene subcommands.
 /// To force a full reapplication of `node_modules`, consider using the `oro
 /// reapply` command.
+///
+/// Also available as `oro install` and `oro i`. Like with npm, passing
+/// packages to it adds them to `dependencies` first, the same as `oro add`.
 #[derive(Debug, Args)]
-#[clap(visible_aliases(["a", "ap", "app"]))]
+#[clap(visible_aliases(["a", "ap", "app", "install", "i"]))]
 pub struct ApplyCmd {
+    /// Packages to add to `dependencies` before applying, like `oro add`.
+    #[arg(conflicts_with = "check")]
+    specs: Vec<String>,
+
+    /// Check whether `node_modules/` is up to date without changing
+    /// anything, exiting with an error that lists any differences if it
+    /// isn't.
+    ///
+    /// Combine with `--locked` to only resolve from the lockfile.
+    #[arg(long)]
+    check: bool,
+
+    /// Ignore the lockfile and any existing `node_modules/` when picking
+    /// versions, and resolve everything from package.json from scratch.
+    ///
+    /// The lockfile is overwritten with the new resolution. Packages that
+    /// are already cached are still reused.
+    #[arg(long, conflicts_with_all = ["check", "specs"])]
+    force: bool,
+
+    /// Prefix to prepend to package versions for resolved NPM dependencies
+    /// passed as `specs`, like `oro add --prefix`.
+    #[arg(long, default_value = "^")]
+    prefix: String,
+
+    /// Suggest matching `@types/*` packages for packages passed as `specs`
+    /// that don't ship their own TypeScript types, like `oro add
+    /// --suggest-types`.
+    ///
+    /// Defaults to on when running in a terminal.
+    #[arg(long)]
+    suggest_types: bool,
+
     #[command(flatten)]
     apply: ApplyArgs,
 }
@@ -24,6 +61,11 @@ pub struct ApplyCmd {
 #[async_trait]
 impl OroCommand for ApplyCmd {
     async fn execute(mut self) -> Result<()> {
+        if !self.specs.is_empty() {
+            return AddCmd::new(self.specs, self.prefix, self.suggest_types, self.apply)
+                .execute()
+                .await;
+        }
         let corgi: CorgiManifest = serde_json::from_str(
             &async_std::fs::read_to_string(self.apply.root.join("package.json"))
                 .await
@@ -34,6 +76,18 @@ impl OroCommand for ApplyCmd {
         // here so that people can have `apply false` in their configurations
         // but have `oro apply` still work.
         self.apply.apply = true;
-        self.apply.execute(corgi).await
+        if self.force {
+            if self.apply.locked {
+                tracing::info!("Ignoring --locked option, since --force re-resolves everything.");
+                self.apply.locked = false;
+            }
+            // An empty update list re-resolves every package.
+            self.apply.update = Some(Vec::new());
+        }
+        if self.check {
+            self.apply.check(corgi).await
+        } else {
+            self.apply.execute(corgi).await
+        }
     }
 }
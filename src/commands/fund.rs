@@ -0,0 +1,119 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use async_trait::async_trait;
+use clap::Args;
+use colored::*;
+use futures::{StreamExt, TryStreamExt};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use node_maintainer::NodeMaintainer;
+use oro_common::CorgiManifest;
+
+use crate::commands::OroCommand;
+use crate::nassun_args::NassunArgs;
+use crate::output::{OutputArgs, OutputFormat};
+
+/// List funding information for the project's dependencies.
+///
+/// Walks the resolved dependency tree and prints the URLs packages have
+/// declared in their `funding` field, grouped by URL.
+#[derive(Debug, Args)]
+pub struct FundCmd {
+    #[command(flatten)]
+    output: OutputArgs,
+
+    #[command(flatten)]
+    nassun_args: NassunArgs,
+}
+
+#[async_trait]
+impl OroCommand for FundCmd {
+    async fn execute(self) -> Result<()> {
+        let root = &self.nassun_args.root;
+        let corgi: CorgiManifest = serde_json::from_str(
+            &async_std::fs::read_to_string(root.join("package.json"))
+                .await
+                .into_diagnostic()?,
+        )
+        .into_diagnostic()?;
+        let nm = NodeMaintainer::builder()
+            .nassun(self.nassun_args.to_nassun()?)
+            .root(root)
+            .resolve_manifest(corgi)
+            .await?;
+        let lockfile = nm.to_lockfile()?;
+
+        // Multiple paths in the tree can point to the same name@version, so
+        // we deduplicate them before fetching any metadata.
+        let mut seen = BTreeSet::new();
+        let packages = lockfile
+            .packages()
+            .iter()
+            .filter(|(_, node)| {
+                seen.insert((
+                    node.name.to_string(),
+                    node.version.as_ref().map(|v| v.to_string()),
+                ))
+            })
+            .filter_map(|(path, _)| nm.package_at_path(Path::new(path.as_str())))
+            .collect::<Vec<_>>();
+
+        let mut funding: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let metadata = futures::stream::iter(packages)
+            .map(|pkg| async move { pkg.metadata().await })
+            .buffer_unordered(node_maintainer::DEFAULT_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+        for meta in metadata {
+            if let Some(fund) = &meta.manifest.funding {
+                let id = format!(
+                    "{}@{}",
+                    meta.manifest.name.as_deref().unwrap_or(""),
+                    meta.manifest
+                        .version
+                        .as_ref()
+                        .map(|v| v.to_string())
+                        .unwrap_or_else(|| "0.0.0".into())
+                );
+                for url in fund.urls() {
+                    funding
+                        .entry(url.to_string())
+                        .or_default()
+                        .insert(id.clone());
+                }
+            }
+        }
+
+        match self.output.format() {
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&funding)
+                    .into_diagnostic()
+                    .wrap_err("fund::json_serialize")?
+            ),
+            OutputFormat::Parseable => {
+                for (url, pkgs) in &funding {
+                    for pkg in pkgs {
+                        println!("{url}\t{pkg}");
+                    }
+                }
+            }
+            OutputFormat::Human if funding.is_empty() => {
+                println!("No dependencies declare funding information.");
+            }
+            OutputFormat::Human => {
+                for (url, pkgs) in &funding {
+                    println!("{}", url.cyan());
+                    println!(
+                        "  {}",
+                        pkgs.iter()
+                            .map(|p| p.yellow().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -3,11 +3,16 @@ use miette::Result;
 
 pub mod add;
 pub mod apply;
+pub mod fund;
 pub mod login;
 pub mod logout;
 pub mod ping;
 pub mod reapply;
 pub mod remove;
+pub mod schema;
+pub mod store;
+pub mod telemetry;
+pub mod update;
 pub mod view;
 
 #[async_trait]
@@ -7,7 +7,12 @@ use oro_client::{self, OroClientBuilder};
 use serde_json::Value;
 use url::Url;
 
-use crate::{client_args::ClientArgs, commands::OroCommand};
+use crate::{
+    client_args::ClientArgs,
+    commands::OroCommand,
+    format::format_duration,
+    output::{OutputArgs, OutputFormat},
+};
 
 /// Ping the registry.
 #[derive(Debug, Args)]
@@ -15,8 +20,8 @@ pub struct PingCmd {
     #[arg(from_global)]
     registry: Url,
 
-    #[arg(from_global)]
-    json: bool,
+    #[command(flatten)]
+    output: OutputArgs,
 
     #[arg(from_global)]
     emoji: bool,
@@ -34,22 +39,31 @@ impl OroCommand for PingCmd {
         let client_builder: OroClientBuilder = self.client_args.try_into()?;
         let client = client_builder.registry(registry.clone()).build();
         let payload = client.ping().await?;
-        let time = start.elapsed().as_micros() as f32 / 1000.0;
-        tracing::info!("{}pong: {time}ms", if self.emoji { "⬅️ " } else { "" });
-        if self.json {
-            let details: Value = serde_json::from_str(&payload)
+        let elapsed = start.elapsed();
+        let time = elapsed.as_micros() as f32 / 1000.0;
+        tracing::info!(
+            "{}pong: {}",
+            if self.emoji { "⬅️ " } else { "" },
+            format_duration(elapsed)
+        );
+        match self.output.format() {
+            OutputFormat::Json => {
+                let details: Value = serde_json::from_str(&payload)
+                    .into_diagnostic()
+                    .wrap_err("ping::deserialize")?;
+                let output = serde_json::to_string_pretty(&serde_json::json!({
+                    "registry": registry.to_string(),
+                    "time": time,
+                    "details": details,
+                }))
                 .into_diagnostic()
-                .wrap_err("ping::deserialize")?;
-            let output = serde_json::to_string_pretty(&serde_json::json!({
-                "registry": registry.to_string(),
-                "time": time,
-                "details": details,
-            }))
-            .into_diagnostic()
-            .wrap_err("ping::serialize")?;
-            println!("{output}");
-        } else {
-            tracing::info!("{}payload: {payload}", if self.emoji { "📦 " } else { "" });
+                .wrap_err("ping::serialize")?;
+                println!("{output}");
+            }
+            OutputFormat::Parseable => println!("{registry}\t{time}"),
+            OutputFormat::Human => {
+                tracing::info!("{}payload: {payload}", if self.emoji { "📦 " } else { "" });
+            }
         }
         Ok(())
     }
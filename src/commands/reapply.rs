@@ -5,6 +5,7 @@ use oro_common::CorgiManifest;
 
 use crate::apply_args::ApplyArgs;
 use crate::commands::OroCommand;
+use crate::format::format_duration;
 
 /// Removes the existing `node_modules`, if any, and reapplies it from
 /// scratch. You can use this to make sure you have a pristine `node_modules`.
@@ -30,9 +31,9 @@ impl OroCommand for ReapplyCmd {
             std::fs::remove_dir_all(nm).into_diagnostic()?;
 
             tracing::info!(
-                "{}node_modules/ cleared in {}s.",
+                "{}node_modules/ cleared in {}.",
                 if self.apply.emoji { "🚮 " } else { "" },
-                total_time.elapsed().as_millis() as f32 / 1000.0,
+                format_duration(total_time.elapsed()),
             );
         } else {
             tracing::info!(
@@ -55,9 +56,9 @@ impl OroCommand for ReapplyCmd {
         self.apply.execute(corgi).await?;
 
         tracing::info!(
-            "{}Reapply done in {}s.",
+            "{}Reapply done in {}.",
             if self.apply.emoji { "✨ " } else { "" },
-            total_time.elapsed().as_millis() as f32 / 1000.0,
+            format_duration(total_time.elapsed()),
         );
         Ok(())
     }
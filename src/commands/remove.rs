@@ -13,11 +13,19 @@ use crate::OroError;
 #[derive(Debug, Args)]
 #[clap(visible_aliases(["rm"]))]
 pub struct RemoveCmd {
-    /// Package names of dependencies to remove. These will be removed from
-    /// all dependency types.
+    /// Package names of dependencies to remove. By default, these will be
+    /// removed from every dependency type they appear in.
     #[arg(required = true)]
     names: Vec<String>,
 
+    /// Only remove packages from devDependencies.
+    #[arg(long, short = 'D')]
+    save_dev: bool,
+
+    /// Only remove packages from optionalDependencies.
+    #[arg(long, short = 'O', conflicts_with = "save_dev")]
+    save_optional: bool,
+
     #[command(flatten)]
     apply: ApplyArgs,
 }
@@ -40,7 +48,15 @@ impl OroCommand for RemoveCmd {
                 if &spec_name != name {
                     tracing::warn!("Ignoring version specifier in `{name}`. Arguments to `oro remove` should only be package names. Proceeding with `{spec_name}` instead.");
                 }
-                count += self.remove_from_manifest(&mut manifest, &spec_name);
+                let removed = self.remove_from_manifest(&mut manifest, &spec_name);
+                if removed == 0 {
+                    return Err(OroError::DependencyNotFound(
+                        spec_name,
+                        self.dep_kinds_str().into(),
+                    )
+                    .into());
+                }
+                count += removed;
             } else {
                 return Err(OroError::InvalidPackageName(name.clone()).into());
             }
@@ -80,12 +96,7 @@ impl OroCommand for RemoveCmd {
 impl RemoveCmd {
     fn remove_from_manifest(&self, mani: &mut Formatted, name: &str) -> usize {
         let mut count = 0;
-        for ty in [
-            "dependencies",
-            "devDependencies",
-            "optionalDependencies",
-            "peerDependencies",
-        ] {
+        for &ty in self.dep_kinds() {
             if mani.value[ty].is_object() {
                 if let Some(obj) = mani.value[ty].as_object_mut() {
                     if obj.contains_key(name) {
@@ -95,10 +106,42 @@ impl RemoveCmd {
                         );
                         obj.remove(name);
                         count += 1;
+                        if obj.is_empty() {
+                            // Don't leave an empty `{}` section behind.
+                            tracing::debug!("Removing now-empty {ty}.");
+                            if let Some(mani_obj) = mani.value.as_object_mut() {
+                                mani_obj.remove(ty);
+                            }
+                        }
                     }
                 }
             }
         }
         count
     }
+
+    fn dep_kinds(&self) -> &'static [&'static str] {
+        if self.save_dev {
+            &["devDependencies"]
+        } else if self.save_optional {
+            &["optionalDependencies"]
+        } else {
+            &[
+                "dependencies",
+                "devDependencies",
+                "optionalDependencies",
+                "peerDependencies",
+            ]
+        }
+    }
+
+    fn dep_kinds_str(&self) -> &'static str {
+        if self.save_dev {
+            "devDependencies"
+        } else if self.save_optional {
+            "optionalDependencies"
+        } else {
+            "any dependency type"
+        }
+    }
 }
@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use clap::{clap_derive::ValueEnum, Args, CommandFactory};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use node_maintainer::Lockfile;
+
+use crate::commands::OroCommand;
+use crate::Orogene;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SchemaFormat {
+    Lockfile,
+    Config,
+}
+
+/// Print a JSON Schema for one of Orogene's file formats.
+///
+/// `lockfile` describes `package-lock.kdl`, and `config` describes
+/// `oro.kdl`. KDL nodes are represented as JSON properties named after the
+/// node.
+#[derive(Debug, Args)]
+pub struct SchemaCmd {
+    /// File format to describe.
+    #[arg(value_enum)]
+    format: SchemaFormat,
+}
+
+#[async_trait]
+impl OroCommand for SchemaCmd {
+    async fn execute(self) -> Result<()> {
+        let schema = match self.format {
+            SchemaFormat::Lockfile => Lockfile::json_schema(),
+            SchemaFormat::Config => oro_config::config_schema(&Orogene::command()),
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&schema)
+                .into_diagnostic()
+                .wrap_err("schema::serialize")?
+        );
+        Ok(())
+    }
+}
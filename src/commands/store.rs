@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use nassun::StoreStatus;
+
+use crate::commands::OroCommand;
+use crate::format::format_bytes;
+use crate::output::{OutputArgs, OutputFormat};
+
+/// Inspect Orogene's package store (its content cache).
+#[derive(Debug, Args)]
+pub struct StoreCmd {
+    #[command(subcommand)]
+    subcommand: StoreSubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum StoreSubCmd {
+    Status(StoreStatusCmd),
+}
+
+#[async_trait]
+impl OroCommand for StoreCmd {
+    async fn execute(self) -> Result<()> {
+        match self.subcommand {
+            StoreSubCmd::Status(cmd) => cmd.execute().await,
+        }
+    }
+}
+
+/// Report disk usage and entry counts for the package store.
+///
+/// This includes the total content size, the number of index entries and
+/// unique content integrities, and how much space is taken up by orphaned
+/// content that nothing refers to anymore.
+#[derive(Debug, Args)]
+pub struct StoreStatusCmd {
+    #[arg(from_global)]
+    cache: Option<PathBuf>,
+
+    #[arg(from_global)]
+    store_dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[async_trait]
+impl OroCommand for StoreStatusCmd {
+    async fn execute(self) -> Result<()> {
+        let cache = self
+            .store_dir
+            .or(self.cache)
+            .ok_or_else(|| miette::miette!("No cache directory configured."))?;
+        let status = {
+            let cache = cache.clone();
+            async_std::task::spawn_blocking(move || StoreStatus::compute(cache)).await?
+        };
+        match self.output.format() {
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&status)
+                    .into_diagnostic()
+                    .wrap_err("store::status::serialize")?
+            ),
+            OutputFormat::Parseable => {
+                println!("store\t{}", cache.display());
+                println!("content-size\t{}", status.content_size);
+                println!("entries\t{}", status.entries);
+                println!("unique-integrities\t{}", status.unique_integrities);
+                println!("orphaned-size\t{}", status.orphaned_size);
+                println!("orphaned-blobs\t{}", status.orphaned_blobs);
+            }
+            OutputFormat::Human => {
+                println!("Store: {}", cache.display());
+                println!("Content size: {}", format_bytes(status.content_size));
+                println!("Entries: {}", status.entries);
+                println!("Unique integrities: {}", status.unique_integrities);
+                println!(
+                    "Orphaned content: {} ({} blob{})",
+                    format_bytes(status.orphaned_size),
+                    status.orphaned_blobs,
+                    if status.orphaned_blobs == 1 { "" } else { "s" }
+                );
+            }
+        }
+        Ok(())
+    }
+}
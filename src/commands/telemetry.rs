@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use miette::{IntoDiagnostic, Result, WrapErr};
+
+use crate::commands::OroCommand;
+use crate::config_file;
+use crate::output::{OutputArgs, OutputFormat};
+
+/// View or change whether anonymous telemetry is sent.
+#[derive(Debug, Args)]
+pub struct TelemetryCmd {
+    #[command(subcommand)]
+    subcommand: TelemetrySubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum TelemetrySubCmd {
+    Status(TelemetryStatusCmd),
+    Enable(TelemetryEnableCmd),
+    Disable(TelemetryDisableCmd),
+}
+
+#[async_trait]
+impl OroCommand for TelemetryCmd {
+    async fn execute(self) -> Result<()> {
+        match self.subcommand {
+            TelemetrySubCmd::Status(cmd) => cmd.execute().await,
+            TelemetrySubCmd::Enable(cmd) => cmd.execute().await,
+            TelemetrySubCmd::Disable(cmd) => cmd.execute().await,
+        }
+    }
+}
+
+/// Show whether telemetry is enabled, and where it will be sent.
+#[derive(Debug, Args)]
+pub struct TelemetryStatusCmd {
+    #[arg(from_global)]
+    telemetry: bool,
+
+    #[arg(from_global)]
+    sentry_dsn: Option<String>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[async_trait]
+impl OroCommand for TelemetryStatusCmd {
+    async fn execute(self) -> Result<()> {
+        let dsn = self
+            .sentry_dsn
+            .as_deref()
+            .or_else(|| option_env!("OROGENE_SENTRY_DSN"));
+        match self.output.format() {
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "enabled": self.telemetry,
+                    "dsn": dsn,
+                }))
+                .into_diagnostic()
+                .wrap_err("telemetry::status::serialize")?
+            ),
+            OutputFormat::Parseable => {
+                println!("enabled\t{}", self.telemetry);
+                println!("dsn\t{}", dsn.unwrap_or(""));
+            }
+            OutputFormat::Human => {
+                println!(
+                    "Telemetry is {}.",
+                    if self.telemetry {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    }
+                );
+                println!("DSN: {}", dsn.unwrap_or("(none)"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Enable anonymous telemetry in your global oro.kdl.
+#[derive(Debug, Args)]
+pub struct TelemetryEnableCmd {
+    #[arg(from_global)]
+    config: Option<PathBuf>,
+}
+
+#[async_trait]
+impl OroCommand for TelemetryEnableCmd {
+    async fn execute(self) -> Result<()> {
+        set_telemetry(self.config, true)
+    }
+}
+
+/// Disable anonymous telemetry in your global oro.kdl.
+#[derive(Debug, Args)]
+pub struct TelemetryDisableCmd {
+    #[arg(from_global)]
+    config: Option<PathBuf>,
+}
+
+#[async_trait]
+impl OroCommand for TelemetryDisableCmd {
+    async fn execute(self) -> Result<()> {
+        set_telemetry(self.config, false)
+    }
+}
+
+fn set_telemetry(config: Option<PathBuf>, enabled: bool) -> Result<()> {
+    let path = config_file::config_path(config.as_deref())
+        .ok_or_else(|| miette::miette!("Could not find a location for the global oro.kdl."))?;
+    let mut config = config_file::read_config(&path)?;
+    config_file::set_bool_option(&mut config, "telemetry", enabled);
+    config_file::write_config(&path, &config)?;
+    tracing::info!(
+        "Telemetry {} in {}.",
+        if enabled { "enabled" } else { "disabled" },
+        path.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use clap::Args;
+use miette::{IntoDiagnostic, Result};
+use nassun::{Nassun, PackageResolution};
+use oro_common::CorgiManifest;
+use oro_package_spec::{PackageSpec, VersionSpec};
+use oro_pretty_json::Formatted;
+
+use crate::apply_args::ApplyArgs;
+use crate::commands::OroCommand;
+use crate::nassun_args::NassunArgs;
+use crate::OroError;
+
+/// Updates dependencies to the newest versions allowed by their declared
+/// ranges.
+#[derive(Debug, Args)]
+#[clap(visible_aliases(["up"]))]
+pub struct UpdateCmd {
+    /// Package names of dependencies to update. If none are given, all
+    /// dependencies are updated.
+    #[arg()]
+    names: Vec<String>,
+
+    /// Update to the latest versions, even if they're outside the declared
+    /// ranges, and bump the ranges in package.json to match.
+    #[arg(long)]
+    latest: bool,
+
+    #[command(flatten)]
+    apply: ApplyArgs,
+}
+
+#[async_trait]
+impl OroCommand for UpdateCmd {
+    async fn execute(mut self) -> Result<()> {
+        let mut manifest = oro_pretty_json::from_str(
+            &async_std::fs::read_to_string(self.apply.root.join("package.json"))
+                .await
+                .into_diagnostic()?,
+        )
+        .into_diagnostic()?;
+        for name in &self.names {
+            match name.parse() {
+                Ok(PackageSpec::Npm {
+                    name: spec_name, ..
+                }) if &spec_name == name => {}
+                _ => return Err(OroError::InvalidPackageName(name.clone()).into()),
+            }
+        }
+
+        let mut bumped = 0;
+        if self.latest {
+            let nassun = NassunArgs::from_apply_args(&self.apply).to_nassun()?;
+            bumped = self.bump_ranges(&nassun, &mut manifest).await?;
+        }
+
+        if self.apply.locked {
+            // NOTE: we force locked to be false here, because it doesn't make
+            // sense to run this command in locked mode.
+            tracing::info!("Ignoring --locked option. It doesn't make sense to run this command in locked mode.");
+            self.apply.locked = false;
+        }
+        self.apply.update = Some(self.names.clone());
+
+        let corgi: CorgiManifest =
+            serde_json::from_str(&oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?)
+                .into_diagnostic()?;
+
+        // Then, we apply the change.
+        self.apply.execute(corgi).await?;
+
+        if bumped > 0 {
+            async_std::fs::write(
+                self.apply.root.join("package.json"),
+                oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?,
+            )
+            .await
+            .into_diagnostic()?;
+
+            tracing::info!(
+                "{}Bumped {bumped} dependency range{} in package.json.",
+                if self.apply.emoji { "📝 " } else { "" },
+                if bumped == 1 { "" } else { "s" },
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl UpdateCmd {
+    /// Rewrites the ranges of the requested registry dependencies so they
+    /// point at the latest available version, keeping `~` ranges and exact
+    /// versions in the same style. Returns how many ranges were changed.
+    async fn bump_ranges(&self, nassun: &Nassun, mani: &mut Formatted) -> Result<usize> {
+        let mut count = 0;
+        for ty in ["dependencies", "devDependencies", "optionalDependencies"] {
+            let Some(deps) = mani.value[ty].as_object_mut() else {
+                continue;
+            };
+            for (name, spec) in deps.iter_mut() {
+                if !self.names.is_empty() && !self.names.contains(name) {
+                    continue;
+                }
+                let Some(current) = spec.as_str() else {
+                    continue;
+                };
+                let prefix = match format!("{name}@{current}").parse() {
+                    Ok(PackageSpec::Npm {
+                        requested: Some(VersionSpec::Version(_)),
+                        ..
+                    }) => "",
+                    Ok(PackageSpec::Npm {
+                        requested: Some(VersionSpec::Range(_)),
+                        ..
+                    }) if current.trim().starts_with('~') => "~",
+                    Ok(PackageSpec::Npm {
+                        requested: Some(VersionSpec::Range(_)),
+                        ..
+                    }) => "^",
+                    // Tags, git, file and alias specs don't have a range to
+                    // bump.
+                    _ => continue,
+                };
+                let pkg = nassun.resolve(name).await?;
+                let PackageResolution::Npm { version, .. } = pkg.resolved() else {
+                    continue;
+                };
+                let latest = format!("{prefix}{version}");
+                if latest != current {
+                    tracing::debug!("Bumping {name}@{current} to {name}@{latest} in {ty}.");
+                    *spec = serde_json::to_value(latest).expect("Value is always a valid string");
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+}
@@ -1,40 +1,260 @@
+use std::io::Write;
+
 use async_trait::async_trait;
-use clap::Args;
+use clap::{clap_derive::ValueEnum, ArgGroup, Args};
 use colored::*;
-use humansize::{file_size_opts, FileSize};
 use miette::{IntoDiagnostic, Result, WrapErr};
+use oro_client::OroClientBuilder;
 use oro_common::{Bin, DeprecationInfo, Manifest, NpmUser, Person, PersonField, VersionMetadata};
+use oro_package_spec::PackageSpec;
 use term_grid::{Cell, Direction, Filling, Grid, GridOptions};
 
+use crate::client_args::ClientArgs;
 use crate::commands::OroCommand;
+use crate::format::format_bytes;
 use crate::nassun_args::NassunArgs;
+use crate::output::{OutputArgs, OutputFormat};
+use crate::OroError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DownloadPeriod {
+    LastDay,
+    LastWeek,
+    LastMonth,
+}
+
+impl DownloadPeriod {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DownloadPeriod::LastDay => "last-day",
+            DownloadPeriod::LastWeek => "last-week",
+            DownloadPeriod::LastMonth => "last-month",
+        }
+    }
+}
+
+/// Which form of a packument `--packument` prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PackumentForm {
+    Full,
+    Corgi,
+}
 
 #[derive(Debug, Args)]
 /// Get information about a package.
 #[clap(visible_aliases(["v", "info"]))]
+#[command(group(ArgGroup::new("link").args(["repo", "homepage"])))]
 pub struct ViewCmd {
     /// Package spec to look up.
     #[arg()]
     pkg: String,
 
-    #[arg(from_global)]
-    json: bool,
+    /// Print the package's repository URL instead of its summary.
+    ///
+    /// Shorthands like `github:user/repo` are expanded into a browsable
+    /// `https` URL.
+    #[arg(long)]
+    repo: bool,
+
+    /// Print the package's homepage URL instead of its summary.
+    #[arg(long)]
+    homepage: bool,
+
+    /// Open the `--repo` or `--homepage` URL in a browser, instead of
+    /// printing it.
+    #[arg(long, requires = "link")]
+    open: bool,
+
+    /// Print how many times the package was downloaded from the registry
+    /// over the given period, instead of its summary.
+    #[arg(long, value_enum, value_name = "PERIOD", conflicts_with = "link")]
+    downloads: Option<DownloadPeriod>,
+
+    /// List every deprecated version of the package along with its
+    /// deprecation message, instead of its summary.
+    #[arg(long, conflicts_with_all = ["link", "downloads"])]
+    deprecated: bool,
+
+    /// Print the package's README instead of its summary.
+    #[arg(long, conflicts_with_all = ["link", "downloads", "deprecated"])]
+    readme: bool,
+
+    /// Print the package's packument exactly as the registry sent it,
+    /// instead of its summary.
+    ///
+    /// `full` (the default) prints every field the registry has, while
+    /// `corgi` prints the abbreviated form the registry serves to
+    /// installers. Any version in the package spec is ignored, and the
+    /// document is printed verbatim regardless of `--output`.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORM",
+        num_args = 0..=1,
+        default_missing_value = "full",
+        conflicts_with_all = ["link", "downloads", "deprecated", "readme"]
+    )]
+    packument: Option<PackumentForm>,
+
+    #[command(flatten)]
+    output: OutputArgs,
 
     #[command(flatten)]
     nassun_args: NassunArgs,
+
+    #[arg(from_global)]
+    extra_network: bool,
 }
 
 #[async_trait]
 impl OroCommand for ViewCmd {
     async fn execute(self) -> Result<()> {
+        if let Some(form) = self.packument {
+            return self.print_packument(form).await;
+        }
         let pkg = self.nassun_args.to_nassun()?.resolve(&self.pkg).await?;
         let packument = pkg.packument().await?;
+        // This is the metadata for the version `self.pkg` resolved to, not
+        // necessarily the latest one.
         let metadata = pkg.metadata().await?;
+        if self.repo || self.homepage {
+            let (kind, url) = if self.repo {
+                (
+                    "repository",
+                    metadata
+                        .manifest
+                        .repository
+                        .as_ref()
+                        .and_then(|repo| repo.normalized_url())
+                        .map(|url| url.to_string()),
+                )
+            } else {
+                ("homepage", metadata.manifest.homepage.clone())
+            };
+            let url = url
+                .ok_or_else(|| OroError::MissingPackageUrl(pkg.name().to_string(), kind.into()))?;
+            if self.open {
+                open::that(&url).into_diagnostic().wrap_err("view::open")?;
+            } else if self.output.format() == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({ "url": url }))
+                        .into_diagnostic()
+                        .wrap_err("view::json_serialize")?
+                );
+            } else {
+                println!("{url}");
+            }
+            return Ok(());
+        }
+        if let Some(period) = self.downloads {
+            if !self.extra_network {
+                tracing::warn!("Skipping --downloads, since --no-extra-network is set.");
+                return Ok(());
+            }
+            let client_args: ClientArgs = self.nassun_args.clone().into();
+            let client_builder: OroClientBuilder = client_args.try_into()?;
+            let registry = self.nassun_args.registry_for(pkg.name());
+            let client = client_builder.registry(registry.clone()).build();
+            let Some(count) = client.downloads(period.as_str(), pkg.name()).await? else {
+                tracing::warn!("{registry} doesn't provide download counts.");
+                return Ok(());
+            };
+            match self.output.format() {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&count)
+                        .into_diagnostic()
+                        .wrap_err("view::json_serialize")?
+                ),
+                OutputFormat::Parseable => {
+                    println!("{}\t{}\t{}", count.downloads, count.start, count.end)
+                }
+                OutputFormat::Human => println!(
+                    "{} downloads ({} to {})",
+                    count.downloads.to_string().bright_green(),
+                    count.start,
+                    count.end
+                ),
+            }
+            return Ok(());
+        }
+        if self.deprecated {
+            let mut deprecated = packument
+                .versions
+                .iter()
+                .filter_map(|(version, meta)| {
+                    let message = match meta.deprecated.as_ref()? {
+                        DeprecationInfo::Reason(msg) => Some(msg.clone()),
+                        DeprecationInfo::UnknownReason => None,
+                    };
+                    Some((version, message))
+                })
+                .collect::<Vec<_>>();
+            deprecated.sort_by(|(a, _), (b, _)| a.cmp(b));
+            match self.output.format() {
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(
+                        &deprecated
+                            .iter()
+                            .map(|(version, message)| {
+                                serde_json::json!({ "version": version, "message": message })
+                            })
+                            .collect::<Vec<_>>()
+                    )
+                    .into_diagnostic()
+                    .wrap_err("view::json_serialize")?
+                ),
+                OutputFormat::Parseable => {
+                    for (version, message) in &deprecated {
+                        println!("{version}\t{}", message.as_deref().unwrap_or(""));
+                    }
+                }
+                OutputFormat::Human => {
+                    if deprecated.is_empty() {
+                        tracing::info!("No versions of {} are deprecated.", pkg.name());
+                    }
+                    for (version, message) in &deprecated {
+                        println!(
+                            "{} {}",
+                            version.to_string().yellow(),
+                            message.as_deref().unwrap_or("(no reason given)")
+                        );
+                    }
+                }
+            }
+            return Ok(());
+        }
+        if self.readme {
+            // Registries usually only keep the latest release's README on
+            // the packument, but some include one for each version, too.
+            let readme = metadata
+                .manifest
+                ._rest
+                .get("readme")
+                .and_then(|readme| readme.as_str())
+                .filter(|readme| !readme.is_empty())
+                .or_else(|| packument.readme())
+                .ok_or_else(|| OroError::MissingReadme(pkg.name().to_string()))?;
+            if self.output.format() == OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({ "readme": readme }))
+                        .into_diagnostic()
+                        .wrap_err("view::json_serialize")?
+                );
+            } else {
+                println!("{}", readme.trim_end());
+            }
+            return Ok(());
+        }
         // TODO: oro view pkg [<field>[.<subfield>...]]
         // Probably the best way to do this is to support doing raw
         // packument/manifest requests that just deserialize to
         // serde_json::Value?
-        if self.json {
+        let format = self.output.format();
+        if format == OutputFormat::Json {
             // TODO: What should this be? NPM is actually a weird mishmash of
             // the packument and the manifest?
             println!(
@@ -43,6 +263,28 @@ impl OroCommand for ViewCmd {
                     .into_diagnostic()
                     .wrap_err("view::json_serialize")?
             );
+        } else if format == OutputFormat::Parseable {
+            // name, version, license, dependency count, release count, and
+            // tarball URL, in that order.
+            let manifest = &metadata.manifest;
+            println!(
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                manifest.name.as_deref().unwrap_or(""),
+                manifest
+                    .version
+                    .as_ref()
+                    .map(|v| v.to_string())
+                    .unwrap_or_default(),
+                manifest.license.as_deref().unwrap_or(""),
+                manifest.dependencies.len(),
+                packument.versions.len(),
+                metadata
+                    .dist
+                    .tarball
+                    .as_ref()
+                    .map(|url| url.to_string())
+                    .unwrap_or_default(),
+            );
         } else {
             let VersionMetadata {
                 ref npm_user,
@@ -153,13 +395,7 @@ impl OroCommand for ViewCmd {
                 println!(".integrity: {}", sri.to_string().yellow());
             }
             if let Some(unpacked) = dist.unpacked_size {
-                println!(
-                    ".unpackedSize: {}",
-                    unpacked
-                        .file_size(file_size_opts::DECIMAL)
-                        .unwrap()
-                        .yellow()
-                );
+                println!(".unpackedSize: {}", format_bytes(unpacked as u64).yellow());
             }
             println!();
 
@@ -227,11 +463,7 @@ impl OroCommand for ViewCmd {
                     .to_string(),
             ) {
                 if let Some(NpmUser { name, email }) = npm_user {
-                    let human = chrono_humanize::HumanTime::from(
-                        chrono::DateTime::parse_from_rfc3339(time)
-                            .into_diagnostic()
-                            .wrap_err("view::bad_date")?,
-                    );
+                    let human = chrono_humanize::HumanTime::from(*time);
                     print!(
                         "published {} by {}",
                         human.to_string().yellow(),
@@ -247,3 +479,30 @@ impl OroCommand for ViewCmd {
         Ok(())
     }
 }
+
+impl ViewCmd {
+    /// Prints the packument for `self.pkg` byte-for-byte as the registry
+    /// returned it.
+    async fn print_packument(&self, form: PackumentForm) -> Result<()> {
+        let spec: PackageSpec = self.pkg.parse()?;
+        let PackageSpec::Npm { name, .. } = spec.target() else {
+            return Err(OroError::NotARegistryPackage(self.pkg.clone()).into());
+        };
+        let client_args: ClientArgs = self.nassun_args.clone().into();
+        let client_builder: OroClientBuilder = client_args.try_into()?;
+        let client = client_builder
+            .registry(self.nassun_args.registry_for(name).clone())
+            .build();
+        let (body, _) = match form {
+            PackumentForm::Full => client.packument_raw(name).await?,
+            PackumentForm::Corgi => client.corgi_packument_raw(name).await?,
+        };
+        let mut stdout = std::io::stdout().lock();
+        stdout
+            .write_all(&body)
+            .and_then(|_| stdout.flush())
+            .into_diagnostic()
+            .wrap_err("view::packument_write")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+//! Helpers for editing Orogene's global `oro.kdl` config file.
+
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+use kdl::{KdlDocument, KdlNode, KdlValue};
+use miette::{IntoDiagnostic, Result};
+
+/// Path to the config file that settings should be written to: either the
+/// file passed in through `--config`, or the global `oro.kdl`.
+pub(crate) fn config_path(config: Option<&Path>) -> Option<PathBuf> {
+    config.map(PathBuf::from).or_else(|| {
+        ProjectDirs::from("", "", "orogene").map(|p| p.config_dir().to_owned().join("oro.kdl"))
+    })
+}
+
+/// Reads the config file at `path`, creating its parent directory if needed.
+/// A missing file is treated as empty.
+pub(crate) fn read_config(path: &Path) -> Result<KdlDocument> {
+    let config_dir = path.parent().expect("must have parent");
+    if !config_dir.exists() {
+        std::fs::create_dir_all(config_dir).into_diagnostic()?;
+    }
+    Ok(std::fs::read_to_string(path).unwrap_or_default().parse()?)
+}
+
+pub(crate) fn write_config(path: &Path, config: &KdlDocument) -> Result<()> {
+    std::fs::write(path, config.to_string()).into_diagnostic()
+}
+
+/// Sets `options > {name}` to `value`, adding the node (and the `options`
+/// node) if it doesn't exist yet.
+pub(crate) fn set_bool_option(config: &mut KdlDocument, name: &str, value: bool) {
+    if config.get("options").is_none() {
+        config.nodes_mut().push(KdlNode::new("options"));
+    }
+    let opts = config
+        .get_mut("options")
+        .expect("options node was just ensured");
+    opts.ensure_children();
+    let children = opts
+        .children_mut()
+        .as_mut()
+        .expect("children were just ensured");
+    if let Some(opt) = children.get_mut(name) {
+        if let Some(val) = opt.get_mut(0) {
+            *val = value.into();
+        } else {
+            opt.push(KdlValue::Bool(value));
+        }
+    } else {
+        let mut node = KdlNode::new(name);
+        node.push(KdlValue::Bool(value));
+        children.nodes_mut().push(node);
+    }
+}
@@ -15,4 +15,87 @@ pub enum OroError {
         help("Use the package name as it appears in your package.json instead.")
     )]
     InvalidPackageName(String),
+
+    /// The project's package.json has a `packageManager` field asking for a
+    /// version of oro that doesn't match the one that's running.
+    ///
+    /// Install a matching version of oro, or update the `packageManager`
+    /// field.
+    #[error("This project wants to be installed with oro@{0}, but this is oro@{1}.")]
+    #[diagnostic(
+        code(oro::package_manager_mismatch),
+        url(docsrs),
+        help("Install a version of oro that matches the `packageManager` field in package.json, or pass `--package-manager-check warn` to continue anyway.")
+    )]
+    PackageManagerMismatch(String, String),
+
+    /// The package passed to `oro remove` isn't listed in any of the
+    /// dependency sections of package.json that were searched.
+    #[error("{0} was not found in {1}.")]
+    #[diagnostic(
+        code(oro::remove::dependency_not_found),
+        url(docsrs),
+        help("Check that the package name matches an entry in your package.json.")
+    )]
+    DependencyNotFound(String, String),
+
+    /// The package passed to `oro view --repo` or `oro view --homepage`
+    /// doesn't declare that URL in its manifest, or it couldn't be
+    /// understood.
+    #[error("{0} does not have a {1} URL.")]
+    #[diagnostic(code(oro::view::missing_url), url(docsrs))]
+    MissingPackageUrl(String, String),
+
+    /// The package passed to `oro view --readme` doesn't have a README on
+    /// the registry.
+    #[error("{0} does not have a README.")]
+    #[diagnostic(code(oro::view::missing_readme), url(docsrs))]
+    MissingReadme(String),
+
+    /// `oro view --packument` was given a spec for a package that doesn't
+    /// come from a registry, like a git repository or a local directory,
+    /// so there's no packument to print.
+    #[error("{0} is not a registry package, so it has no packument.")]
+    #[diagnostic(
+        code(oro::view::not_a_registry_package),
+        url(docsrs),
+        help("Pass a package name, optionally with a version or dist-tag.")
+    )]
+    NotARegistryPackage(String),
+
+    /// The packages passed to `oro add` would leave the tree with peer
+    /// dependencies resolved to incompatible versions. Neither
+    /// `package.json` nor `node_modules/` were changed.
+    #[error("Adding these packages would introduce peer dependency conflicts:\n{0}")]
+    #[diagnostic(
+        code(oro::add::peer_conflicts),
+        url(docsrs),
+        help("Pass `--force` to add them anyway.")
+    )]
+    AddPeerConflicts(String),
+
+    /// `oro apply --check` found differences between the resolved tree and
+    /// what's currently installed in `node_modules/`. Each difference is
+    /// logged before this error is returned.
+    #[error("node_modules/ is out of date. Found {0} difference(s) from the resolved tree.")]
+    #[diagnostic(
+        code(oro::apply::out_of_date),
+        url(docsrs),
+        help("Run `oro apply` to bring node_modules/ up to date.")
+    )]
+    NodeModulesOutOfDate(usize),
+
+    /// `--audit` found security advisories at or above `--audit-level` for
+    /// packages in the resolved tree. Each advisory is logged before this
+    /// error is returned, and nothing is installed.
+    #[error(
+        "Found {0} security {} at or above {1} severity.",
+        if *.0 == 1 { "advisory" } else { "advisories" }
+    )]
+    #[diagnostic(
+        code(oro::apply::audit_failed),
+        url(docsrs),
+        help("Update the affected packages, or raise `--audit-level` to ignore less severe advisories.")
+    )]
+    AuditFailed(usize, String),
 }
@@ -0,0 +1,49 @@
+//! Shared formatting for sizes and durations, so every command reports them
+//! with the same units and rounding.
+
+use std::time::Duration;
+
+use humansize::{file_size_opts, FileSize};
+
+/// Formats a byte count using decimal units, like `999 B` or `1.5 KB`.
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    bytes
+        .file_size(file_size_opts::DECIMAL)
+        .expect("unsigned sizes are never negative")
+}
+
+/// Formats a duration as microseconds below a millisecond, whole
+/// milliseconds below a second, and seconds with two decimals otherwise.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_millis(1) {
+        format!("{}µs", duration.as_micros())
+    } else if duration < Duration::from_secs(1) {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(999), "999 B");
+        assert_eq!(format_bytes(1000), "1 KB");
+        assert_eq!(format_bytes(1500), "1.50 KB");
+        assert_eq!(format_bytes(1_000_000), "1 MB");
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(Duration::ZERO), "0µs");
+        assert_eq!(format_duration(Duration::from_micros(999)), "999µs");
+        assert_eq!(format_duration(Duration::from_millis(1)), "1ms");
+        assert_eq!(format_duration(Duration::from_micros(999_999)), "999ms");
+        assert_eq!(format_duration(Duration::from_secs(1)), "1.00s");
+        assert_eq!(format_duration(Duration::from_millis(12_345)), "12.35s");
+    }
+}
@@ -100,7 +100,6 @@ use clap::{Args, Command, CommandFactory, FromArgMatches as _, Parser, Subcomman
 use dialoguer::{theme::ColorfulTheme, Confirm};
 use directories::ProjectDirs;
 use is_terminal::IsTerminal;
-use kdl::{KdlDocument, KdlNode, KdlValue};
 use miette::{IntoDiagnostic, Result};
 use oro_config::{OroConfig, OroConfigLayerExt, OroConfigOptions};
 use tracing_appender::non_blocking::WorkerGuard;
@@ -114,14 +113,19 @@ use tracing_subscriber::{
 use url::Url;
 
 use commands::OroCommand;
+use output::OutputFormat;
 
 pub use error::OroError;
 
 mod apply_args;
 mod client_args;
 mod commands;
+mod config_file;
 mod error;
+mod format;
 mod nassun_args;
+mod output;
+mod telemetry;
 
 const MAX_RETAINED_LOGS: usize = 5;
 
@@ -145,6 +149,9 @@ pub struct Orogene {
     root: PathBuf,
 
     /// Registry used for unscoped packages.
+    ///
+    /// Can also be a `file://` URL pointing at a local directory mirror of
+    /// packuments and tarballs, for installing without network access.
     #[arg(
         help_heading = "Global Options",
         global = true,
@@ -188,6 +195,20 @@ pub struct Orogene {
     #[arg(help_heading = "Global Options", global = true, long)]
     cache: Option<PathBuf>,
 
+    /// Location of the content-addressable package store.
+    ///
+    /// Defaults to the `--cache` directory. Useful for keeping the (large)
+    /// store on a different volume than the metadata cache.
+    #[arg(help_heading = "Global Options", global = true, long)]
+    store_dir: Option<PathBuf>,
+
+    /// Location of the HTTP cache for packuments and other registry
+    /// metadata.
+    ///
+    /// Defaults to the `--cache` directory.
+    #[arg(help_heading = "Global Options", global = true, long)]
+    metadata_cache_dir: Option<PathBuf>,
+
     /// File to read configuration values from.
     ///
     /// When specified, global configuration loading is disabled and
@@ -212,7 +233,19 @@ pub struct Orogene {
     #[arg(help_heading = "Global Options", global = true, long, short)]
     quiet: bool,
 
-    /// Format output as JSON.
+    /// Format to print command results in.
+    ///
+    /// `parseable` prints tab-separated fields, one record per line.
+    #[arg(
+        help_heading = "Global Options",
+        global = true,
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Human
+    )]
+    output: OutputFormat,
+
+    /// Format output as JSON. Shorthand for `--output json`.
     #[arg(help_heading = "Global Options", global = true, long)]
     json: bool,
 
@@ -265,6 +298,19 @@ pub struct Orogene {
     #[arg(help_heading = "Global Options", global = true, long)]
     sentry_dsn: Option<String>,
 
+    /// Only talk to registries to fetch packuments and tarballs.
+    ///
+    /// This turns off telemetry, `--audit`, and `oro view --downloads`, for
+    /// environments where nothing else should leave the machine. Can also be
+    /// set with `extra-network false` in an oro.kdl.
+    #[arg(
+        help_heading = "Global Options",
+        global = true,
+        long = "no-extra-network",
+        action = clap::ArgAction::SetFalse,
+    )]
+    extra_network: bool,
+
     #[command(subcommand)]
     subcommand: OroCmd,
 
@@ -300,6 +346,25 @@ pub struct Orogene {
     )]
     no_proxy_domain: Option<String>,
 
+    /// Path to a PEM-encoded bundle of additional CA certificates to trust.
+    ///
+    /// Useful for registries or proxies that use certificates signed by a
+    /// private certificate authority.
+    #[arg(
+        help_heading = "Global Options",
+        global = true,
+        long,
+        value_name = "PATH"
+    )]
+    ca_bundle: Option<PathBuf>,
+
+    /// Skip TLS certificate validation entirely.
+    ///
+    /// This makes it trivial for anyone on the network to tamper with
+    /// downloaded packages. Only use it for testing.
+    #[arg(help_heading = "Global Options", global = true, long)]
+    danger_accept_invalid_certs: bool,
+
     /// How many times to retry failed network operations.
     #[arg(
         help_heading = "Global Options",
@@ -436,6 +501,14 @@ impl Orogene {
 
         let mut cfg_builder = OroConfigOptions::new()
             .set_default("root", &root.to_string_lossy())?
+            .set_default(
+                "suggest-types",
+                if std::io::stderr().is_terminal() {
+                    "true"
+                } else {
+                    "false"
+                },
+            )?
             .env(true);
         if let Some(cache) = dirs.as_ref().map(|d| d.cache_dir().to_owned()) {
             cfg_builder = cfg_builder.set_default("cache", &cache.to_string_lossy())?;
@@ -513,72 +586,14 @@ impl Orogene {
         // We skip first-time-setup operations in CI entirely.
         if self.first_time && !is_ci::cached() {
             tracing::info!("Performing first-time setup...");
-            if let Some(config_path) = self.config.clone().or_else(|| {
-                ProjectDirs::from("", "", "orogene")
-                    .map(|p| p.config_dir().to_owned().join("oro.kdl"))
-            }) {
-                let config_dir = config_path.parent().expect("must have parent");
-                if !config_dir.exists() {
-                    std::fs::create_dir_all(config_dir).unwrap();
-                }
-                let mut config: KdlDocument = std::fs::read_to_string(&config_path)
-                    .unwrap_or_default()
-                    .parse()?;
-                let telemetry_exists = config.query("options > telemetry")?.is_some();
-                if config.get("options").is_none() {
-                    config.nodes_mut().push(KdlNode::new("options"));
+            if let Some(config_path) = config_file::config_path(self.config.as_deref()) {
+                let mut config = config_file::read_config(&config_path)?;
+                if self.extra_network && std::io::stdout().is_terminal() {
+                    self.telemetry = self.prompt_telemetry_opt_in()?;
+                    config_file::set_bool_option(&mut config, "telemetry", self.telemetry);
                 }
-                if std::io::stdout().is_terminal() {
-                    if let Some(opts) = config.get_mut("options") {
-                        self.telemetry = self.prompt_telemetry_opt_in()?;
-                        if !telemetry_exists {
-                            let mut node = KdlNode::new("telemetry");
-                            node.push(KdlValue::Bool(self.telemetry));
-                            opts.ensure_children();
-                            if let Some(doc) = opts.children_mut().as_mut() {
-                                doc.nodes_mut().push(node)
-                            }
-                        }
-                        if let Some(opt) = config
-                            .get_mut("options")
-                            .unwrap()
-                            .children_mut()
-                            .as_mut()
-                            .unwrap()
-                            .get_mut("telemetry")
-                        {
-                            if let Some(val) = opt.get_mut(0) {
-                                *val = self.telemetry.into();
-                            } else {
-                                opt.push(KdlValue::Bool(self.telemetry));
-                            }
-                        }
-                    }
-                }
-                if config.query("options > first-time")?.is_none() {
-                    let mut node = KdlNode::new("first-time");
-                    node.push(KdlValue::Bool(false));
-                    let opts = config.get_mut("options").unwrap();
-                    opts.ensure_children();
-                    if let Some(doc) = opts.children_mut().as_mut() {
-                        doc.nodes_mut().push(node)
-                    }
-                }
-                if let Some(opt) = config
-                    .get_mut("options")
-                    .unwrap()
-                    .children_mut()
-                    .as_mut()
-                    .unwrap()
-                    .get_mut("first-time")
-                {
-                    if let Some(val) = opt.get_mut(0) {
-                        *val = false.into();
-                    } else {
-                        opt.push(KdlValue::Bool(false));
-                    }
-                }
-                std::fs::write(config_path, config.to_string()).into_diagnostic()?;
+                config_file::set_bool_option(&mut config, "first-time", false);
+                config_file::write_config(&config_path, &config)?;
             }
         }
         Ok(())
@@ -636,15 +651,7 @@ impl Orogene {
                         move |info: &PanicInfo| {
                             if let Some(log_file) = log_file.as_deref() {
                                 sentry::configure_scope(|s| {
-                                    s.add_attachment(sentry::protocol::Attachment {
-                                        filename: log_file
-                                            .file_name()
-                                            .map(|f| f.to_string_lossy().to_string())
-                                            .unwrap_or_else(|| "oro-debug.log".into()),
-                                        content_type: Some("text/plain".into()),
-                                        buffer: std::fs::read(log_file).unwrap_or_default(),
-                                        ty: None,
-                                    });
+                                    s.add_attachment(telemetry::log_attachment(log_file));
                                 });
                             }
                             let msg = sentry::integrations::panic::message_from_panic_info(info);
@@ -699,6 +706,9 @@ impl Orogene {
             .or_else(|| config.get::<String>("cache").ok().map(PathBuf::from))
             .map(|c| c.join("_logs").join(log_file_name()));
         let _logging_guard = oro.setup_logging(log_file.as_deref())?;
+        if !oro.extra_network {
+            oro.telemetry = false;
+        }
         oro.first_time_setup()?;
         let _telemetry_guard = oro.setup_telemetry(log_file.clone())?;
         let do_term_progress = !oro.quiet && oro.progress;
@@ -724,22 +734,14 @@ impl Orogene {
                 if let Some(log_file) = log_file.as_deref() {
                     tracing::warn!("A debug log was written to {}", log_file.display());
                     sentry::configure_scope(|s| {
-                        s.add_attachment(sentry::protocol::Attachment {
-                            filename: log_file
-                                .file_name()
-                                .map(|f| f.to_string_lossy().to_string())
-                                .unwrap_or_else(|| "oro-debug.log".into()),
-                            content_type: Some("text/plain".into()),
-                            buffer: std::fs::read(log_file).unwrap_or_default(),
-                            ty: None,
-                        });
+                        s.add_attachment(telemetry::log_attachment(log_file));
                     });
                 }
                 let dyn_err: &dyn std::error::Error = e.as_ref();
                 sentry::capture_error(dyn_err);
                 e
             })?;
-        tracing::debug!("Ran in {}s", start.elapsed().as_millis() as f32 / 1000.0);
+        tracing::debug!("Ran in {}", format::format_duration(start.elapsed()));
         Ok(())
     }
 }
@@ -845,6 +847,8 @@ pub enum OroCmd {
 
     Apply(commands::apply::ApplyCmd),
 
+    Fund(commands::fund::FundCmd),
+
     Login(commands::login::LoginCmd),
 
     Logout(commands::logout::LogoutCmd),
@@ -855,6 +859,14 @@ pub enum OroCmd {
 
     Remove(commands::remove::RemoveCmd),
 
+    Schema(commands::schema::SchemaCmd),
+
+    Store(commands::store::StoreCmd),
+
+    Telemetry(commands::telemetry::TelemetryCmd),
+
+    Update(commands::update::UpdateCmd),
+
     View(commands::view::ViewCmd),
 
     #[clap(hide = true)]
@@ -868,11 +880,16 @@ impl OroCommand for Orogene {
         match self.subcommand {
             OroCmd::Add(cmd) => cmd.execute().await,
             OroCmd::Apply(cmd) => cmd.execute().await,
+            OroCmd::Fund(cmd) => cmd.execute().await,
             OroCmd::Login(cmd) => cmd.execute().await,
             OroCmd::Logout(cmd) => cmd.execute().await,
             OroCmd::Ping(cmd) => cmd.execute().await,
             OroCmd::Reapply(cmd) => cmd.execute().await,
             OroCmd::Remove(cmd) => cmd.execute().await,
+            OroCmd::Schema(cmd) => cmd.execute().await,
+            OroCmd::Store(cmd) => cmd.execute().await,
+            OroCmd::Telemetry(cmd) => cmd.execute().await,
+            OroCmd::Update(cmd) => cmd.execute().await,
             OroCmd::View(cmd) => cmd.execute().await,
             OroCmd::HelpMarkdown(cmd) => cmd.execute().await,
         }
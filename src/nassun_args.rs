@@ -4,6 +4,7 @@ use clap::Args;
 use miette::Result;
 use nassun::{Nassun, NassunOpts};
 use oro_client::OroClientBuilder;
+use oro_common::WarningCollector;
 use url::Url;
 
 use crate::{apply_args::ApplyArgs, client_args::ClientArgs};
@@ -26,6 +27,12 @@ pub struct NassunArgs {
     #[arg(from_global)]
     pub cache: Option<PathBuf>,
 
+    #[arg(from_global)]
+    pub store_dir: Option<PathBuf>,
+
+    #[arg(from_global)]
+    pub metadata_cache_dir: Option<PathBuf>,
+
     #[arg(from_global)]
     pub proxy: bool,
 
@@ -35,11 +42,20 @@ pub struct NassunArgs {
     #[arg(from_global)]
     pub no_proxy_domain: Option<String>,
 
+    #[arg(from_global)]
+    pub ca_bundle: Option<PathBuf>,
+
+    #[arg(from_global)]
+    pub danger_accept_invalid_certs: bool,
+
     #[arg(from_global)]
     pub retries: u32,
 
     #[arg(from_global)]
     pub auth: Vec<(String, String, String)>,
+
+    #[arg(skip)]
+    pub warnings: WarningCollector,
 }
 
 impl NassunArgs {
@@ -50,14 +66,34 @@ impl NassunArgs {
             scoped_registries: apply_args.scoped_registries.clone(),
             root: apply_args.root.clone(),
             cache: apply_args.cache.clone(),
+            store_dir: apply_args.store_dir.clone(),
+            metadata_cache_dir: apply_args.metadata_cache_dir.clone(),
             proxy: apply_args.proxy,
             proxy_url: apply_args.proxy_url.clone(),
             no_proxy_domain: apply_args.no_proxy_domain.clone(),
+            ca_bundle: apply_args.ca_bundle.clone(),
+            danger_accept_invalid_certs: apply_args.danger_accept_invalid_certs,
             retries: apply_args.retries,
             auth: apply_args.auth.clone(),
+            warnings: apply_args.warnings.clone(),
         }
     }
 
+    /// Registry that requests for `package_name` are sent to, taking
+    /// `--scoped-registry` into account.
+    pub fn registry_for(&self, package_name: &str) -> &Url {
+        package_name
+            .strip_prefix('@')
+            .and_then(|name| name.split_once('/'))
+            .and_then(|(scope, _)| {
+                self.scoped_registries
+                    .iter()
+                    .find(|(s, _)| s.strip_prefix('@').unwrap_or(s) == scope)
+            })
+            .map(|(_, registry)| registry)
+            .unwrap_or(&self.registry)
+    }
+
     pub fn to_nassun(&self) -> Result<Nassun> {
         let client_args: ClientArgs = ((*self).clone()).into();
         let client_builder: OroClientBuilder = client_args.try_into()?;
@@ -65,13 +101,16 @@ impl NassunArgs {
             .registry(self.registry.clone())
             .base_dir(self.root.clone())
             .default_tag(&self.default_tag)
+            .retries(self.retries)
+            .warnings(self.warnings.clone())
             .client(client_builder.build());
-        for (scope, registry) in &self.scoped_registries {
-            nassun_opts = nassun_opts.scope_registry(scope.clone(), registry.clone());
-        }
+        nassun_opts = nassun_opts.registries(self.scoped_registries.iter().cloned().collect());
         if let Some(cache) = &self.cache {
             nassun_opts = nassun_opts.cache(cache.clone());
         }
+        if let Some(store_dir) = &self.store_dir {
+            nassun_opts = nassun_opts.store_dir(store_dir.clone());
+        }
         Ok(nassun_opts.build())
     }
 }
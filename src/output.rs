@@ -0,0 +1,34 @@
+//! The output format shared by commands that print results, selected with
+//! the global `--output` option (or its `--json` shorthand).
+
+use clap::{clap_derive::ValueEnum, Args};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+    Parseable,
+}
+
+/// Output options for a command, picked up from the global flags.
+#[derive(Debug, Clone, Args)]
+pub struct OutputArgs {
+    #[arg(from_global)]
+    output: OutputFormat,
+
+    #[arg(from_global)]
+    json: bool,
+}
+
+impl OutputArgs {
+    /// The format to print results in. `--json` takes precedence over
+    /// `--output`, since it's shorthand for `--output json`.
+    pub fn format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.output
+        }
+    }
+}
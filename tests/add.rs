@@ -0,0 +1,271 @@
+use serde_json::json;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+mod common;
+
+use common::{oro, stderr};
+
+#[test]
+fn add_no_save() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("dep")).unwrap();
+    std::fs::write(
+        root.join("dep").join("package.json"),
+        r#"{"name": "dep", "version": "1.0.0"}"#,
+    )
+    .unwrap();
+    let original = "{\n  \"name\": \"project\",\n  \"version\": \"1.0.0\"\n}\n";
+    std::fs::write(root.join("package.json"), original).unwrap();
+
+    let output = oro(root, &["add", "file:./dep", "--no-save"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(root.join("node_modules").join("dep").exists());
+    assert_eq!(
+        std::fs::read_to_string(root.join("package.json")).unwrap(),
+        original
+    );
+}
+
+#[test]
+fn install_alias_adds() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("dep")).unwrap();
+    std::fs::write(
+        root.join("dep").join("package.json"),
+        r#"{"name": "dep", "version": "1.0.0"}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0"}"#,
+    )
+    .unwrap();
+
+    let output = oro(root, &["i", "file:./dep"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(root.join("node_modules").join("dep").exists());
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(root.join("package.json")).unwrap()).unwrap();
+    assert!(manifest["dependencies"]["dep"].is_string(), "{manifest}");
+}
+
+#[async_std::test]
+async fn install_alias_uses_add_config() {
+    let mock_server = MockServer::start().await;
+    for (name, mount_path) in [("dep", "dep"), ("@types/dep", "@types%2fdep")] {
+        Mock::given(method("GET"))
+            .and(path(mount_path))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": name,
+                "dist-tags": { "latest": "1.2.3" },
+                "versions": {
+                    "1.2.3": {
+                        "name": name,
+                        "version": "1.2.3",
+                        "dist": {
+                            "tarball": format!("{}/{mount_path}/-/dep-1.2.3.tgz", mock_server.uri()),
+                            "integrity": "sha512-deadbeef",
+                        }
+                    }
+                },
+            })))
+            .mount(&mock_server)
+            .await;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join("oro.kdl"),
+        "options {\n    prefix \"~\"\n    suggest-types true\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0"}"#,
+    )
+    .unwrap();
+
+    let output = oro(
+        root,
+        &[
+            "i",
+            "dep",
+            "--lockfile-only",
+            "--registry",
+            &mock_server.uri(),
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("`oro add -D @types/dep`"),
+        "{}",
+        stderr(&output)
+    );
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(root.join("package.json")).unwrap()).unwrap();
+    assert_eq!(manifest["dependencies"]["dep"], "~1.2.3", "{manifest}");
+}
+
+#[test]
+fn add_rejects_invalid_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let original = "{\n  \"name\": \"project\",\n  \"version\": \"1.0.0\"\n}\n";
+    std::fs::write(root.join("package.json"), original).unwrap();
+
+    let output = oro(root, &["add", "_private@1.0.0"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("Package names can't start with `_`"),
+        "{}",
+        stderr(&output)
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("package.json")).unwrap(),
+        original
+    );
+}
+
+#[test]
+fn add_refuses_peer_conflicts() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    for (name, manifest) in [
+        ("host", r#"{"name": "host", "version": "1.0.0"}"#),
+        (
+            "plugin",
+            r#"{"name": "plugin", "version": "1.0.0", "peerDependencies": {"host": "^2.0.0"}}"#,
+        ),
+    ] {
+        std::fs::create_dir_all(root.join(name)).unwrap();
+        std::fs::write(root.join(name).join("package.json"), manifest).unwrap();
+    }
+    let original = "{\n  \"name\": \"project\",\n  \"version\": \"1.0.0\",\n  \"dependencies\": {\n    \"host\": \"file:./host\"\n  }\n}\n";
+    std::fs::write(root.join("package.json"), original).unwrap();
+
+    let output = oro(root, &["add", "file:./plugin"]);
+    assert!(!output.status.success());
+    let stderr_text = stderr(&output);
+    assert!(
+        stderr_text.contains("would introduce peer dependency conflicts"),
+        "{stderr_text}"
+    );
+    assert!(
+        stderr_text.contains("plugin requires peer dependency host@"),
+        "{stderr_text}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("package.json")).unwrap(),
+        original
+    );
+    assert!(!root.join("node_modules").join("plugin").exists());
+
+    let output = oro(root, &["add", "file:./plugin", "--force"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(std::fs::read_to_string(root.join("package.json"))
+        .unwrap()
+        .contains("plugin"));
+}
+
+#[test]
+fn add_ignores_existing_peer_conflicts() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    for (name, manifest) in [
+        ("host", r#"{"name": "host", "version": "1.0.0"}"#),
+        (
+            "plugin",
+            r#"{"name": "plugin", "version": "1.0.0", "peerDependencies": {"host": "^2.0.0"}}"#,
+        ),
+        ("other", r#"{"name": "other", "version": "1.0.0"}"#),
+    ] {
+        std::fs::create_dir_all(root.join(name)).unwrap();
+        std::fs::write(root.join(name).join("package.json"), manifest).unwrap();
+    }
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"host": "file:./host", "plugin": "file:./plugin"}}"#,
+    )
+    .unwrap();
+
+    // `plugin` and `host` already disagree, but that has nothing to do with
+    // the package being added.
+    let output = oro(root, &["add", "file:./other"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(root.join("node_modules").join("other").exists());
+    assert!(std::fs::read_to_string(root.join("package.json"))
+        .unwrap()
+        .contains("other"));
+}
+
+#[async_std::test]
+async fn add_suggests_types_package() {
+    let mock_server = MockServer::start().await;
+    for (name, mount_path) in [
+        ("typeless", "typeless"),
+        ("@types/typeless", "@types%2ftypeless"),
+    ] {
+        Mock::given(method("GET"))
+            .and(path(mount_path))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": name,
+                "dist-tags": { "latest": "1.0.0" },
+                "versions": {
+                    "1.0.0": {
+                        "name": name,
+                        "version": "1.0.0",
+                        "dist": {
+                            "tarball": format!("{}/{mount_path}/-/typeless-1.0.0.tgz", mock_server.uri()),
+                            "integrity": "sha512-deadbeef",
+                        }
+                    }
+                },
+            })))
+            .mount(&mock_server)
+            .await;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0"}"#,
+    )
+    .unwrap();
+
+    let output = oro(
+        root,
+        &[
+            "add",
+            "typeless",
+            "--lockfile-only",
+            "--suggest-types",
+            "--registry",
+            &mock_server.uri(),
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("`oro add -D @types/typeless`"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = oro(
+        root,
+        &[
+            "add",
+            "typeless",
+            "--lockfile-only",
+            "--no-suggest-types",
+            "--registry",
+            &mock_server.uri(),
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stderr(&output).contains("@types/typeless"));
+}
@@ -0,0 +1,795 @@
+use std::path::Path;
+use std::process::Output;
+use std::time::Duration;
+
+use serde_json::json;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+mod common;
+
+use common::{oro, oro_project_command, stderr};
+
+#[async_std::test]
+async fn lockfile_only_skips_node_modules() {
+    let registry = registry_with_tarball().await;
+    let dir = tempfile::tempdir().unwrap();
+    setup_registry_project(dir.path());
+
+    let output = apply(
+        dir.path(),
+        &["--lockfile-only", "--registry", &registry.uri()],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(dir.path().join("package-lock.kdl").exists());
+    assert!(!dir.path().join("node_modules").exists());
+}
+
+#[async_std::test]
+async fn no_lockfile_skips_lockfile() {
+    let registry = registry_with_tarball().await;
+    let dir = tempfile::tempdir().unwrap();
+    setup_registry_project(dir.path());
+
+    let output = apply(
+        dir.path(),
+        &["--no-lockfile", "--registry", &registry.uri()],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!dir.path().join("package-lock.kdl").exists());
+    assert!(dir.path().join("node_modules").join("a").exists());
+}
+
+#[test]
+fn lockfile_only_conflicts_with_no_lockfile() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_registry_project(dir.path());
+
+    let output = apply(dir.path(), &["--lockfile-only", "--no-lockfile"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("cannot be used with"));
+    assert!(!dir.path().join("package-lock.kdl").exists());
+    assert!(!dir.path().join("node_modules").exists());
+}
+
+#[test]
+fn report_timings_written() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+    let report_path = dir.path().join("timings.json");
+
+    let output = apply(
+        dir.path(),
+        &["--report-timings", report_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap();
+    for key in [
+        "total_ms",
+        "packages",
+        "phases",
+        "cache_hits",
+        "cache_misses",
+        "cache_hit_ratio",
+        "bytes_downloaded",
+    ] {
+        assert!(report.get(key).is_some(), "missing {key} in {report}");
+    }
+    for phase in ["resolve", "prune", "extract", "rebuild"] {
+        assert!(
+            report["phases"][phase]["duration_ms"].is_u64(),
+            "missing {phase} phase in {report}"
+        );
+    }
+    assert_eq!(report["phases"]["extract"]["packages"], 1);
+    // The only dependency is a directory, which never touches the cache.
+    assert_eq!(report["cache_hits"], 0);
+    assert_eq!(report["cache_misses"], 0);
+}
+
+#[async_std::test]
+async fn concurrent_apply_fails_fast() {
+    // The registry is slow enough that the first apply is still resolving
+    // while the second one starts.
+    let registry = registry_with_tarball_delayed(Duration::from_secs(3)).await;
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup_registry_project(root);
+    let args = ["--registry", &registry.uri()];
+
+    let first = spawn_apply(root, &args);
+    let output = apply(root, &args);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Another orogene process is running"));
+    let first = first.wait_with_output().unwrap();
+    assert!(first.status.success(), "{}", stderr(&first));
+    assert!(root.join("node_modules").join("a").exists());
+
+    // Killing an apply halfway through doesn't leave the project locked.
+    let mut killed = spawn_apply(root, &[&args[..], &["--force"]].concat());
+    killed.kill().unwrap();
+    killed.wait().unwrap();
+    let output = apply(root, &args);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn hoisted_apply_relinks_existing_bins() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+    std::fs::write(
+        dir.path().join("dep").join("package.json"),
+        r#"{"name": "dep", "version": "1.0.0", "bin": {"dep": "cli.js"}}"#,
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("dep").join("cli.js"), "").unwrap();
+    let bin = dir.path().join("node_modules").join(".bin").join("dep");
+
+    let output = apply(dir.path(), &["--hoisted"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(bin.exists());
+
+    // Nothing needs extracting the second time around, but bins for
+    // packages already in node_modules/ should still be linked.
+    std::fs::remove_file(&bin).unwrap();
+    let output = apply(dir.path(), &["--hoisted"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(bin.exists());
+}
+
+#[test]
+fn no_bin_links_skips_bin_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+    std::fs::write(
+        dir.path().join("dep").join("package.json"),
+        r#"{"name": "dep", "version": "1.0.0", "bin": {"dep": "cli.js"}}"#,
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("dep").join("cli.js"), "").unwrap();
+
+    for mode in [&["--no-bin-links"][..], &["--no-bin-links", "--hoisted"]] {
+        let output = apply(dir.path(), mode);
+        assert!(output.status.success(), "{}", stderr(&output));
+        let node_modules = dir.path().join("node_modules");
+        assert!(node_modules.join("dep").join("cli.js").exists());
+        assert!(!node_modules.join(".bin").exists());
+        std::fs::remove_dir_all(node_modules).unwrap();
+    }
+}
+
+#[test]
+fn check_in_sync_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = apply(dir.path(), &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = apply(dir.path(), &["--check", "--locked"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("node_modules/ is up to date"));
+}
+
+#[test]
+fn check_reports_warnings() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+    std::fs::write(
+        dir.path().join("dep").join("package.json"),
+        r#"{"name": "dep", "version": "1.0.0", "peerDependencies": {"peer": "^1.0.0"}}"#,
+    )
+    .unwrap();
+
+    let output = apply(dir.path(), &["--no-auto-install-peers"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = apply(dir.path(), &["--check", "--no-auto-install-peers"]);
+    let stderr = stderr(&output);
+    assert!(output.status.success(), "{stderr}");
+    assert!(stderr.contains("1 warning:"), "{stderr}");
+    assert!(
+        stderr.contains("dep requires peer dependency peer@"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn json_warnings_only_when_present() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = apply(dir.path(), &["--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("warnings"), "{stdout}");
+
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+    std::fs::write(
+        dir.path().join("dep").join("package.json"),
+        r#"{"name": "dep", "version": "1.0.0", "peerDependencies": {"peer": "^1.0.0"}}"#,
+    )
+    .unwrap();
+    let output = apply(dir.path(), &["--json", "--no-auto-install-peers"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["warnings"][0]["kind"], json!("peer-conflict"));
+}
+
+#[test]
+fn check_drifted_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = apply(dir.path(), &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let other = dir.path().join("other");
+    std::fs::create_dir_all(&other).unwrap();
+    std::fs::write(
+        other.join("package.json"),
+        r#"{"name": "other", "version": "2.0.0"}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("package.json"),
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"other": "file:./other"}}"#,
+    )
+    .unwrap();
+    let lockfile = std::fs::read_to_string(dir.path().join("package-lock.kdl")).unwrap();
+
+    let output = apply(dir.path(), &["--check"]);
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    let drift_line = |path: &str| {
+        stderr
+            .lines()
+            .find(|line| line.contains(&format!("{path}: ")))
+            .unwrap_or_else(|| panic!("no drift reported for {path}: {stderr}"))
+    };
+    assert!(drift_line("node_modules/other").contains("is not installed"));
+    assert!(drift_line("node_modules/dep").contains("no longer needed"));
+    assert!(stderr.contains("node_modules/ is out of date"), "{stderr}");
+
+    // Nothing should have been touched.
+    assert!(dir.path().join("node_modules").join("dep").exists());
+    assert!(!dir.path().join("node_modules").join("other").exists());
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("package-lock.kdl")).unwrap(),
+        lockfile
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn linked_packages_left_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = apply(dir.path(), &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // Swap the installed copy of `dep` for a link to its source, and link in
+    // a workspace package that isn't part of the tree at all.
+    let node_modules = dir.path().join("node_modules");
+    std::fs::remove_dir_all(node_modules.join("dep")).unwrap();
+    std::os::unix::fs::symlink(dir.path().join("dep"), node_modules.join("dep")).unwrap();
+    let workspace = dir.path().join("workspace");
+    std::fs::create_dir_all(&workspace).unwrap();
+    std::fs::write(
+        workspace.join("package.json"),
+        r#"{"name": "workspace", "version": "1.0.0"}"#,
+    )
+    .unwrap();
+    std::os::unix::fs::symlink(&workspace, node_modules.join("workspace")).unwrap();
+
+    let output = apply(dir.path(), &["--check"]);
+    let stderr_output = stderr(&output);
+    assert!(output.status.success(), "{stderr_output}");
+    assert!(
+        stderr_output.contains("node_modules/dep: linked to"),
+        "{stderr_output}"
+    );
+
+    let output = apply(dir.path(), &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    for name in ["dep", "workspace"] {
+        let meta = std::fs::symlink_metadata(node_modules.join(name)).unwrap();
+        assert!(
+            meta.file_type().is_symlink(),
+            "{name} should still be linked"
+        );
+    }
+}
+
+fn setup_native_project(root: &Path) {
+    let mut optional = serde_json::Map::new();
+    for (name, os, cpu, libc) in [
+        ("native-darwin-arm64", "darwin", "arm64", None),
+        ("native-linux-x64-gnu", "linux", "x64", Some("glibc")),
+        ("native-linux-x64-musl", "linux", "x64", Some("musl")),
+        ("native-win32-x64", "win32", "x64", None),
+    ] {
+        let pkg = root.join(name);
+        std::fs::create_dir_all(&pkg).unwrap();
+        let mut manifest = serde_json::json!({
+            "name": name,
+            "version": "1.0.0",
+            "os": [os],
+            "cpu": [cpu],
+        });
+        if let Some(libc) = libc {
+            manifest["libc"] = serde_json::json!([libc]);
+        }
+        std::fs::write(pkg.join("package.json"), manifest.to_string()).unwrap();
+        optional.insert(name.into(), format!("file:./{name}").into());
+    }
+    std::fs::write(
+        root.join("package.json"),
+        serde_json::json!({
+            "name": "project",
+            "version": "1.0.0",
+            "optionalDependencies": optional,
+        })
+        .to_string(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn target_platform_selects_optional_deps() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_native_project(dir.path());
+
+    let output = apply(
+        dir.path(),
+        &[
+            "--target-os",
+            "linux",
+            "--target-cpu",
+            "x64",
+            "--target-libc",
+            "musl",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let node_modules = dir.path().join("node_modules");
+    assert!(node_modules.join("native-linux-x64-musl").exists());
+    for name in [
+        "native-darwin-arm64",
+        "native-linux-x64-gnu",
+        "native-win32-x64",
+    ] {
+        assert!(!node_modules.join(name).exists(), "{name} was installed");
+    }
+
+    // The lockfile written above only has the musl package in it, and
+    // records which platforms it supports, so switching targets swaps it out.
+    let output = apply(
+        dir.path(),
+        &["--target-os", "darwin", "--target-cpu", "arm64"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(node_modules.join("native-darwin-arm64").exists());
+    assert!(!node_modules.join("native-linux-x64-musl").exists());
+    let lockfile = std::fs::read_to_string(dir.path().join("package-lock.kdl")).unwrap();
+    assert!(!lockfile.contains("native-linux-x64-musl"), "{lockfile}");
+    assert!(lockfile.contains(r#"os "darwin""#), "{lockfile}");
+}
+
+#[test]
+fn old_lockfile_optional_deps_get_platform_checked() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_native_project(dir.path());
+    let output = apply(
+        dir.path(),
+        &[
+            "--target-os",
+            "linux",
+            "--target-cpu",
+            "x64",
+            "--target-libc",
+            "musl",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // v1 lockfiles never recorded platform info, so the musl package has to
+    // have its manifest looked at again before it can be kept.
+    let lockfile = dir.path().join("package-lock.kdl");
+    let old = std::fs::read_to_string(&lockfile)
+        .unwrap()
+        .replace("lockfile-version 2", "lockfile-version 1")
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !(line.starts_with("os ") || line.starts_with("cpu ") || line.starts_with("libc "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert!(old.contains("native-linux-x64-musl"), "{old}");
+    std::fs::write(&lockfile, old).unwrap();
+
+    let output = apply(
+        dir.path(),
+        &["--target-os", "darwin", "--target-cpu", "arm64"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let node_modules = dir.path().join("node_modules");
+    assert!(node_modules.join("native-darwin-arm64").exists());
+    assert!(!node_modules.join("native-linux-x64-musl").exists());
+    let lockfile = std::fs::read_to_string(&lockfile).unwrap();
+    assert!(lockfile.contains("lockfile-version 2"), "{lockfile}");
+    assert!(!lockfile.contains("native-linux-x64-musl"), "{lockfile}");
+}
+
+#[test]
+fn unknown_target_platform() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = apply(dir.path(), &["--target-os", "plan9"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("invalid value 'plan9'"));
+    assert!(!dir.path().join("node_modules").exists());
+}
+
+#[test]
+fn corrupt_lockfile() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+    let lockfile = dir.path().join("package-lock.kdl");
+    std::fs::write(&lockfile, "lockfile-version 1\nroot {\n    version \"1.0.0").unwrap();
+
+    let output = apply(dir.path(), &["--lockfile-only"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("--fix-lockfile"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = apply(dir.path(), &["--lockfile-only", "--fix-lockfile"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let fixed = node_maintainer::Lockfile::from_kdl(std::fs::read_to_string(&lockfile).unwrap())
+        .expect("rewritten lockfile should be valid");
+    assert!(fixed
+        .packages()
+        .keys()
+        .any(|path| path.to_string() == "dep"));
+}
+
+#[async_std::test]
+async fn audit_level_gate() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("-/npm/v1/security/advisories/bulk"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "dep": [{
+                "id": 1,
+                "url": "https://example.com/advisories/1",
+                "title": "Remote code execution",
+                "severity": "high",
+                "vulnerable_versions": "<=1.0.0",
+            }],
+        })))
+        .mount(&mock_server)
+        .await;
+    let registry = mock_server.uri();
+
+    let output = apply(
+        dir.path(),
+        &["--registry", &registry, "--audit", "--audit-level", "high"],
+    );
+    assert!(!output.status.success());
+    let err = stderr(&output);
+    assert!(err.contains("Remote code execution"), "{err}");
+    assert!(
+        err.contains("1 security advisory at or above high"),
+        "{err}"
+    );
+    assert!(!dir.path().join("node_modules").exists());
+    assert!(!dir.path().join("package-lock.kdl").exists());
+
+    let output = apply(
+        dir.path(),
+        &[
+            "--registry",
+            &registry,
+            "--audit",
+            "--audit-level",
+            "critical",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(dir.path().join("node_modules").join("dep").exists());
+}
+
+#[async_std::test]
+async fn no_extra_network_skips_audit() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("-/npm/v1/security/advisories/bulk"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let output = apply(
+        dir.path(),
+        &[
+            "--registry",
+            &mock_server.uri(),
+            "--audit",
+            "--no-extra-network",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Skipping --audit"));
+    assert!(dir.path().join("node_modules").join("dep").exists());
+}
+
+#[async_std::test]
+async fn force_ignores_stale_lockfile() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"a": "^1.0.0"}}"#,
+    )
+    .unwrap();
+    let locked_version = |root: &Path| {
+        let lockfile = node_maintainer::Lockfile::from_kdl(
+            std::fs::read_to_string(root.join("package-lock.kdl")).unwrap(),
+        )
+        .unwrap();
+        lockfile
+            .packages()
+            .values()
+            .find(|node| node.name.as_str() == "a")
+            .and_then(|node| node.version.clone())
+            .map(|version| version.to_string())
+    };
+
+    let old_registry = registry_with_versions(&["1.0.0"]).await;
+    let output = apply(
+        root,
+        &["--registry", &old_registry.uri(), "--lockfile-only"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(locked_version(root).as_deref(), Some("1.0.0"));
+
+    let new_registry = registry_with_versions(&["1.0.0", "1.1.0"]).await;
+    let output = apply(
+        root,
+        &[
+            "--registry",
+            &new_registry.uri(),
+            "--lockfile-only",
+            "--force",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(locked_version(root).as_deref(), Some("1.1.0"));
+}
+
+#[async_std::test]
+async fn separate_store_and_metadata_dirs() {
+    let mock_server = MockServer::start().await;
+    let mut tarball = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::default(),
+    ));
+    let manifest = r#"{"name": "a", "version": "1.0.0"}"#;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    tarball
+        .append_data(&mut header, "package/package.json", manifest.as_bytes())
+        .unwrap();
+    let tarball = tarball.into_inner().unwrap().finish().unwrap();
+    Mock::given(method("GET"))
+        .and(path("a"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "a",
+            "dist-tags": { "latest": "1.0.0" },
+            "versions": {
+                "1.0.0": {
+                    "name": "a",
+                    "version": "1.0.0",
+                    "dist": {
+                        "tarball": format!("{}/a/-/a-1.0.0.tgz", mock_server.uri()),
+                    }
+                }
+            },
+        })))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("a/-/a-1.0.0.tgz"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(tarball))
+        .mount(&mock_server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"a": "^1.0.0"}}"#,
+    )
+    .unwrap();
+    let store = root.join("store");
+    let metadata = root.join("metadata");
+    let output = apply(
+        root,
+        &[
+            "--registry",
+            &mock_server.uri(),
+            "--store-dir",
+            store.to_str().unwrap(),
+            "--metadata-cache-dir",
+            metadata.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let keys = |cache: &Path| {
+        cacache::list_sync(cache)
+            .map(|entry| entry.unwrap().key)
+            .collect::<Vec<_>>()
+    };
+    let packument_url = format!("{}/a", mock_server.uri());
+    let store_keys = keys(&store);
+    let metadata_keys = keys(&metadata);
+    assert!(store_keys
+        .iter()
+        .any(|key| key.starts_with("nassun::package::")));
+    assert!(!store_keys.iter().any(|key| key.ends_with(&packument_url)));
+    assert!(metadata_keys
+        .iter()
+        .any(|key| key.ends_with(&packument_url)));
+    assert!(!metadata_keys
+        .iter()
+        .any(|key| key.starts_with("nassun::package::")));
+}
+
+/// Serves a packument for `a` with each of `versions`, the last of which is
+/// `latest`.
+async fn registry_with_versions(versions: &[&str]) -> MockServer {
+    let mock_server = MockServer::start().await;
+    let manifests = versions
+        .iter()
+        .map(|version| {
+            (
+                version.to_string(),
+                json!({
+                    "name": "a",
+                    "version": version,
+                    "dist": {
+                        "tarball": format!("{}/a/-/a-{version}.tgz", mock_server.uri()),
+                        "integrity": "sha512-deadbeef",
+                    }
+                }),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>();
+    Mock::given(method("GET"))
+        .and(path("a"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "a",
+            "dist-tags": { "latest": versions.last().unwrap() },
+            "versions": manifests,
+        })))
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+/// Starts `oro apply` with `args` in the background, and waits for it to
+/// take the project lock.
+fn spawn_apply(root: &Path, args: &[&str]) -> std::process::Child {
+    let lock = root.join(".oro-lock");
+    let _ = std::fs::remove_file(&lock);
+    let child = oro_project_command(root, &[&["apply"], args].concat())
+        .spawn()
+        .expect("Failed to execute oro");
+    let start = std::time::Instant::now();
+    while !lock.exists() {
+        assert!(
+            start.elapsed() < Duration::from_secs(30),
+            "apply never took the project lock"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    child
+}
+
+/// Serves `a@1.0.0`, with a tarball that can actually be extracted.
+async fn registry_with_tarball() -> MockServer {
+    registry_with_tarball_delayed(Duration::ZERO).await
+}
+
+/// Serves `a@1.0.0` like [`registry_with_tarball`], but waits `delay` before
+/// responding with its packument.
+async fn registry_with_tarball_delayed(delay: Duration) -> MockServer {
+    let mock_server = MockServer::start().await;
+    let mut tarball = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::default(),
+    ));
+    let manifest = r#"{"name": "a", "version": "1.0.0"}"#;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    tarball
+        .append_data(&mut header, "package/package.json", manifest.as_bytes())
+        .unwrap();
+    let tarball = tarball.into_inner().unwrap().finish().unwrap();
+    Mock::given(method("GET"))
+        .and(path("a"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_delay(delay)
+                .set_body_json(json!({
+                    "name": "a",
+                    "dist-tags": { "latest": "1.0.0" },
+                    "versions": {
+                        "1.0.0": {
+                            "name": "a",
+                            "version": "1.0.0",
+                            "dist": {
+                                "tarball": format!("{}/a/-/a-1.0.0.tgz", mock_server.uri()),
+                            }
+                        }
+                    },
+                })),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("a/-/a-1.0.0.tgz"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(tarball))
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+/// Creates a project with a single registry dependency on `a`.
+fn setup_registry_project(root: &Path) {
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"a": "^1.0.0"}}"#,
+    )
+    .unwrap();
+}
+
+/// Creates a project with a single `file:` dependency, so applying it
+/// doesn't need to talk to a registry.
+fn setup_project(root: &Path) {
+    let dep = root.join("dep");
+    std::fs::create_dir_all(&dep).unwrap();
+    std::fs::write(
+        dep.join("package.json"),
+        r#"{"name": "dep", "version": "1.0.0"}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"dep": "file:./dep"}}"#,
+    )
+    .unwrap();
+}
+
+fn apply(root: &Path, args: &[&str]) -> Output {
+    oro(root, &[&["apply"], args].concat())
+}
@@ -0,0 +1,45 @@
+//! Helpers for running the `oro` binary in integration tests.
+
+// Each test file only uses some of these.
+#![allow(dead_code)]
+
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+pub static BIN: &str = env!("CARGO_BIN_EXE_oro");
+
+/// An `oro` command with `args`, that won't prompt, print progress bars or
+/// emoji, or run first-time setup.
+pub fn oro_command(args: &[&str]) -> Command {
+    let mut cmd = Command::new(BIN);
+    cmd.args(args)
+        .args(["--no-first-time", "--no-progress", "--no-emoji"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    cmd
+}
+
+/// An `oro` command with `args` against the project at `root`, with a cache
+/// inside it and telemetry off.
+pub fn oro_project_command(root: &Path, args: &[&str]) -> Command {
+    let mut cmd = oro_command(args);
+    cmd.arg("--root")
+        .arg(root)
+        .arg("--cache")
+        .arg(root.join("cache"))
+        .arg("--no-telemetry");
+    cmd
+}
+
+/// Runs `oro` with `args` against the project at `root`, with a cache
+/// inside it and telemetry off.
+pub fn oro(root: &Path, args: &[&str]) -> Output {
+    oro_project_command(root, args)
+        .output()
+        .expect("Failed to execute oro")
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
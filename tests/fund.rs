@@ -0,0 +1,108 @@
+use std::path::Path;
+
+mod common;
+
+use common::{oro, stderr};
+
+const DONATE: &str = "https://example.com/donate";
+
+/// A project with `file:` dependencies that declare `funding` in each of
+/// the forms npm accepts, with some URLs shared between them.
+fn setup_project(root: &Path) {
+    for (name, funding) in [
+        ("a", serde_json::json!(DONATE)),
+        ("b", serde_json::json!({"type": "patreon", "url": DONATE})),
+        (
+            "c",
+            serde_json::json!([
+                "https://example.com/c",
+                {"type": "github", "url": DONATE},
+                "https://example.com/c",
+            ]),
+        ),
+        ("d", serde_json::Value::Null),
+    ] {
+        let mut manifest = serde_json::json!({"name": name, "version": "1.0.0"});
+        if !funding.is_null() {
+            manifest["funding"] = funding;
+        }
+        std::fs::create_dir_all(root.join(name)).unwrap();
+        std::fs::write(root.join(name).join("package.json"), manifest.to_string()).unwrap();
+    }
+    std::fs::write(
+        root.join("package.json"),
+        serde_json::json!({
+            "name": "project",
+            "version": "1.0.0",
+            "dependencies": {
+                "a": "file:./a",
+                "b": "file:./b",
+                "c": "file:./c",
+                "d": "file:./d",
+            },
+        })
+        .to_string(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn fund_json_groups_by_url() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = oro(dir.path(), &["fund", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let funding: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        funding,
+        serde_json::json!({
+            "https://example.com/c": ["c@1.0.0"],
+            DONATE: ["a@1.0.0", "b@1.0.0", "c@1.0.0"],
+        })
+    );
+}
+
+#[test]
+fn fund_human_lists_each_url_once() {
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path());
+
+    let output = oro(dir.path(), &["fund"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches(DONATE).count(), 1, "{stdout}");
+    assert_eq!(
+        stdout.matches("https://example.com/c").count(),
+        1,
+        "{stdout}"
+    );
+    for pkg in ["a@1.0.0", "b@1.0.0"] {
+        assert_eq!(stdout.matches(pkg).count(), 1, "{stdout}");
+    }
+    // `c` is listed under both of its URLs, but only once under each.
+    assert_eq!(stdout.matches("c@1.0.0").count(), 2, "{stdout}");
+    assert!(!stdout.contains("d@1.0.0"), "{stdout}");
+}
+
+#[test]
+fn fund_without_funding() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("d")).unwrap();
+    std::fs::write(
+        root.join("d").join("package.json"),
+        r#"{"name": "d", "version": "1.0.0"}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"d": "file:./d"}}"#,
+    )
+    .unwrap();
+
+    let output = oro(root, &["fund"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("No dependencies declare funding information."));
+}
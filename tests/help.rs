@@ -12,6 +12,11 @@ fn apply_markdown() {
     insta::assert_snapshot!("apply", sub_md("apply"));
 }
 
+#[test]
+fn fund_markdown() {
+    insta::assert_snapshot!("fund", sub_md("fund"));
+}
+
 #[test]
 fn login_markdown() {
     insta::assert_snapshot!("login", sub_md("login"));
@@ -37,6 +42,26 @@ fn remove_markdown() {
     insta::assert_snapshot!("remove", sub_md("remove"));
 }
 
+#[test]
+fn schema_markdown() {
+    insta::assert_snapshot!("schema", sub_md("schema"));
+}
+
+#[test]
+fn store_markdown() {
+    insta::assert_snapshot!("store", sub_md("store"));
+}
+
+#[test]
+fn telemetry_markdown() {
+    insta::assert_snapshot!("telemetry", sub_md("telemetry"));
+}
+
+#[test]
+fn update_markdown() {
+    insta::assert_snapshot!("update", sub_md("update"));
+}
+
 #[test]
 fn view_markdown() {
     insta::assert_snapshot!("view", sub_md("view"));
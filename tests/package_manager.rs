@@ -0,0 +1,46 @@
+use std::path::Path;
+use std::process::Output;
+
+mod common;
+
+use common::{oro, stderr};
+
+#[test]
+fn matching_package_manager() {
+    let dir = project(r#"{"name": "project", "packageManager": "oro@>=0.1.0+sha256.abc123"}"#);
+    let output = apply(dir.path(), "error");
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn mismatched_package_manager() {
+    let dir = project(r#"{"name": "project", "packageManager": "oro@<0.1.0"}"#);
+
+    let output = apply(dir.path(), "error");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("oro::package_manager_mismatch"));
+
+    let output = apply(dir.path(), "warn");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("This project wants to be installed with oro@<0.1.0"));
+}
+
+#[test]
+fn other_package_managers_are_ignored() {
+    let dir = project(r#"{"name": "project", "packageManager": "yarn@3.6.0"}"#);
+    let output = apply(dir.path(), "error");
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+fn project(manifest: &str) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("package.json"), manifest).unwrap();
+    dir
+}
+
+fn apply(root: &Path, check: &str) -> Output {
+    oro(
+        root,
+        &["apply", "--lockfile-only", "--package-manager-check", check],
+    )
+}
@@ -0,0 +1,52 @@
+use std::path::Path;
+
+mod common;
+
+use common::{oro, stderr};
+
+#[test]
+fn reapply_restores_bins() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup_project(root);
+
+    let output = oro(root, &["apply"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let bin = root.join("node_modules").join(".bin").join("tool");
+    assert!(bin.exists());
+
+    // The package is already in place the second time around, so nothing
+    // gets extracted, but its bin should still come back.
+    std::fs::remove_dir_all(root.join("node_modules").join(".bin")).unwrap();
+    let output = oro(root, &["apply"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(bin.exists(), "apply should recreate node_modules/.bin");
+
+    std::fs::remove_dir_all(root.join("node_modules").join(".bin")).unwrap();
+    let output = oro(root, &["reapply"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(bin.exists(), "reapply should recreate node_modules/.bin");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&bin).unwrap().permissions().mode();
+        assert!(mode & 0o111 != 0, "bin should be executable: {mode:o}");
+    }
+}
+
+/// Creates a project with a local `tool` dependency that has a bin.
+fn setup_project(root: &Path) {
+    let tool = root.join("tool");
+    std::fs::create_dir_all(&tool).unwrap();
+    std::fs::write(
+        tool.join("package.json"),
+        r#"{"name": "tool", "version": "1.0.0", "bin": {"tool": "cli.js"}}"#,
+    )
+    .unwrap();
+    std::fs::write(tool.join("cli.js"), "#!/usr/bin/env node\n").unwrap();
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"tool": "file:./tool"}}"#,
+    )
+    .unwrap();
+}
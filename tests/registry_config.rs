@@ -0,0 +1,60 @@
+use serde_json::json;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+mod common;
+
+use common::{oro, stderr};
+
+#[async_std::test]
+async fn configured_registry_is_the_default() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("-/ping"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("a"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "a",
+            "dist-tags": { "latest": "1.0.0" },
+            "versions": {
+                "1.0.0": {
+                    "name": "a",
+                    "version": "1.0.0",
+                    "dist": {
+                        "tarball": format!("{}/a/-/a-1.0.0.tgz", mock_server.uri()),
+                        "integrity": "sha512-deadbeef",
+                    }
+                }
+            },
+        })))
+        .expect(2..)
+        .mount(&mock_server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join("oro.kdl"),
+        format!("options {{\n    registry \"{}\"\n}}\n", mock_server.uri()),
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"a": "^1.0.0"}}"#,
+    )
+    .unwrap();
+
+    for args in [&["ping"][..], &["view", "a"], &["apply", "--lockfile-only"]] {
+        let output = oro(root, args);
+        assert!(output.status.success(), "{args:?}: {}", stderr(&output));
+    }
+    assert!(std::fs::read_to_string(root.join("package-lock.kdl"))
+        .unwrap()
+        .contains(&mock_server.uri()));
+}
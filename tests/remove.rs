@@ -0,0 +1,128 @@
+use std::path::Path;
+use std::process::Output;
+
+mod common;
+
+use common::stderr;
+
+#[test]
+fn remove_last_dependency() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    for (name, manifest) in [
+        (
+            "dep",
+            r#"{"name": "dep", "version": "1.0.0", "dependencies": {"sub": "file:./sub"}}"#,
+        ),
+        ("sub", r#"{"name": "sub", "version": "1.0.0"}"#),
+    ] {
+        std::fs::create_dir_all(root.join(name)).unwrap();
+        std::fs::write(root.join(name).join("package.json"), manifest).unwrap();
+    }
+    std::fs::write(
+        root.join("package.json"),
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"dep": "file:./dep"}}"#,
+    )
+    .unwrap();
+
+    let output = oro(root, &["apply"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(root.join("node_modules").join("dep").exists());
+    assert!(root.join("node_modules").join("sub").exists());
+
+    let output = oro(root, &["remove", "dep"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let manifest = read_manifest(root);
+    assert!(
+        manifest.get("dependencies").is_none(),
+        "empty dependencies should be removed: {manifest}"
+    );
+    assert!(!root.join("node_modules").join("dep").exists());
+    assert!(!root.join("node_modules").join("sub").exists());
+    let lock = std::fs::read_to_string(root.join("package-lock.kdl")).unwrap();
+    assert!(
+        !lock.contains(r#"pkg "dep""#) && !lock.contains(r#"pkg "sub""#),
+        "{lock}"
+    );
+}
+
+#[test]
+fn remove_dev_dependency() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup_project(
+        root,
+        r#"{"name": "project", "version": "1.0.0", "devDependencies": {"dep": "file:./dep"}}"#,
+    );
+
+    let output = oro(root, &["remove", "dep"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let manifest = read_manifest(root);
+    assert!(
+        manifest.get("devDependencies").is_none(),
+        "dep should be removed from devDependencies: {manifest}"
+    );
+}
+
+#[test]
+fn remove_from_one_section() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup_project(
+        root,
+        r#"{
+            "name": "project",
+            "version": "1.0.0",
+            "dependencies": {"dep": "file:./dep"},
+            "devDependencies": {"dep": "file:./dep", "other": "file:./dep"}
+        }"#,
+    );
+
+    let output = oro(root, &["remove", "dep", "--save-dev"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let manifest = read_manifest(root);
+    assert_eq!(manifest["dependencies"]["dep"], "file:./dep");
+    assert!(manifest["devDependencies"].get("dep").is_none());
+    assert_eq!(manifest["devDependencies"]["other"], "file:./dep");
+}
+
+#[test]
+fn remove_missing_dependency() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let original =
+        r#"{"name": "project", "version": "1.0.0", "dependencies": {"dep": "file:./dep"}}"#;
+    setup_project(root, original);
+
+    let output = oro(root, &["remove", "dep", "--save-optional"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("dep was not found in optionalDependencies"),
+        "{}",
+        stderr(&output)
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("package.json")).unwrap(),
+        original
+    );
+}
+
+/// Creates a project with the given manifest, plus a local `dep` package for
+/// its dependencies to point at.
+fn setup_project(root: &Path, manifest: &str) {
+    std::fs::create_dir_all(root.join("dep")).unwrap();
+    std::fs::write(
+        root.join("dep").join("package.json"),
+        r#"{"name": "dep", "version": "1.0.0"}"#,
+    )
+    .unwrap();
+    std::fs::write(root.join("package.json"), manifest).unwrap();
+}
+
+fn read_manifest(root: &Path) -> serde_json::Value {
+    serde_json::from_str(&std::fs::read_to_string(root.join("package.json")).unwrap()).unwrap()
+}
+
+fn oro(root: &Path, args: &[&str]) -> Output {
+    common::oro(root, &[args, &["--hoisted"]].concat())
+}
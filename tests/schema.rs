@@ -0,0 +1,40 @@
+use std::process::Output;
+
+mod common;
+
+use common::{oro_command, stderr};
+#[test]
+fn lockfile_schema() {
+    let schema = schema("lockfile");
+    let properties = &schema["properties"];
+    for field in ["lockfile-version", "root", "pkg"] {
+        assert!(properties.get(field).is_some(), "missing {field}");
+    }
+    let pkg = &properties["pkg"]["items"]["properties"];
+    for field in ["path", "version", "resolved", "integrity", "dependencies"] {
+        assert!(pkg.get(field).is_some(), "missing pkg {field}");
+    }
+}
+
+#[test]
+fn config_schema() {
+    let schema = schema("config");
+    let options = &schema["properties"]["options"]["properties"];
+    for field in ["registry", "cache", "telemetry", "locked", "default-tag"] {
+        assert!(options.get(field).is_some(), "missing option {field}");
+    }
+    assert_eq!(options["telemetry"]["type"], "boolean");
+}
+
+fn schema(format: &str) -> serde_json::Value {
+    let output = oro(&["schema", format]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn oro(args: &[&str]) -> Output {
+    oro_command(args)
+        .arg("--no-telemetry")
+        .output()
+        .expect("Failed to execute oro")
+}
@@ -0,0 +1,39 @@
+use std::path::Path;
+use std::process::Output;
+
+mod common;
+
+use common::{oro_command, stderr};
+
+#[test]
+fn toggle_telemetry() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("oro.kdl");
+
+    let output = oro(&config, &["enable"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let contents = std::fs::read_to_string(&config).unwrap();
+    assert!(contents.contains("telemetry true"), "{contents}");
+    assert_eq!(status(&config)["enabled"], true);
+
+    let output = oro(&config, &["disable"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let contents = std::fs::read_to_string(&config).unwrap();
+    assert!(contents.contains("telemetry false"), "{contents}");
+    assert!(!contents.contains("telemetry true"), "{contents}");
+    assert_eq!(status(&config)["enabled"], false);
+}
+
+fn status(config: &Path) -> serde_json::Value {
+    let output = oro(config, &["status", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn oro(config: &Path, args: &[&str]) -> Output {
+    oro_command(&[&["telemetry"], args].concat())
+        .arg("--config")
+        .arg(config)
+        .output()
+        .expect("Failed to execute oro")
+}
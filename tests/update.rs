@@ -0,0 +1,126 @@
+use std::path::Path;
+use std::process::Output;
+
+use serde_json::json;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+mod common;
+
+use common::stderr;
+
+const MANIFEST: &str = r#"{
+  "name": "project",
+  "version": "1.0.0",
+  "dependencies": {
+    "a": "^1.0.0"
+  }
+}
+"#;
+
+#[async_std::test]
+async fn update_within_range() {
+    let mock_server = mock_registry().await;
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path(), &mock_server.uri());
+
+    // Plain apply sticks with what the lockfile says.
+    let output = oro(dir.path(), &mock_server.uri(), "apply", &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(lockfile(dir.path()).contains(r#"version "1.0.0""#));
+
+    let output = oro(dir.path(), &mock_server.uri(), "update", &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let lock = lockfile(dir.path());
+    assert!(lock.contains(r#"version "1.1.0""#), "{lock}");
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("package.json")).unwrap(),
+        MANIFEST
+    );
+}
+
+#[async_std::test]
+async fn update_latest_bumps_range() {
+    let mock_server = mock_registry().await;
+    let dir = tempfile::tempdir().unwrap();
+    setup_project(dir.path(), &mock_server.uri());
+
+    let output = oro(dir.path(), &mock_server.uri(), "update", &["a", "--latest"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let lock = lockfile(dir.path());
+    assert!(lock.contains(r#"version "2.0.0""#), "{lock}");
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("package.json")).unwrap())
+            .unwrap();
+    assert_eq!(manifest["dependencies"]["a"], "^2.0.0");
+}
+
+/// Serves a packument for `a`, with a newer in-range version and a newer
+/// major version than what the project's lockfile has.
+async fn mock_registry() -> MockServer {
+    let mock_server = MockServer::start().await;
+    let versions = ["1.0.0", "1.1.0", "2.0.0"]
+        .iter()
+        .map(|version| {
+            (
+                version.to_string(),
+                json!({
+                    "name": "a",
+                    "version": version,
+                    "dist": {
+                        "tarball": format!("{}/a/-/a-{version}.tgz", mock_server.uri()),
+                        "integrity": "sha512-deadbeef",
+                    }
+                }),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>();
+    Mock::given(method("GET"))
+        .and(path("a"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "a",
+            "dist-tags": { "latest": "2.0.0" },
+            "versions": versions,
+        })))
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+fn setup_project(root: &Path, registry: &str) {
+    std::fs::write(root.join("package.json"), MANIFEST).unwrap();
+    std::fs::write(
+        root.join("package-lock.kdl"),
+        format!(
+            r#"lockfile-version 1
+root {{
+    version "1.0.0"
+    dependencies {{
+        a "^1.0.0"
+    }}
+}}
+pkg "a" {{
+    version "1.0.0"
+    resolved "{registry}/a/-/a-1.0.0.tgz"
+    integrity "sha512-deadbeef"
+}}
+"#
+        ),
+    )
+    .unwrap();
+}
+
+fn lockfile(root: &Path) -> String {
+    std::fs::read_to_string(root.join("package-lock.kdl")).unwrap()
+}
+
+/// Runs an oro command in lockfile-only mode, since the mock registry doesn't
+/// serve any tarballs.
+fn oro(root: &Path, registry: &str, cmd: &str, args: &[&str]) -> Output {
+    common::oro(
+        root,
+        &[&[cmd], args, &["--lockfile-only", "--registry", registry]].concat(),
+    )
+}
@@ -0,0 +1,378 @@
+use std::process::Output;
+
+use serde_json::json;
+use wiremock::{
+    matchers::{header, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+mod common;
+
+use common::stderr;
+
+#[async_std::test]
+async fn view_repo_url() {
+    let mock_server = mock_registry().await;
+
+    let output = oro(&mock_server.uri(), &["view", "a", "--repo"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "https://github.com/user/repo"
+    );
+
+    let output = oro(&mock_server.uri(), &["view", "a", "--homepage"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "https://a.example.com/"
+    );
+}
+
+#[async_std::test]
+async fn view_json_exact_version() {
+    let mock_server = mock_registry().await;
+
+    let output = oro(&mock_server.uri(), &["view", "a@1.2.3", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(metadata["version"], "1.2.3");
+    assert_eq!(metadata["description"], "a at 1.2.3");
+}
+
+#[async_std::test]
+async fn view_json_range() {
+    let mock_server = mock_registry().await;
+
+    let output = oro(&mock_server.uri(), &["view", "a@^1", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(metadata["version"], "1.5.0");
+    assert_eq!(metadata["description"], "a at 1.5.0");
+}
+
+#[async_std::test]
+async fn view_output_formats() {
+    let mock_server = mock_registry().await;
+
+    let output = oro(
+        &mock_server.uri(),
+        &["view", "a@1.2.3", "--output", "parseable"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1, "{stdout}");
+    let tarball = format!("{}/a/-/a-1.2.3.tgz", mock_server.uri());
+    let fields = lines[0].split('\t').collect::<Vec<_>>();
+    assert_eq!(fields, ["a", "1.2.3", "", "0", "4", tarball.as_str()]);
+
+    // `--json` is shorthand for `--output json`.
+    let output = oro(&mock_server.uri(), &["view", "a@1.2.3", "--output", "json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let long: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let output = oro(&mock_server.uri(), &["view", "a@1.2.3", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let short: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(long, short);
+    assert_eq!(short["version"], "1.2.3");
+}
+
+#[async_std::test]
+async fn view_downloads() {
+    let mock_server = mock_registry().await;
+    Mock::given(method("GET"))
+        .and(path("downloads/point/last-week/a"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "downloads": 1234,
+            "start": "2023-01-01",
+            "end": "2023-01-07",
+            "package": "a",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let output = oro(
+        &mock_server.uri(),
+        &["view", "a", "--downloads", "last-week", "--json"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let count: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(count["downloads"], 1234);
+    assert_eq!(count["start"], "2023-01-01");
+    assert_eq!(count["end"], "2023-01-07");
+
+    // No stats for this period, so the registry 404s.
+    let output = oro(
+        &mock_server.uri(),
+        &["view", "a", "--downloads", "last-day"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    assert!(stderr(&output).contains("doesn't provide download counts"));
+}
+
+#[async_std::test]
+async fn view_scoped_registry_with_auth() {
+    let default_registry = MockServer::start().await;
+    let private_registry = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("@private%2fpkg"))
+        .and(header("authorization", "Bearer secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "@private/pkg",
+            "dist-tags": { "latest": "1.0.0" },
+            "versions": {
+                "1.0.0": {
+                    "name": "@private/pkg",
+                    "version": "1.0.0",
+                    "description": "private package",
+                    "dist": {
+                        "tarball": format!("{}/@private/pkg/-/pkg-1.0.0.tgz", private_registry.uri()),
+                        "integrity": "sha512-deadbeef",
+                    }
+                }
+            },
+        })))
+        .mount(&private_registry)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("downloads/point/last-week/@private/pkg"))
+        .and(header("authorization", "Bearer secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "downloads": 42,
+            "start": "2023-01-01",
+            "end": "2023-01-07",
+            "package": "@private/pkg",
+        })))
+        .mount(&private_registry)
+        .await;
+
+    let scoped = format!("@private={}", private_registry.uri());
+    let auth = format!("{{{}}}token=secret", private_registry.uri());
+
+    let output = oro(
+        &default_registry.uri(),
+        &[
+            "view",
+            "@private/pkg",
+            "--json",
+            "--scoped-registry",
+            &scoped,
+            "--auth",
+            &auth,
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(metadata["description"], "private package");
+
+    let output = oro(
+        &default_registry.uri(),
+        &[
+            "view",
+            "@private/pkg",
+            "--downloads",
+            "last-week",
+            "--json",
+            "--scoped-registry",
+            &scoped,
+            "--auth",
+            &auth,
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let count: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(count["downloads"], 42);
+
+    // Without credentials, the private registry won't serve anything.
+    let output = oro(
+        &default_registry.uri(),
+        &[
+            "view",
+            "@private/pkg",
+            "--json",
+            "--scoped-registry",
+            &scoped,
+        ],
+    );
+    assert!(!output.status.success());
+}
+
+#[async_std::test]
+async fn view_deprecated_versions() {
+    let mock_server = MockServer::start().await;
+    let version = |version: &str, deprecated: Option<&str>| {
+        let mut meta = json!({
+            "name": "b",
+            "version": version,
+            "dist": {
+                "tarball": format!("{}/b/-/b-{version}.tgz", mock_server.uri()),
+                "integrity": "sha512-deadbeef",
+            }
+        });
+        if let Some(message) = deprecated {
+            meta["deprecated"] = message.into();
+        }
+        (version.to_string(), meta)
+    };
+    let versions = [
+        version("1.0.0", Some("please upgrade to 2.x")),
+        version("1.1.0", Some("security issue, see CVE-1234")),
+        version("2.0.0", None),
+    ]
+    .into_iter()
+    .collect::<serde_json::Map<_, _>>();
+    Mock::given(method("GET"))
+        .and(path("b"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "b",
+            "dist-tags": { "latest": "2.0.0" },
+            "versions": versions,
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let output = oro(&mock_server.uri(), &["view", "b", "--deprecated"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "1.0.0 please upgrade to 2.x",
+            "1.1.0 security issue, see CVE-1234"
+        ]
+    );
+
+    let output = oro(&mock_server.uri(), &["view", "b", "--deprecated", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let listed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        listed,
+        json!([
+            { "version": "1.0.0", "message": "please upgrade to 2.x" },
+            { "version": "1.1.0", "message": "security issue, see CVE-1234" },
+        ])
+    );
+}
+
+#[async_std::test]
+async fn view_readme() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("b"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "b",
+            "dist-tags": { "latest": "1.0.0" },
+            "versions": {
+                "1.0.0": {
+                    "name": "b",
+                    "version": "1.0.0",
+                    "dist": {
+                        "tarball": format!("{}/b/-/b-1.0.0.tgz", mock_server.uri()),
+                        "integrity": "sha512-deadbeef",
+                    }
+                }
+            },
+            "readme": "# b\n\nDoes b things.\n",
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let output = oro(&mock_server.uri(), &["view", "b", "--readme"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "# b\n\nDoes b things.\n"
+    );
+
+    let output = oro(&mock_server.uri(), &["view", "b", "--readme", "--json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["readme"], "# b\n\nDoes b things.\n");
+
+    let mock_server = mock_registry().await;
+    let output = oro(&mock_server.uri(), &["view", "a", "--readme"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("a does not have a README"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[async_std::test]
+async fn view_raw_packument() {
+    let mock_server = MockServer::start().await;
+    // Deliberately not how serde_json would format them.
+    let full = "{\"name\":\"b\",  \"dist-tags\":{\"latest\":\"1.0.0\"},\"versions\":{}, \"readme\":\"hi\"}\n";
+    let corgi = "{ \"name\": \"b\", \"dist-tags\": {\"latest\": \"1.0.0\"}, \"versions\": {} }";
+    Mock::given(method("GET"))
+        .and(path("b"))
+        .and(header("accept", "application/json"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(full, "application/json"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("b"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(corgi, "application/vnd.npm.install-v1+json"),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let output = oro(
+        &mock_server.uri(),
+        &["view", "b@^1", "--packument", "--json"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(output.stdout, full.as_bytes());
+
+    let output = oro(
+        &mock_server.uri(),
+        &["view", "b", "--packument", "corgi", "--json"],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(output.stdout, corgi.as_bytes());
+}
+
+/// Serves a packument for `a`, whose repository is in shorthand form, with a
+/// few versions to pick from.
+async fn mock_registry() -> MockServer {
+    let mock_server = MockServer::start().await;
+    let versions = ["1.0.0", "1.2.3", "1.5.0", "2.0.0"]
+        .iter()
+        .map(|version| {
+            (
+                version.to_string(),
+                json!({
+                    "name": "a",
+                    "version": version,
+                    "description": format!("a at {version}"),
+                    "homepage": "https://a.example.com/",
+                    "repository": "github:user/repo",
+                    "dist": {
+                        "tarball": format!("{}/a/-/a-{version}.tgz", mock_server.uri()),
+                        "integrity": "sha512-deadbeef",
+                    }
+                }),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>();
+    Mock::given(method("GET"))
+        .and(path("a"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "a",
+            "dist-tags": { "latest": "2.0.0" },
+            "versions": versions,
+        })))
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+fn oro(registry: &str, args: &[&str]) -> Output {
+    let dir = tempfile::tempdir().unwrap();
+    common::oro(dir.path(), &[args, &["--registry", registry]].concat())
+}